@@ -49,6 +49,9 @@ impl Prefix {
     /// `zpub` prefix
     pub const ZPUB: Self = Self::from_parts_unchecked("zpub", 0x04b24746);
 
+    /// `epub` prefix, of Ed25519 public keys in the 77-byte encoding
+    pub const EPUB: Self = Self::from_parts_unchecked("epub", 0x0d8f3c90);
+
     /// Create a new prefix from the given 4-character string and version number.
     /// The main intended use case for this function is [`Prefix`] constants
     /// such as [`Prefix::XPRV`].
@@ -5,7 +5,7 @@ pub mod prelude {
     pub use crate::{Outcome, TraitStdOptionToOutcome, TraitStdResultToOutcome};
 }
 
-use std::{fmt, result::Result as StdResult};
+use std::{any::Any, fmt, result::Result as StdResult, sync::Arc};
 
 pub struct Exception {
     name: String,
@@ -14,6 +14,7 @@ pub struct Exception {
     column: u32,
     context: Option<String>,
     inner: Option<Box<dyn std::string::ToString + Send + Sync>>,
+    payload: Option<Arc<dyn Any + Send + Sync>>,
 }
 
 unsafe impl Send for Exception {}
@@ -31,6 +32,7 @@ impl Exception {
             column: 0,
             context: None,
             inner: None,
+            payload: None,
         })
     }
 
@@ -42,6 +44,7 @@ impl Exception {
             column: 0,
             context: None,
             inner: None,
+            payload: None,
         })
     }
 
@@ -84,6 +87,14 @@ impl Exception {
         self
     }
 
+    /// Attach a typed value for the caller to inspect.
+    /// It is carried over when the exception is wrapped by `catch()` or `catch_()`.
+    #[inline]
+    pub fn set_payload(&mut self, payload: impl Any + Send + Sync) -> &mut Self {
+        self.payload = Some(Arc::new(payload));
+        self
+    }
+
     #[inline]
     pub fn get_name(&self) -> &str {
         &self.name
@@ -96,6 +107,22 @@ impl Exception {
             None => None,
         }
     }
+
+    #[inline]
+    pub fn get_payload<P: Any>(&self) -> Option<&P> {
+        match &self.payload {
+            Some(payload) => payload.downcast_ref::<P>(),
+            None => None,
+        }
+    }
+
+    /// Payload of the exception, if `err` is one.
+    fn payload_of(err: &dyn Any) -> Option<Arc<dyn Any + Send + Sync>> {
+        match err.downcast_ref::<Box<Exception>>() {
+            Some(ex) => ex.payload.clone(),
+            None => None,
+        }
+    }
 }
 
 /// std::string::ToString has a default to_string() implementation
@@ -161,6 +188,7 @@ where
                 let mut ex = Exception::new();
                 let loc = std::panic::Location::caller();
                 let (file, line, column) = (loc.file(), loc.line(), loc.column());
+                ex.payload = Exception::payload_of(&e);
                 ex.set_name(name)
                     .set_file(file)
                     .set_line(line)
@@ -182,6 +210,7 @@ where
                 let mut ex = Exception::new();
                 let loc = std::panic::Location::caller();
                 let (file, line, column) = (loc.file(), loc.line(), loc.column());
+                ex.payload = Exception::payload_of(&e);
                 ex.set_name("")
                     .set_file(file)
                     .set_line(line)
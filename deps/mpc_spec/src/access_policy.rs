@@ -0,0 +1,77 @@
+use std::collections::HashSet;
+
+use libexception::*;
+use serde::{Deserialize, Serialize};
+
+/// Monotone access structure across groups, as a tree of threshold gates.
+/// Each group shall appear once, and signs with its own threshold.
+///
+/// E.g. "group 1 OR (group 2 AND group 3)" is
+/// `or(vec![Group(1), and(vec![Group(2), Group(3)])])`,
+/// and "any 2 of 3 groups" is `Threshold(2, vec![Group(1), Group(2), Group(3)])`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum AccessPolicy {
+    /// The group must reach its threshold.
+    Group(u16),
+    /// At least `k` of the sub-policies must be satisfied.
+    /// The `i`-th sub-policy is at evaluation point `i + 1` of the gate.
+    Threshold(usize, Vec<AccessPolicy>),
+}
+
+impl AccessPolicy {
+    /// All of `subs`.
+    pub fn and(subs: Vec<AccessPolicy>) -> Self {
+        AccessPolicy::Threshold(subs.len(), subs)
+    }
+
+    /// Any of `subs`.
+    pub fn or(subs: Vec<AccessPolicy>) -> Self {
+        AccessPolicy::Threshold(1, subs)
+    }
+
+    /// Groups of the policy, in the order they appear.
+    pub fn groups(&self) -> Vec<u16> {
+        match self {
+            AccessPolicy::Group(gid) => vec![*gid],
+            AccessPolicy::Threshold(_, subs) => subs.iter().flat_map(|sub| sub.groups()).collect(),
+        }
+    }
+
+    pub fn validate(&self) -> Outcome<()> {
+        let groups = self.groups();
+        let unique: HashSet<u16> = groups.iter().cloned().collect();
+        assert_throw!(
+            unique.len() == groups.len(),
+            "InvalidPolicy",
+            "A group appears more than once"
+        );
+        self.validate_gates()
+    }
+
+    fn validate_gates(&self) -> Outcome<()> {
+        if let AccessPolicy::Threshold(k, subs) = self {
+            assert_throw!(
+                1 <= *k && *k <= subs.len(),
+                "InvalidPolicy",
+                format!("Threshold {} of {} sub-policies", k, subs.len())
+            );
+            for sub in subs.iter() {
+                sub.validate_gates().catch_()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether `groups` together satisfy the policy.
+    pub fn is_satisfied_by(&self, groups: &HashSet<u16>) -> bool {
+        match self {
+            AccessPolicy::Group(gid) => groups.contains(gid),
+            AccessPolicy::Threshold(k, subs) => {
+                subs.iter()
+                    .filter(|sub| sub.is_satisfied_by(groups))
+                    .count()
+                    >= *k
+            }
+        }
+    }
+}
@@ -0,0 +1,12 @@
+use serde::{Deserialize, Serialize};
+
+/// Hash and transcript convention of a keystore.
+/// All parties of a session must run in the same mode.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum FrostMode {
+    /// Hashes of the original Komlo-Goldberg PoC, kept for existing keystores.
+    #[default]
+    Legacy,
+    /// FROST(Ed25519, SHA-512) as specified by RFC 9591.
+    Rfc9591,
+}
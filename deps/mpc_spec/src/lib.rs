@@ -7,7 +7,16 @@ pub use shard::*;
 mod tuple_id;
 pub use tuple_id::*;
 
+mod session_id;
+pub use session_id::*;
+
 mod multi_shard;
 pub use multi_shard::*;
 
+mod frost_mode;
+pub use frost_mode::*;
+
+mod access_policy;
+pub use access_policy::*;
+
 pub use async_trait::async_trait;
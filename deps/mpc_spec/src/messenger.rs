@@ -1,13 +1,14 @@
 use std::collections::{HashMap, HashSet};
 use std::fmt::Display;
 
-use super::MpcAddr;
+use super::{MpcAddr, SessionId};
 
 use async_trait::async_trait;
 use serde::{de::DeserializeOwned, Serialize};
 
+/// Implementors are `Sync` anyway, as the futures of `async_trait` hold `&self`.
 #[async_trait]
-pub trait Messenger {
+pub trait Messenger: Sync {
     type E: Display + Send + Sync + 'static;
 
     async fn send<T>(
@@ -42,4 +43,73 @@ pub trait Messenger {
     ) -> Result<HashMap<MpcAddr, T>, Self::E>
     where
         T: Serialize + DeserializeOwned + Send + Sync;
+
+    /// This messenger with every mailbox entry namespaced by `sid`, so that
+    /// concurrent or retried sessions never read each other's messages.
+    fn scoped(&self, sid: SessionId) -> Scoped<'_, Self>
+    where
+        Self: Sized,
+    {
+        Scoped { inner: self, sid }
+    }
+}
+
+/// `Messenger` whose topics are prefixed by a session id. See `Messenger::scoped()`.
+pub struct Scoped<'a, M> {
+    inner: &'a M,
+    sid: SessionId,
+}
+
+impl<M> Scoped<'_, M> {
+    pub fn sid(&self) -> SessionId {
+        self.sid
+    }
+
+    fn topic(&self, topic: &str) -> String {
+        format!("{}/{}", self.sid, topic)
+    }
+}
+
+#[async_trait]
+impl<M: Messenger> Messenger for Scoped<'_, M> {
+    type E = M::E;
+
+    async fn send<T>(&self, topic: &str, src: MpcAddr, dst: MpcAddr, obj: &T) -> Result<(), Self::E>
+    where
+        T: Serialize + DeserializeOwned + Send + Sync,
+    {
+        self.inner.send(&self.topic(topic), src, dst, obj).await
+    }
+
+    async fn receive<T>(&self, topic: &str, src: MpcAddr, dst: MpcAddr) -> Result<T, Self::E>
+    where
+        T: Serialize + DeserializeOwned + Send + Sync,
+    {
+        self.inner.receive(&self.topic(topic), src, dst).await
+    }
+
+    async fn scatter<T>(
+        &self,
+        topic: &str,
+        src: MpcAddr,
+        dsts: &HashSet<MpcAddr>,
+        obj: &T,
+    ) -> Result<(), Self::E>
+    where
+        T: Serialize + DeserializeOwned + Send + Sync,
+    {
+        self.inner.scatter(&self.topic(topic), src, dsts, obj).await
+    }
+
+    async fn gather<T>(
+        &self,
+        topic: &str,
+        srcs: &HashSet<MpcAddr>,
+        dst: MpcAddr,
+    ) -> Result<HashMap<MpcAddr, T>, Self::E>
+    where
+        T: Serialize + DeserializeOwned + Send + Sync,
+    {
+        self.inner.gather(&self.topic(topic), srcs, dst).await
+    }
 }
@@ -6,7 +6,7 @@ use std::{
 use libexception::*;
 use serde::{Deserialize, Serialize};
 
-use crate::MpcAddr;
+use crate::{AccessPolicy, FrostMode, MpcAddr};
 
 #[derive(Clone, Debug, Deserialize, Serialize, Default)]
 pub struct MultiShard<ScalarType, PointType>
@@ -21,12 +21,56 @@ where
         + std::ops::Add<Output = PointType>
         + std::ops::Mul<ScalarType, Output = PointType>,
 {
+    /// Party keys $u_i$ of keystores created before `channel_sk_pergroup`
+    /// existed, which served as their channel keys.
     pub ui_pergroup: HashMap<u16, ScalarType>,
     pub xi_pergroup: HashMap<u16, ScalarType>,
     pub vss_com_grid: HashMap<u16, HashMap<MpcAddr, Vec<PointType>>>, // (group, member(poly), coef)
 
     pub ids: HashSet<MpcAddr>,
     pub aux: Option<Vec<u8>>,
+
+    #[serde(default)] // keystores created before `mode` existed are legacy ones
+    pub mode: FrostMode,
+
+    /// Number of share refreshes so far. Shares of different epochs never combine.
+    #[serde(default)]
+    pub epoch: u64,
+
+    /// Chain code of HD derivation, drawn jointly at keygen. Keystores created
+    /// before it existed have none, and derive it from the public key.
+    #[serde(default)]
+    pub chain_code: Option<[u8; 32]>,
+
+    /// Access structure across groups. Without one, every group must sign.
+    #[serde(default)]
+    pub policy: Option<AccessPolicy>,
+    /// Public key of a keystore with a policy, where it is no longer the sum
+    /// of the constant terms of `vss_com_grid`.
+    #[serde(default)]
+    pub policy_pk: Option<PointType>,
+
+    /// Weight of each member which has more than one sub-share. A member of
+    /// weight w counts as w signers towards the threshold of its group.
+    #[serde(default)]
+    pub weights: HashMap<MpcAddr, u16>,
+    /// My sub-shares beyond the first one, i.e. the one in `xi_pergroup`.
+    #[serde(default)]
+    pub sub_xi_pergroup: HashMap<u16, Vec<ScalarType>>,
+
+    /// Secret key of my channels to the other members of each group, i.e. of
+    /// my entry of `channel_pk_grid`. See `channel_sk()` for older keystores.
+    #[serde(default)]
+    pub channel_sk_pergroup: HashMap<u16, ScalarType>,
+    /// Channel public key of every member of each group, which the shares of
+    /// a refresh, repair, reshare or policy change are encrypted to.
+    /// See `channel_pk()` for older keystores.
+    #[serde(default)]
+    pub channel_pk_grid: HashMap<u16, HashMap<MpcAddr, PointType>>,
+    /// Dealers disqualified at keygen, whose polynomials were left out of
+    /// `vss_com_grid`. They remain members, with a share and a channel key.
+    #[serde(default)]
+    pub disqualified: HashSet<MpcAddr>,
 }
 
 impl<ScalarType, PointType> MultiShard<ScalarType, PointType>
@@ -55,7 +99,52 @@ where
         Ok(vss_com.len())
     }
 
+    /// Number of sub-shares of `id`.
+    pub fn weight(&self, id: &MpcAddr) -> u16 {
+        self.weights.get(id).cloned().unwrap_or(1)
+    }
+
+    /// Secret key of my channels in group `gid`. Keystores created before
+    /// channel keys were kept apart use $u_i$ instead.
+    pub fn channel_sk(&self, gid: u16) -> Option<&ScalarType> {
+        match self.channel_sk_pergroup.get(&gid) {
+            Some(channel_sk) => Some(channel_sk),
+            None => self.ui_pergroup.get(&gid),
+        }
+    }
+
+    /// Channel public key of `id`. Keystores created before channel keys were
+    /// kept apart use the constant term of its commitment, i.e. $u_j G$.
+    pub fn channel_pk(&self, id: &MpcAddr) -> Option<PointType> {
+        let gid = id.group_id();
+        match self.channel_pk_grid.get(&gid) {
+            Some(channel_pk_dict) => channel_pk_dict.get(id).cloned(),
+            None => {
+                let vss_com = self.vss_com_grid.get(&gid)?.get(id)?;
+                vss_com.first().cloned()
+            }
+        }
+    }
+
+    /// Members of group `gid`, i.e. those with a channel key.
+    pub fn members(&self, gid: u16) -> HashSet<MpcAddr> {
+        match self.channel_pk_grid.get(&gid) {
+            Some(channel_pk_dict) => channel_pk_dict.keys().cloned().collect(),
+            None => match self.vss_com_grid.get(&gid) {
+                Some(vss_com_dict) => vss_com_dict
+                    .keys()
+                    .filter(|id| id.member_id() != 0)
+                    .cloned()
+                    .collect(),
+                None => HashSet::new(),
+            },
+        }
+    }
+
     pub fn pk(&self) -> Result<PointType, &'static str> {
+        if let Some(pk) = self.policy_pk.as_ref() {
+            return Ok(pk.clone());
+        }
         let mut res = PointType::default();
         for vss_com_dict in self.vss_com_grid.values() {
             for vss_com in vss_com_dict.values() {
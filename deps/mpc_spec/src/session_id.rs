@@ -0,0 +1,57 @@
+use std::fmt::Display;
+
+use rand_core::{OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+
+#[rustfmt::skip]
+#[derive(
+    Clone, Copy, Default,
+    Deserialize, Serialize,
+    PartialEq, Eq, Hash,
+    PartialOrd, Ord,
+    Debug,
+)]
+/// ID of a keygen or sign session, generated by whoever starts the session
+/// and handed to every party together with the key or session arch.
+/// Each session must have its own, including a retry after a failure.
+pub struct SessionId([u8; 16]);
+
+impl SessionId {
+    pub fn new(bytes: [u8; 16]) -> Self {
+        SessionId(bytes)
+    }
+
+    /// A fresh random session id.
+    pub fn random() -> Self {
+        let mut bytes = [0u8; 16];
+        OsRng.fill_bytes(&mut bytes);
+        SessionId(bytes)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 16] {
+        &self.0
+    }
+
+    /// Parse the 32 hex digits printed by `Display`.
+    pub fn from_text(s: impl AsRef<str>) -> Result<Self, &'static str> {
+        let s = s.as_ref();
+        if s.len() != 32 || !s.is_ascii() {
+            return Err("SessionId is not 32 hex digits");
+        }
+        let mut bytes = [0u8; 16];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&s[2 * i..2 * i + 2], 16)
+                .map_err(|_| "SessionId is not 32 hex digits")?;
+        }
+        Ok(SessionId(bytes))
+    }
+}
+
+impl Display for SessionId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for byte in self.0.iter() {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
@@ -1,16 +1,43 @@
 use libexception::*;
+use mpc_spec::MpcAddr;
+
+/// Version of the AEAD wire format, i.e. of the key derivation and the AAD.
+pub const AEAD_VERSION: u8 = 1;
 
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct AEAD {
+    pub version: u8,
     pub ciphertext: Vec<u8>,
     pub tag: Vec<u8>,
 }
 
-pub fn aes_encrypt(key: &[u8], plaintext: &[u8]) -> Outcome<AEAD> {
-    let mut full_length_key: [u8; 32] = [0; 32];
-    full_length_key[(32 - key.len())..].copy_from_slice(key); // pad key with zeros
+/// AES-256 key of the channel whose ECDH secret encodes as `shared`, i.e.
+/// HKDF-SHA256 of it, so that the key is uniform whatever the curve.
+pub fn channel_key(shared: &[u8]) -> [u8; 32] {
+    let hk = Hkdf::<Sha256>::new(Some(b"mpc_eddsa channel salt"), shared);
+    let mut key = [0u8; 32];
+    hk.expand(b"mpc_eddsa aes-256-gcm key", &mut key)
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+    key
+}
 
-    let aes_key = aes_gcm::Key::from_slice(full_length_key.as_slice());
+/// Associated data of a pack from `src` to `dst` under `topic`, so that it
+/// cannot be replayed to another party, protocol step or session.
+/// `context` is the one keygen runs under, or the session id for other
+/// protocols.
+pub fn channel_aad(src: MpcAddr, dst: MpcAddr, topic: &str, context: &str) -> Vec<u8> {
+    let mut aad = b"mpc_eddsa aead".to_vec();
+    aad.extend(src.to_be_bytes());
+    aad.extend(dst.to_be_bytes());
+    for s in [topic, context] {
+        aad.extend((s.len() as u64).to_be_bytes());
+        aad.extend_from_slice(s.as_bytes());
+    }
+    aad
+}
+
+pub fn aes_encrypt(key: &[u8; 32], plaintext: &[u8], aad: &[u8]) -> Outcome<AEAD> {
+    let aes_key = aes_gcm::Key::from_slice(key.as_slice());
     let cipher = Aes256Gcm::new(aes_key);
 
     let mut _buf = [0u8; 12];
@@ -19,8 +46,8 @@ pub fn aes_encrypt(key: &[u8], plaintext: &[u8]) -> Outcome<AEAD> {
         Nonce::from_slice(&_buf.as_slice())
     };
 
-    // reserve for later changes when a non-empty aad could be imported
-    let aad: Vec<u8> = std::iter::repeat(0).take(16).collect();
+    // The version is authenticated together with the caller's AAD.
+    let aad = [&[AEAD_VERSION], aad].concat();
     let payload = Payload {
         msg: plaintext,
         aad: &aad.as_slice(),
@@ -31,21 +58,29 @@ pub fn aes_encrypt(key: &[u8], plaintext: &[u8]) -> Outcome<AEAD> {
         .catch("AesGcmException", "")?;
 
     Ok(AEAD {
+        version: AEAD_VERSION,
         ciphertext: ciphertext,
         tag: nonce.to_vec(),
     })
 }
 
-pub fn aes_decrypt(key: &[u8], aead_pack: &AEAD) -> Outcome<Vec<u8>> {
-    let mut full_length_key: [u8; 32] = [0; 32];
-    full_length_key[(32 - key.len())..].copy_from_slice(key); // Pad key with zeros
+pub fn aes_decrypt(key: &[u8; 32], aead_pack: &AEAD, aad: &[u8]) -> Outcome<Vec<u8>> {
+    assert_throw!(
+        aead_pack.version == AEAD_VERSION,
+        "UnsupportedVersion",
+        format!("AEAD version {}", aead_pack.version)
+    );
+    assert_throw!(
+        aead_pack.tag.len() == 12,
+        "AesGcmException",
+        "Invalid nonce"
+    );
 
-    let aes_key = aes_gcm::Key::from_slice(full_length_key.as_slice());
+    let aes_key = aes_gcm::Key::from_slice(key.as_slice());
     let nonce = Nonce::from_slice(&aead_pack.tag);
     let gcm = Aes256Gcm::new(aes_key);
 
-    // reserve for later changes when a non-empty aad could be imported
-    let aad: Vec<u8> = std::iter::repeat(0).take(16).collect();
+    let aad = [&[aead_pack.version], aad].concat();
     let payload = Payload {
         msg: aead_pack.ciphertext.as_slice(),
         aad: aad.as_slice(),
@@ -54,7 +89,7 @@ pub fn aes_decrypt(key: &[u8], aead_pack: &AEAD) -> Outcome<Vec<u8>> {
     // NOTE: no error reported but return a value NONE when decrypt key is wrong
     let out = gcm
         .decrypt(nonce, payload)
-        .catch("AesGcmException", "Wrong password or nonce.")?;
+        .catch("AesGcmException", "Wrong key, nonce or AAD.")?;
     Ok(out)
 }
 
@@ -62,5 +97,38 @@ use aes_gcm::{
     aead::{Aead, NewAead, Payload},
     Aes256Gcm, Nonce,
 };
+use hkdf::Hkdf;
 use rand_core::{OsRng, RngCore};
 use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aead_binds_version_and_aad() {
+        let key = channel_key(b"shared secret");
+        let (i, j) = (MpcAddr::new(1, 1), MpcAddr::new(1, 2));
+        let aad = channel_aad(i, j, "aead_share", "ctx");
+        let pack = aes_encrypt(&key, b"share", &aad).unwrap();
+        assert_eq!(aes_decrypt(&key, &pack, &aad).unwrap(), b"share");
+
+        for other in [
+            channel_aad(j, i, "aead_share", "ctx"),
+            channel_aad(i, j, "aead_share2", "ctx"),
+            channel_aad(i, j, "aead_share", "ctx2"),
+            channel_aad(i, j, "aead_sharectx", ""),
+        ] {
+            assert!(aes_decrypt(&key, &pack, &other).is_err());
+        }
+        assert!(aes_decrypt(&channel_key(b"other secret"), &pack, &aad).is_err());
+
+        let mut pack = pack;
+        pack.version += 1;
+        assert!(aes_decrypt(&key, &pack, &aad).is_err());
+        pack.version -= 1;
+        pack.tag.pop();
+        assert!(aes_decrypt(&key, &pack, &aad).is_err());
+    }
+}
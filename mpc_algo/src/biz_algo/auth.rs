@@ -0,0 +1,319 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use ed25519_dalek::{Keypair, PublicKey, Signature, Signer};
+use libexception::*;
+use mpc_spec::*;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+/// Long-term identity keys of the parties, by every shard id they hold.
+pub type Roster = HashMap<MpcAddr, PublicKey>;
+
+/// A message as `AuthMessenger` puts it on the wire: the JSON payload with
+/// where it is bound, signed by the identity key of `src`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Envelope {
+    pub session: SessionId,
+    pub topic: String,
+    pub src: MpcAddr,
+    pub dst: MpcAddr,
+    pub payload: Vec<u8>,
+    pub sig: Vec<u8>,
+}
+
+impl Envelope {
+    /// Every field but the signature, each length-prefixed.
+    pub fn signed_bytes(&self) -> Vec<u8> {
+        let mut m = b"mpc_eddsa envelope".to_vec();
+        for field in [&self.session.as_bytes()[..], self.topic.as_bytes()] {
+            m.extend((field.len() as u64).to_be_bytes());
+            m.extend_from_slice(field);
+        }
+        m.extend(self.src.to_be_bytes());
+        m.extend(self.dst.to_be_bytes());
+        m.extend((self.payload.len() as u64).to_be_bytes());
+        m.extend_from_slice(&self.payload);
+        m
+    }
+}
+
+/// `Messenger` adapter over any transport, which signs every message with
+/// the identity key of this party, and accepts a message only if the key
+/// that `roster` assigns to its `src` signed it for this session, topic and
+/// `dst`. Messages are checked before their payload is deserialized.
+///
+/// One party may hold several shard ids, all mapped to its identity key.
+/// `session` is usually the `sid` of the protocol run over the messenger,
+/// which scopes its topics as well; see `Messenger::scoped()`.
+pub struct AuthMessenger<M> {
+    inner: M,
+    session: SessionId,
+    identity: Arc<Keypair>,
+    roster: Arc<Roster>,
+}
+
+impl<M: Clone> Clone for AuthMessenger<M> {
+    fn clone(&self) -> Self {
+        AuthMessenger {
+            inner: self.inner.clone(),
+            session: self.session,
+            identity: self.identity.clone(),
+            roster: self.roster.clone(),
+        }
+    }
+}
+
+impl<M: Messenger + Send + Sync> AuthMessenger<M> {
+    pub fn new(inner: M, session: SessionId, identity: Keypair, roster: Roster) -> Self {
+        AuthMessenger {
+            inner,
+            session,
+            identity: Arc::new(identity),
+            roster: Arc::new(roster),
+        }
+    }
+
+    pub fn identity(&self) -> PublicKey {
+        self.identity.public
+    }
+
+    fn seal<T: Serialize>(
+        &self,
+        topic: &str,
+        src: MpcAddr,
+        dst: MpcAddr,
+        obj: &T,
+    ) -> Outcome<Envelope> {
+        let src_key = self
+            .roster
+            .get(&src)
+            .ifnone("NotInRoster", src.to_string())?;
+        assert_throw!(
+            *src_key == self.identity.public,
+            "NotMyAddr",
+            format!("{} belongs to another identity", src)
+        );
+        let mut envelope = Envelope {
+            session: self.session,
+            topic: topic.to_string(),
+            src,
+            dst,
+            payload: serde_json::to_vec(obj).catch_()?,
+            sig: Vec::new(),
+        };
+        envelope.sig = self
+            .identity
+            .sign(&envelope.signed_bytes())
+            .to_bytes()
+            .to_vec();
+        Ok(envelope)
+    }
+
+    fn open<T: DeserializeOwned>(
+        &self,
+        envelope: &Envelope,
+        topic: &str,
+        src: MpcAddr,
+        dst: MpcAddr,
+    ) -> Outcome<T> {
+        let ctx = format!("{} from {} to {}", topic, src, dst);
+        assert_throw!(
+            envelope.session == self.session
+                && envelope.topic == topic
+                && envelope.src == src
+                && envelope.dst == dst,
+            "UnauthenticatedMessage",
+            format!("{} is signed for another session or route", ctx)
+        );
+        let src_key = self
+            .roster
+            .get(&src)
+            .ifnone("NotInRoster", src.to_string())?;
+        let sig = Signature::from_bytes(&envelope.sig).catch("UnauthenticatedMessage", &ctx)?;
+        src_key
+            .verify_strict(&envelope.signed_bytes(), &sig)
+            .catch("UnauthenticatedMessage", &ctx)?;
+        let obj = serde_json::from_slice(&envelope.payload).catch("InvalidPayload", &ctx)?;
+        Ok(obj)
+    }
+}
+
+#[async_trait]
+impl<M: Messenger + Send + Sync> Messenger for AuthMessenger<M> {
+    type E = Box<Exception>;
+
+    async fn send<T>(&self, topic: &str, src: MpcAddr, dst: MpcAddr, obj: &T) -> Outcome<()>
+    where
+        T: Serialize + DeserializeOwned + Send + Sync,
+    {
+        let envelope = self.seal(topic, src, dst, obj).catch_()?;
+        self.inner.send(topic, src, dst, &envelope).await.catch_()?;
+        Ok(())
+    }
+
+    async fn receive<T>(&self, topic: &str, src: MpcAddr, dst: MpcAddr) -> Outcome<T>
+    where
+        T: Serialize + DeserializeOwned + Send + Sync,
+    {
+        let envelope: Envelope = self.inner.receive(topic, src, dst).await.catch_()?;
+        self.open(&envelope, topic, src, dst)
+    }
+
+    /// Every destination gets its own envelope, since the signature covers `dst`.
+    async fn scatter<T>(
+        &self,
+        topic: &str,
+        src: MpcAddr,
+        dsts: &HashSet<MpcAddr>,
+        obj: &T,
+    ) -> Outcome<()>
+    where
+        T: Serialize + DeserializeOwned + Send + Sync,
+    {
+        for dst in dsts.iter() {
+            self.send(topic, src, *dst, obj).await.catch_()?;
+        }
+        Ok(())
+    }
+
+    async fn gather<T>(
+        &self,
+        topic: &str,
+        srcs: &HashSet<MpcAddr>,
+        dst: MpcAddr,
+    ) -> Outcome<HashMap<MpcAddr, T>>
+    where
+        T: Serialize + DeserializeOwned + Send + Sync,
+    {
+        let envelopes: HashMap<MpcAddr, Envelope> =
+            self.inner.gather(topic, srcs, dst).await.catch_()?;
+        let mut ret: HashMap<MpcAddr, T> = HashMap::new();
+        for (src, envelope) in envelopes.iter() {
+            ret.insert(*src, self.open(envelope, topic, *src, dst).catch_()?);
+        }
+        Ok(ret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::OsRng;
+
+    use super::*;
+    use crate::biz_algo::testkit::MemMessenger;
+
+    fn alice() -> MpcAddr {
+        MpcAddr::new(1, 1)
+    }
+
+    fn bob() -> MpcAddr {
+        MpcAddr::new(1, 2)
+    }
+
+    /// Alice and Bob, each knowing both identity keys.
+    fn pair(session: SessionId) -> (AuthMessenger<MemMessenger>, AuthMessenger<MemMessenger>) {
+        let (key_a, key_b) = (Keypair::generate(&mut OsRng), Keypair::generate(&mut OsRng));
+        let roster: Roster = [(alice(), key_a.public), (bob(), key_b.public)]
+            .into_iter()
+            .collect();
+        let inner = MemMessenger::default();
+        (
+            AuthMessenger::new(inner.clone(), session, key_a, roster.clone()),
+            AuthMessenger::new(inner, session, key_b, roster),
+        )
+    }
+
+    fn rejected<T: std::fmt::Debug>(result: Outcome<T>, name: &str) {
+        match result {
+            Ok(obj) => panic!("Accepted {:?}", obj),
+            Err(ex) => assert_eq!(ex.get_name(), name),
+        }
+    }
+
+    #[test]
+    fn open_accepts_what_was_sealed() {
+        let (a, b) = pair(SessionId::random());
+        let envelope = a.seal("topic", alice(), bob(), &42u32).unwrap();
+        let obj: u32 = b.open(&envelope, "topic", alice(), bob()).unwrap();
+        assert_eq!(obj, 42);
+    }
+
+    #[test]
+    fn open_rejects_a_tampered_payload() {
+        let (a, b) = pair(SessionId::random());
+        let mut envelope = a.seal("topic", alice(), bob(), &42u32).unwrap();
+        envelope.payload = serde_json::to_vec(&43u32).unwrap();
+        rejected(
+            b.open::<u32>(&envelope, "topic", alice(), bob()),
+            "UnauthenticatedMessage",
+        );
+    }
+
+    #[test]
+    fn open_rejects_another_topic_dst_or_session() {
+        let (a, b) = pair(SessionId::random());
+        let envelope = a.seal("topic", alice(), bob(), &42u32).unwrap();
+        rejected(
+            b.open::<u32>(&envelope, "other", alice(), bob()),
+            "UnauthenticatedMessage",
+        );
+        rejected(
+            b.open::<u32>(&envelope, "topic", alice(), MpcAddr::bcast_id()),
+            "UnauthenticatedMessage",
+        );
+
+        // Rewriting the fields breaks the signature instead.
+        let mut rerouted = envelope.clone();
+        rerouted.dst = MpcAddr::bcast_id();
+        rejected(
+            b.open::<u32>(&rerouted, "topic", alice(), MpcAddr::bcast_id()),
+            "UnauthenticatedMessage",
+        );
+
+        let s2 = SessionId::random();
+        let (_, b2) = pair(s2);
+        rejected(
+            b2.open::<u32>(&envelope, "topic", alice(), bob()),
+            "UnauthenticatedMessage",
+        );
+        let mut replayed = envelope;
+        replayed.session = s2;
+        rejected(
+            b.open::<u32>(&replayed, "topic", alice(), bob()),
+            "UnauthenticatedMessage",
+        );
+    }
+
+    #[test]
+    fn src_must_be_in_the_roster() {
+        let (a, b) = pair(SessionId::random());
+        let stranger = MpcAddr::new(2, 1);
+        rejected(a.seal("topic", stranger, bob(), &42u32), "NotInRoster");
+
+        let mut envelope = a.seal("topic", alice(), bob(), &42u32).unwrap();
+        envelope.src = stranger;
+        rejected(
+            b.open::<u32>(&envelope, "topic", stranger, bob()),
+            "NotInRoster",
+        );
+    }
+
+    #[test]
+    fn src_must_be_mapped_to_the_signer() {
+        let (a, b) = pair(SessionId::random());
+        rejected(a.seal("topic", bob(), alice(), &42u32), "NotMyAddr");
+
+        // Alice signs as Bob, which a roster of her own would allow.
+        let mut envelope = a.seal("topic", alice(), alice(), &42u32).unwrap();
+        envelope.src = bob();
+        envelope.sig = a
+            .identity
+            .sign(&envelope.signed_bytes())
+            .to_bytes()
+            .to_vec();
+        rejected(
+            b.open::<u32>(&envelope, "topic", bob(), alice()),
+            "UnauthenticatedMessage",
+        );
+    }
+}
@@ -0,0 +1,401 @@
+use std::collections::{HashMap, HashSet};
+
+use curve25519_dalek::{
+    constants,
+    edwards::{CompressedEdwardsY, EdwardsPoint},
+    scalar::Scalar,
+};
+use libexception::*;
+use mpc_spec::*;
+use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha512};
+use zeroize::Zeroize;
+
+use super::aes::{aes_decrypt, aes_encrypt, channel_aad, channel_key, AEAD};
+use super::{
+    assert_unweighted, chain_code_of, group_coefs, DerivationScheme, HdScheme, KeyStore,
+    KeyStorePublic, SignMode, Signature,
+};
+use crate::frost::{eval_xi_com, lagrange_lambda, Ed25519};
+
+/// What every participant of an export consents to. Each one constructs it on
+/// its own, and no share leaves a participant unless all of them, and the
+/// recipient, hold the very same approval.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ExportApproval {
+    /// Who learns the key. It must not be a shard id of the session.
+    pub recipient: MpcAddr,
+    /// `MultiShard::pk()` of the key to export.
+    pub pk: EdwardsPoint,
+    /// Non-hardened child to export, or "" for the key itself.
+    pub drv_path: String,
+    /// How the child of `drv_path` is derived.
+    #[serde(default)]
+    pub hd_scheme: HdScheme,
+}
+
+/// A plain Ed25519 key, reconstructed from the shares.
+///
+/// The key is a scalar rather than an RFC 8032 seed, since no seed ever
+/// existed. No Solana keypair file can be produced from it: those hold the
+/// seed, from which `solana-keygen` and wallets derive the scalar. The key is
+/// only available as an expanded secret key, which signs as any Ed25519 key
+/// does and is accepted by `ed25519_dalek::ExpandedSecretKey`.
+pub struct ExportedKey {
+    pub secret: Scalar,
+    pub pk: EdwardsPoint,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ExpandedKeyFile {
+    pubkey: String,
+    expanded_secret_key: Vec<u8>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct ExportShare {
+    g_f: EdwardsPoint, // ephemeral key of the sender
+    aead_pack: AEAD,
+}
+
+impl ExportedKey {
+    /// The secret scalar followed by the nonce prefix, which is derived from
+    /// the scalar since the seed it would come from does not exist.
+    pub fn expanded_secret_key(&self) -> [u8; 64] {
+        let mut hasher = Sha512::new();
+        hasher.update(b"mpc_eddsa export nonce prefix");
+        hasher.update(self.secret.as_bytes());
+        let mut h: [u8; 64] = hasher.finalize().into();
+        let mut esk = [0u8; 64];
+        esk[..32].copy_from_slice(self.secret.as_bytes());
+        esk[32..].copy_from_slice(&h[32..]);
+        h.zeroize();
+        esk
+    }
+
+    /// Solana address of the key, i.e. base58 of the compressed public key.
+    pub fn address(&self) -> String {
+        bs58::encode(self.pk.compress().as_bytes()).into_string()
+    }
+
+    /// JSON of the address and the expanded secret key in a byte array. It is
+    /// not a Solana keypair file, and Solana tools do not read it.
+    pub fn to_expanded_key_json(&self) -> Outcome<String> {
+        let mut file = ExpandedKeyFile {
+            pubkey: self.address(),
+            expanded_secret_key: self.expanded_secret_key().to_vec(),
+        };
+        let json = serde_json::to_string_pretty(&file).catch_()?;
+        file.expanded_secret_key.zeroize();
+        Ok(json)
+    }
+
+    /// Sign `msg` as pure Ed25519, which Solana accepts.
+    pub fn sign(&self, msg: &[u8]) -> Outcome<Signature> {
+        let mut esk_bytes = self.expanded_secret_key();
+        let esk = ed25519_dalek::ExpandedSecretKey::from_bytes(&esk_bytes).catch_()?;
+        esk_bytes.zeroize();
+        let pk = ed25519_dalek::PublicKey::from_bytes(self.pk.compress().as_bytes()).catch_()?;
+        let sig = esk.sign(msg, &pk).to_bytes();
+
+        let mut r_bytes = [0u8; 32];
+        let mut s_bytes = [0u8; 32];
+        r_bytes.copy_from_slice(&sig[..32]);
+        s_bytes.copy_from_slice(&sig[32..]);
+        let sig = Signature {
+            r: CompressedEdwardsY(r_bytes).decompress().ifnone_()?,
+            s: Scalar::from_canonical_bytes(s_bytes).ifnone_()?,
+            msg: msg.to_vec(),
+            mode: SignMode::Ed25519,
+        };
+        Ok(sig)
+    }
+}
+
+impl Drop for ExportedKey {
+    fn drop(&mut self) {
+        self.secret.zeroize();
+    }
+}
+
+/// Send my shares to the recipient of an export, once every participant in
+/// `ses_arch` and the recipient have approved it.
+pub async fn algo_export_send(
+    messenger: &impl Messenger,
+    sid: SessionId,
+    ses_arch: &HashMap<u16 /*group_id*/, HashSet<MpcAddr>>,
+    approval: &ExportApproval,
+    keystore: &KeyStore,
+) -> Outcome<()> {
+    let messenger = &messenger.scoped(sid);
+    assert_unweighted(keystore).catch_()?;
+    let recipient = approval.recipient;
+    let mut whoami_asc: Vec<MpcAddr> = keystore.ids.iter().cloned().collect();
+    whoami_asc.sort();
+    for my_id in whoami_asc.iter() {
+        let members = ses_arch.get(&my_id.group_id()).ifnone_()?;
+        assert_throw!(members.contains(my_id), format!("{} not in session", my_id));
+    }
+    assert_throw!(
+        approval.pk == keystore.pk().catch_()?,
+        "ExportNotApproved",
+        "The approval is for another key"
+    );
+
+    for my_id in whoami_asc.iter() {
+        messenger
+            .send("export_approval", *my_id, MpcAddr::bcast_id(), approval)
+            .await
+            .catch_()?;
+    }
+    check_approvals(messenger, ses_arch, approval)
+        .await
+        .catch_()?;
+
+    let g_e: EdwardsPoint = messenger
+        .receive("export_eph", recipient, MpcAddr::bcast_id())
+        .await
+        .catch_()?;
+    let mut rng = OsRng;
+    for my_id in whoami_asc.iter() {
+        let x_i = keystore.xi_pergroup.get(&my_id.group_id()).ifnone_()?;
+        let mut f = Scalar::random(&mut rng);
+        let aes_key = channel_key(&(g_e * f).compress().to_bytes());
+        let aad = channel_aad(*my_id, recipient, "export_share", &sid.to_string());
+        let share = ExportShare {
+            g_f: &constants::ED25519_BASEPOINT_TABLE * &f,
+            aead_pack: aes_encrypt(&aes_key, &x_i.to_bytes(), &aad).catch_()?,
+        };
+        f.zeroize();
+        messenger
+            .send(
+                "export_pub",
+                *my_id,
+                recipient,
+                &KeyStorePublic::of(keystore),
+            )
+            .await
+            .catch_()?;
+        messenger
+            .send("export_share", *my_id, recipient, &share)
+            .await
+            .catch_()?;
+    }
+
+    Ok(())
+}
+
+/// Reconstruct the exported key from the shares of `ses_arch`, which must hold
+/// at least `th` members of every group. The key is checked against
+/// `approval.pk`, or against its child when `approval.drv_path` is not empty.
+pub async fn algo_export_receive(
+    messenger: &impl Messenger,
+    sid: SessionId,
+    ses_arch: &HashMap<u16 /*group_id*/, HashSet<MpcAddr>>,
+    approval: &ExportApproval,
+) -> Outcome<ExportedKey> {
+    let messenger = &messenger.scoped(sid);
+    let recipient = approval.recipient;
+    let mut rng = OsRng;
+    let mut e = Scalar::random(&mut rng);
+    let g_e = &constants::ED25519_BASEPOINT_TABLE * &e;
+    messenger
+        .send("export_approval", recipient, MpcAddr::bcast_id(), approval)
+        .await
+        .catch_()?;
+    check_approvals(messenger, ses_arch, approval)
+        .await
+        .catch_()?;
+    messenger
+        .send("export_eph", recipient, MpcAddr::bcast_id(), &g_e)
+        .await
+        .catch_()?;
+
+    let signers: HashSet<MpcAddr> = ses_arch.values().flatten().cloned().collect();
+    let public = KeyStorePublic::gather(messenger, "export_pub", &signers, recipient)
+        .await
+        .catch_()?;
+    let pubstore = KeyStore::<Ed25519> {
+        vss_com_grid: public.vss_com_grid,
+        chain_code: public.chain_code,
+        policy: public.policy,
+        policy_pk: public.policy_pk,
+        ..Default::default()
+    };
+    let main_pk = pubstore.pk().catch_()?;
+    assert_throw!(main_pk == approval.pk, "Wrong key");
+    let coef_dict =
+        group_coefs::<Ed25519>(&pubstore, &ses_arch.keys().cloned().collect()).catch_()?;
+    for (gid, coef) in coef_dict.iter() {
+        if *coef == Scalar::zero() {
+            continue;
+        }
+        let th = pubstore.th(*gid).catch_()?;
+        let n = ses_arch.get(gid).map_or(0, |members| members.len());
+        assert_throw!(
+            n >= th,
+            "NotEnoughSigners",
+            format!("Only {} of group {}, {} needed", n, gid, th)
+        );
+    }
+
+    let share_dict: HashMap<MpcAddr, ExportShare> = messenger
+        .gather("export_share", &signers, recipient)
+        .await
+        .catch_()?;
+    let mut secret = Scalar::zero();
+    for (gid, members) in ses_arch.iter() {
+        let vss_com_dict = pubstore.vss_com_grid.get(gid).ifnone_()?;
+        let coef = coef_dict.get(gid).ifnone_()?;
+        for j in members.iter() {
+            let share = share_dict.get(j).ifnone_()?;
+            let aes_key = channel_key(&(share.g_f * e).compress().to_bytes());
+            let aad = channel_aad(*j, recipient, "export_share", &sid.to_string());
+            let mut out = aes_decrypt(&aes_key, &share.aead_pack, &aad).catch_()?;
+            assert_throw!(out.len() == 32, "Invalid plaintext length");
+            let mut out_arr = [0u8; 32];
+            out_arr.copy_from_slice(&out);
+            let x_j = Scalar::from_canonical_bytes(out_arr);
+            out.zeroize();
+            out_arr.zeroize();
+            let mut x_j = x_j.ifnone("NonCanonicalScalar", j.to_string())?;
+            assert_throw!(
+                eval_xi_com(*j, vss_com_dict) == &constants::ED25519_BASEPOINT_TABLE * &x_j,
+                "InvalidShare",
+                format!("The share of {} does not match the commitments", j)
+            );
+            secret += coef * lagrange_lambda(*j, members).catch_()? * x_j;
+            x_j.zeroize();
+        }
+    }
+    e.zeroize();
+    let mut key = ExportedKey {
+        secret,
+        pk: main_pk,
+    };
+    secret.zeroize();
+    assert_throw!(
+        &constants::ED25519_BASEPOINT_TABLE * &key.secret == main_pk,
+        "Reconstructed key does not match the public key"
+    );
+
+    if !approval.drv_path.is_empty() {
+        let chain_code = chain_code_of::<Ed25519>(&pubstore).catch_()?;
+        let (tweak_sk, child_pk) = approval
+            .hd_scheme
+            .derive(&approval.drv_path, &main_pk, &chain_code)
+            .catch_()?;
+        key.secret += tweak_sk;
+        key.pk = child_pk;
+        assert_throw!(
+            &constants::ED25519_BASEPOINT_TABLE * &key.secret == child_pk,
+            "Reconstructed key does not match the child public key"
+        );
+    }
+
+    Ok(key)
+}
+
+/// Every participant and the recipient must have sent an approval equal to mine.
+async fn check_approvals(
+    messenger: &impl Messenger,
+    ses_arch: &HashMap<u16, HashSet<MpcAddr>>,
+    approval: &ExportApproval,
+) -> Outcome<()> {
+    let mut parties: HashSet<MpcAddr> = ses_arch.values().flatten().cloned().collect();
+    assert_throw!(
+        !parties.contains(&approval.recipient),
+        "The recipient cannot be a participant"
+    );
+    parties.insert(approval.recipient);
+    let approval_dict: HashMap<MpcAddr, ExportApproval> = messenger
+        .gather("export_approval", &parties, MpcAddr::bcast_id())
+        .await
+        .catch_()?;
+    let mut disagree: Vec<MpcAddr> = approval_dict
+        .iter()
+        .filter(|(_, appr)| *appr != approval)
+        .map(|(id, _)| *id)
+        .collect();
+    disagree.sort();
+    if !disagree.is_empty() {
+        let disagree: Vec<String> = disagree.iter().map(|id| id.to_string()).collect();
+        throw!(
+            "ExportNotApproved",
+            format!("Not approved by {}", disagree.join(", "))
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::future::join_all;
+
+    use super::*;
+    use crate::biz_algo::testkit::*;
+    use crate::frost::verify_signature;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn exported_key_signs_for_the_pk() {
+        let messenger = MemMessenger::default();
+        let arch = key_arch(&[(1, 2, 3)]);
+        let keystores =
+            keygen::<Ed25519>(&messenger, &arch, &paired(&arch), FrostMode::Rfc9591).await;
+        let pk = keystores[0].pk().unwrap();
+
+        let ses_arch = ses_arch(&[(1, &[1, 3])]);
+        let approval = ExportApproval {
+            recipient: MpcAddr::new(9, 1),
+            pk,
+            drv_path: String::new(),
+            hd_scheme: HdScheme::Legacy,
+        };
+        let sid = SessionId::random();
+        let tasks = [&keystores[0], &keystores[2]]
+            .into_iter()
+            .map(|keystore| algo_export_send(&messenger, sid, &ses_arch, &approval, keystore));
+        let (sent, key) = tokio::join!(
+            join_all(tasks),
+            algo_export_receive(&messenger, sid, &ses_arch, &approval)
+        );
+        for result in sent.into_iter() {
+            result.unwrap();
+        }
+        let key = key.unwrap();
+        assert_eq!(key.pk, pk);
+        verify_signature(&key.sign(b"exported").unwrap(), &pk).unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn mismatched_approval_blocks_every_share() {
+        let messenger = MemMessenger::default();
+        let arch = key_arch(&[(1, 2, 3)]);
+        let keystores =
+            keygen::<Ed25519>(&messenger, &arch, &paired(&arch), FrostMode::Rfc9591).await;
+        let pk = keystores[0].pk().unwrap();
+
+        // Member 1.3 approves a child rather than the key itself.
+        let ses_arch = ses_arch(&[(1, &[1, 3])]);
+        let approval = ExportApproval {
+            recipient: MpcAddr::new(9, 1),
+            pk,
+            drv_path: String::new(),
+            hd_scheme: HdScheme::Legacy,
+        };
+        let mut other = approval.clone();
+        other.drv_path = "m/5".to_string();
+        let sid = SessionId::random();
+        let (sent_1, sent_3, key) = tokio::join!(
+            algo_export_send(&messenger, sid, &ses_arch, &approval, &keystores[0]),
+            algo_export_send(&messenger, sid, &ses_arch, &other, &keystores[2]),
+            algo_export_receive(&messenger, sid, &ses_arch, &approval)
+        );
+        assert!(sent_1.is_err());
+        assert!(sent_3.is_err());
+        assert!(key.is_err());
+        assert!(!messenger.has_topic(&format!("{}/export_eph", sid)));
+        assert!(!messenger.has_topic(&format!("{}/export_share", sid)));
+    }
+}
@@ -5,13 +5,127 @@ use bip32::{
     Prefix, PrivateKey, PublicKey, XPrv, XPub, KEY_SIZE,
 };
 use curve25519_dalek::{
+    constants,
     edwards::{CompressedEdwardsY, EdwardsPoint},
     scalar::Scalar,
 };
 use hmac::{Hmac, Mac, NewMac};
+use k256::elliptic_curve::PrimeField;
 use libexception::*;
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha512};
 
+use super::KeyStore;
+use crate::frost::{taproot_tweak, Ciphersuite, Ed25519, Secp256k1, Secp256k1Point};
+
+/// Non-hardened child derivation over the ciphersuite `C`, which needs no more
+/// than the public key and the chain code of the parent, so that it applies to
+/// a threshold key.
+///
+/// Hardened derivation hashes the parent secret, which no party holds. SLIP-10
+/// for Ed25519 only defines hardened children, hence it cannot be a scheme here.
+pub trait DerivationScheme<C: Ciphersuite = Ed25519> {
+    /// The tweak to add to the parent secret, and the child pk of `drv_path`.
+    fn derive(
+        &self,
+        drv_path: &str,
+        parent_pk: &C::Point,
+        chain_code: &ChainCode,
+    ) -> Outcome<(
+        /* tweak_sk: */ C::Scalar,
+        /* child_pk: */ C::Point,
+    )>;
+
+    /// Whether the root key is derived as well, i.e. for an empty `drv_path`,
+    /// where it otherwise signs as is.
+    fn derives_root(&self) -> bool {
+        false
+    }
+}
+
+/// Derivation schemes shipped with this crate.
+/// All parties of a session must derive with the same scheme.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum HdScheme {
+    /// BIP32 HMAC over compressed Edwards points, i.e. `non_hardened_derive()`,
+    /// kept for the child keys derived so far.
+    #[default]
+    Legacy,
+    /// Ed25519-BIP32 of Khovratovich and Law, as Cardano wallets derive,
+    /// i.e. `ed25519_bip32_derive()`.
+    Ed25519Bip32,
+}
+
+impl DerivationScheme for HdScheme {
+    fn derive(
+        &self,
+        drv_path: &str,
+        parent_pk: &EdwardsPoint,
+        chain_code: &ChainCode,
+    ) -> Outcome<(Scalar, EdwardsPoint)> {
+        match self {
+            HdScheme::Legacy => non_hardened_derive(drv_path, parent_pk, chain_code),
+            HdScheme::Ed25519Bip32 => ed25519_bip32_derive(drv_path, parent_pk, chain_code),
+        }
+    }
+}
+
+/// For ciphersuites without a derivation scheme, which only sign with the
+/// root key, i.e. with an empty `drv_path`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct NoDerivation;
+
+impl<C: Ciphersuite> DerivationScheme<C> for NoDerivation {
+    fn derive(
+        &self,
+        drv_path: &str,
+        _parent_pk: &C::Point,
+        _chain_code: &ChainCode,
+    ) -> Outcome<(C::Scalar, C::Point)> {
+        throw!(
+            "UnsupportedDerivation",
+            format!("No derivation scheme to derive \"{}\" with", drv_path)
+        );
+    }
+}
+
+/// Taproot outputs of BIP-86: BIP32 public derivation over secp256k1, i.e.
+/// `secp256k1_bip32_derive()`, then the taproot tweak of BIP-341. The root key
+/// is tweaked as well, since BIP-341 spends every output key by its tweak.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct Taproot {
+    /// Merkle root of the script tree, for outputs with a script path.
+    pub merkle_root: Option<[u8; 32]>,
+}
+
+impl DerivationScheme<Secp256k1> for Taproot {
+    fn derive(
+        &self,
+        drv_path: &str,
+        parent_pk: &Secp256k1Point,
+        chain_code: &ChainCode,
+    ) -> Outcome<(k256::Scalar, Secp256k1Point)> {
+        let (mut tweak_sk, internal_key) = match drv_path.is_empty() {
+            true => (k256::Scalar::ZERO, *parent_pk),
+            false => secp256k1_bip32_derive(drv_path, parent_pk, chain_code).catch_()?,
+        };
+        // The tweak is added to the internal key of even y, which is the
+        // negation of ours if it has an odd y.
+        let mut tweak = taproot_tweak(&internal_key, self.merkle_root.as_ref());
+        if Secp256k1::has_odd_y(&internal_key) {
+            tweak = -tweak;
+        }
+        tweak_sk += tweak;
+        Ok((tweak_sk, internal_key + Secp256k1::base_mul(&tweak)))
+    }
+
+    fn derives_root(&self) -> bool {
+        true
+    }
+}
+
+/// A home-grown scheme, which runs the HMAC of secp256k1 BIP32 over compressed
+/// Edwards points. No wallet outside this crate derives alike.
 pub fn non_hardened_derive(
     drv_path: &str,
     parent_pk: &EdwardsPoint,
@@ -105,8 +219,125 @@ pub fn non_hardened_derive(
     Ok((tweak_sk, child_pk))
 }
 
+/// Public derivation of Ed25519-BIP32 (Khovratovich and Law, 2017), with the
+/// little-endian indices of its V2 scheme. For each index $i$, let
+/// $Z = \mathrm{HMAC\text{-}SHA512}(c, \mathtt{0x02} \| A \| i)$, then the child
+/// is $A + 8 \cdot \mathrm{trunc}_{28}(Z_L) \cdot G$ with chain code
+/// $\mathrm{HMAC\text{-}SHA512}(c, \mathtt{0x03} \| A \| i)_R$.
+pub fn ed25519_bip32_derive(
+    drv_path: &str,
+    parent_pk: &EdwardsPoint,
+    chain_code: &ChainCode,
+) -> Outcome<(
+    /* tweak_sk: */ Scalar,
+    /* child_pk: */ EdwardsPoint,
+)> {
+    let HDE = "NonHardenedDerivationException";
+    let path = DerivationPath::from_str(drv_path).catch(
+        HDE,
+        &format!("String \"{}\" is not a valid derivation path", drv_path),
+    )?;
+    let mut pk = *parent_pk;
+    let mut chain_code = *chain_code;
+    let mut total_tweak = Scalar::zero();
+    for ccnum in path.as_ref() {
+        assert_throw!(
+            false == ccnum.is_hardened(),
+            HDE,
+            &format!("child number {} is hardened", &ccnum)
+        );
+        let pk_bytes = pk.compress().to_bytes();
+        let index = ccnum.index().to_le_bytes();
+
+        let mut hmac: Hmac<Sha512> =
+            Hmac::new_from_slice(&chain_code).catch(HDE, "Invalid HMAC SHA512 bytes")?;
+        hmac.update(&[0x02]);
+        hmac.update(&pk_bytes);
+        hmac.update(&index);
+        let z = hmac.finalize().into_bytes();
+        // $8 \cdot \mathrm{trunc}_{28}(Z_L)$, which is less than the group order.
+        let mut zl_mul8 = [0u8; 32];
+        let mut carry: u16 = 0;
+        for k in 0..28 {
+            let r = ((z[k] as u16) << 3) + carry;
+            zl_mul8[k] = r as u8;
+            carry = r >> 8;
+        }
+        zl_mul8[28] = carry as u8;
+        let tweak = Scalar::from_bytes_mod_order(zl_mul8);
+
+        let mut hmac: Hmac<Sha512> =
+            Hmac::new_from_slice(&chain_code).catch(HDE, "Invalid HMAC SHA512 bytes")?;
+        hmac.update(&[0x03]);
+        hmac.update(&pk_bytes);
+        hmac.update(&index);
+        let i = hmac.finalize().into_bytes();
+        chain_code.copy_from_slice(&i[32..]);
+
+        pk += &constants::ED25519_BASEPOINT_TABLE * &tweak;
+        total_tweak += tweak;
+    }
+
+    Ok((total_tweak, pk))
+}
+
+/// Public derivation of BIP32 over secp256k1. For each index $i$, let
+/// $I = \mathrm{HMAC\text{-}SHA512}(c, \mathrm{ser}_P(P) \| \mathrm{ser}_{32}(i))$,
+/// then the child is $P + I_L \cdot G$ with chain code $I_R$.
+pub fn secp256k1_bip32_derive(
+    drv_path: &str,
+    parent_pk: &Secp256k1Point,
+    chain_code: &ChainCode,
+) -> Outcome<(
+    /* tweak_sk: */ k256::Scalar,
+    /* child_pk: */ Secp256k1Point,
+)> {
+    let HDE = "NonHardenedDerivationException";
+    let path = DerivationPath::from_str(drv_path).catch(
+        HDE,
+        format!("String \"{}\" is not a valid derivation path", drv_path),
+    )?;
+    let mut pk = *parent_pk;
+    let mut chain_code = *chain_code;
+    let mut total_tweak = k256::Scalar::ZERO;
+    for ccnum in path.as_ref() {
+        assert_throw!(
+            !ccnum.is_hardened(),
+            HDE,
+            format!("child number {} is hardened", &ccnum)
+        );
+        let mut hmac: Hmac<Sha512> =
+            Hmac::new_from_slice(&chain_code).catch(HDE, "Invalid HMAC SHA512 bytes")?;
+        hmac.update(&Secp256k1::serialize_point(&pk));
+        hmac.update(&ccnum.to_bytes());
+        let i = hmac.finalize().into_bytes();
+        let tweak: Option<k256::Scalar> =
+            k256::Scalar::from_repr(*k256::FieldBytes::from_slice(&i[..32])).into();
+        let tweak = tweak.ifnone(HDE, "I_L is not less than the group order")?;
+        chain_code.copy_from_slice(&i[32..]);
+
+        pk += Secp256k1::base_mul(&tweak);
+        total_tweak += tweak;
+    }
+
+    Ok((total_tweak, pk))
+}
+
+/// Chain code of the root key, i.e. the one drawn at keygen, or
+/// `eval_chain_code()` of the public key for keystores without one.
+pub fn chain_code_of<C: Ciphersuite>(keystore: &KeyStore<C>) -> Outcome<ChainCode> {
+    match keystore.chain_code {
+        Some(chain_code) => Ok(chain_code),
+        None => Ok(eval_chain_code_of::<C>(&keystore.pk().catch_()?)),
+    }
+}
+
 pub fn eval_chain_code(pk: &EdwardsPoint) -> ChainCode {
-    let pk_bytes_short = pk.compress().to_bytes();
+    eval_chain_code_of::<Ed25519>(pk)
+}
+
+fn eval_chain_code_of<C: Ciphersuite>(pk: &C::Point) -> ChainCode {
+    let pk_bytes_short = C::serialize_point(pk);
     let chain_code: ChainCode = Sha512::digest(&pk_bytes_short)
         .get(..32)
         .unwrap()
@@ -114,3 +345,175 @@ pub fn eval_chain_code(pk: &EdwardsPoint) -> ChainCode {
         .unwrap();
     chain_code
 }
+
+/// Extended public key of `pk` and `chain_code`, as a Base58Check string with
+/// `Prefix::EPUB`. It holds no secret, so watch-only services may keep it. The
+/// root xpub of a keystore is that of `MultiShard::pk()` and `chain_code_of()`.
+pub fn encode_xpub(pk: &EdwardsPoint, chain_code: &ChainCode) -> String {
+    let attrs = ExtendedKeyAttrs {
+        parent_fingerprint: [0u8; 4],
+        child_number: ChildNumber(0u32),
+        chain_code: *chain_code,
+        depth: 0u8,
+    };
+    XPub::new(*pk, attrs).to_string(Prefix::EPUB)
+}
+
+/// Parse a string of `encode_xpub()` back into the pk and chain code.
+pub fn parse_xpub(xpub: &str) -> Outcome<(EdwardsPoint, ChainCode)> {
+    let ex_pk = ExtendedKey::from_str(xpub).catch("InvalidXPub", xpub)?;
+    assert_throw!(
+        ex_pk.prefix == Prefix::EPUB,
+        "InvalidXPub",
+        format!("Prefix {} is not {}", ex_pk.prefix, Prefix::EPUB)
+    );
+    let pk: XPub = XPub::try_from(ex_pk).catch("InvalidXPub", xpub)?;
+    Ok((*pk.public_key(), pk.attrs().chain_code))
+}
+
+/// Child pk of the non-hardened `drv_path` below `xpub`, or the pk itself if
+/// `drv_path` is empty. No secret, nor any party, is involved.
+pub fn xpub_derive(
+    xpub: &str,
+    drv_path: &str,
+    hd_scheme: &impl DerivationScheme,
+) -> Outcome<EdwardsPoint> {
+    let (pk, chain_code) = parse_xpub(xpub).catch_()?;
+    if drv_path.is_empty() {
+        return Ok(pk);
+    }
+    let (_, child_pk) = hd_scheme.derive(drv_path, &pk, &chain_code).catch_()?;
+    Ok(child_pk)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frost::x_only;
+
+    fn point(hex_str: &str) -> EdwardsPoint {
+        let bytes = hex::decode(hex_str).unwrap();
+        CompressedEdwardsY::from_slice(&bytes).decompress().unwrap()
+    }
+
+    fn check(scheme: HdScheme, parent_pk: &str, chain_code: &ChainCode, cases: &[(&str, &str)]) {
+        let parent_pk = point(parent_pk);
+        for (drv_path, child_pk) in cases {
+            let (tweak_sk, pk) = scheme.derive(drv_path, &parent_pk, chain_code).unwrap();
+            assert_eq!(hex::encode(pk.compress().as_bytes()), *child_pk);
+            assert_eq!(
+                parent_pk + &constants::ED25519_BASEPOINT_TABLE * &tweak_sk,
+                pk
+            );
+        }
+        assert!(scheme.derive("m/0'", &parent_pk, chain_code).is_err());
+    }
+
+    /// The scheme has no spec of its own; the parent is the public key of
+    /// RFC 8032, Section 7.1, TEST 1.
+    #[test]
+    fn legacy_vectors() {
+        let pk = "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
+        let chain_code = eval_chain_code(&point(pk));
+        check(
+            HdScheme::Legacy,
+            pk,
+            &chain_code,
+            &[(
+                "m/0/1",
+                "d0254165b1ff2b1caae3d0cf354e264f9bdec42d3f18df8a11b0b0682372c444",
+            )],
+        );
+    }
+
+    #[test]
+    fn xpub_roundtrip() {
+        let pk = point("d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a");
+        let chain_code = eval_chain_code(&pk);
+        let xpub = encode_xpub(&pk, &chain_code);
+        assert!(xpub.starts_with("epub"));
+        assert_eq!(parse_xpub(&xpub).unwrap(), (pk, chain_code));
+        assert_eq!(
+            hex::encode(
+                xpub_derive(&xpub, "m/0/1", &HdScheme::Legacy)
+                    .unwrap()
+                    .compress()
+                    .as_bytes()
+            ),
+            "d0254165b1ff2b1caae3d0cf354e264f9bdec42d3f18df8a11b0b0682372c444"
+        );
+
+        let ex_pk = ExtendedKey::from_str(&xpub).unwrap();
+        let xpub = XPub::try_from(ex_pk).unwrap().to_string(Prefix::XPUB);
+        assert!(parse_xpub(&xpub).is_err());
+    }
+
+    /// The parent is $k_L \cdot G$ and the chain code of `D1`, the root key in
+    /// the tests of the reference implementation `ed25519-bip32`.
+    #[test]
+    fn ed25519_bip32_vectors() {
+        let pk = "86ae05cac74c490e097646814c76a9ac813c470afb6ce6429bf836b1bf517878";
+        let chain_code: ChainCode =
+            hex::decode("7384db9ad6003bbd08b3b1ddc0d07a597293ff85e961bf252b331262eddfad0d")
+                .unwrap()
+                .try_into()
+                .unwrap();
+        check(
+            HdScheme::Ed25519Bip32,
+            pk,
+            &chain_code,
+            &[
+                (
+                    "m/0",
+                    "10abaae2cf8f9c2d0cee0a880c0c3f6fcaaae9a1edff667fc567a117f6359c20",
+                ),
+                (
+                    "m/0/1",
+                    "9bed6d25cf8ddceed0c873498be8431a4dba91ffb5fd7ed51b20341b3f5be8c9",
+                ),
+                (
+                    "m/1/2/3",
+                    "1373ca200926f675f946caeb9525fdc2e407b92066990509cf360c9c50d2e589",
+                ),
+            ],
+        );
+    }
+
+    /// Public key and chain code of a secp256k1 xpub.
+    fn parse_secp256k1_xpub(xpub: &str) -> (Secp256k1Point, ChainCode) {
+        let bytes = bs58::decode(xpub).into_vec().unwrap();
+        let pk = Secp256k1::deserialize_point(&bytes[45..78]).unwrap();
+        (pk, bytes[13..45].try_into().unwrap())
+    }
+
+    /// m/0H and m/0H/1 of test vector 1 of BIP32.
+    #[test]
+    fn secp256k1_bip32_vectors() {
+        let (pk, chain_code) = parse_secp256k1_xpub("xpub68Gmy5EdvgibQVfPdqkBBCHxA5htiqg55crXYuXoQRKfDBFA1WEjWgP6LHhwBZeNK1VTsfTFUHCdrfp1bgwQ9xv5ski8PX9rL2dZXvgGDnw");
+        let (child_pk, _) = parse_secp256k1_xpub("xpub6ASuArnXKPbfEwhqN6e3mwBcDTgzisQN1wXN9BJcM47sSikHjJf3UFHKkNAWbWMiGj7Wf5uMash7SyYq527Hqck2AxYysAA7xmALppuCkwQ");
+        let (tweak_sk, pk_1) = secp256k1_bip32_derive("m/1", &pk, &chain_code).unwrap();
+        assert_eq!(pk_1, child_pk);
+        assert_eq!(pk + Secp256k1::base_mul(&tweak_sk), child_pk);
+        assert!(secp256k1_bip32_derive("m/1'", &pk, &chain_code).is_err());
+    }
+
+    /// The first receiving address of account m/86'/0'/0' in the test vectors
+    /// of BIP-86.
+    #[test]
+    fn taproot_bip86_vector() {
+        let (pk, chain_code) = parse_secp256k1_xpub("xpub6BgBgsespWvERF3LHQu6CnqdvfEvtMcQjYrcRzx53QJjSxarj2afYWcLteoGVky7D3UKDP9QyrLprQ3VCECoY49yfdDEHGCtMMj92pReUsQ");
+        let output_key = "a60869f0dbcf1dc659c9cecbaf8050135ea9e8cdc487053f1dc6880949dc684c";
+        let scheme = Taproot::default();
+        let (tweak_sk, child_pk) = scheme.derive("m/0/0", &pk, &chain_code).unwrap();
+        assert_eq!(hex::encode(x_only(&child_pk)), output_key);
+        assert_eq!(pk + Secp256k1::base_mul(&tweak_sk), child_pk);
+
+        // The internal key of either y is tweaked to the same output key.
+        let (_, internal_key) = secp256k1_bip32_derive("m/0/0", &pk, &chain_code).unwrap();
+        for parent_pk in [internal_key, -internal_key] {
+            let (tweak_sk, child_pk) = scheme.derive("", &parent_pk, &chain_code).unwrap();
+            assert_eq!(hex::encode(x_only(&child_pk)), output_key);
+            assert_eq!(parent_pk + Secp256k1::base_mul(&tweak_sk), child_pk);
+        }
+    }
+}
@@ -0,0 +1,226 @@
+use std::collections::{HashMap, HashSet};
+
+use curve25519_dalek::{constants, edwards::EdwardsPoint, scalar::Scalar};
+use libexception::*;
+use mpc_spec::*;
+use rand::{rngs::OsRng, RngCore};
+use sha2::{Digest, Sha512};
+use zeroize::Zeroize;
+
+use super::KeyStore;
+use crate::frost::{eval_xi_com, generate_vss_share, Ed25519, PartyKey};
+
+/// Expand a 32-byte Ed25519 seed into its secret scalar, as RFC 8032 does:
+/// the lower half of SHA-512(seed), clamped.
+pub fn ed25519_expand_seed(seed: &[u8; 32]) -> Scalar {
+    let mut h: [u8; 64] = Sha512::digest(seed).into();
+    let mut a = [0u8; 32];
+    a.copy_from_slice(&h[..32]);
+    a[0] &= 248;
+    a[31] &= 127;
+    a[31] |= 64;
+    let scalar = Scalar::from_bytes_mod_order(a);
+    a.zeroize();
+    h.zeroize();
+    scalar
+}
+
+/// Extract the seed of a keypair file written by `solana-keygen`, which is a
+/// JSON array of the 32-byte seed followed by the 32-byte public key.
+pub fn solana_keypair_seed(json: &str) -> Outcome<[u8; 32]> {
+    let mut bytes: Vec<u8> = serde_json::from_str(json).catch("InvalidKeypair", "")?;
+    assert_throw!(
+        bytes.len() == 64,
+        "InvalidKeypair",
+        format!("{} bytes, 64 expected", bytes.len())
+    );
+    let mut seed = [0u8; 32];
+    seed.copy_from_slice(&bytes[..32]);
+    let pk = &constants::ED25519_BASEPOINT_TABLE * &ed25519_expand_seed(&seed);
+    let matched = pk.compress().as_bytes() == &bytes[32..];
+    bytes.zeroize();
+    if !matched {
+        seed.zeroize();
+        throw!("InvalidKeypair", "The public key does not match the seed");
+    }
+    Ok(seed)
+}
+
+/// Split an existing Ed25519 key, given by its seed, across the groups of
+/// `key_arch`, acting as a trusted dealer. `parties[k]` lists the shard ids of
+/// the `k`-th party, who gets the `k`-th keystore; every member of `key_arch`
+/// must be held by some party.
+///
+/// The dealer plays every member in keygen, with party keys $u_i$ summing up to
+/// the secret scalar, so that `MultiShard::pk()` is the original public key.
+/// It learns every share, and must be erased once the keystores are handed out.
+/// The chain code and the channel keys are drawn by the dealer as well.
+pub fn algo_import(
+    key_arch: &HashMap<u16 /*group_id*/, (usize /*th*/, HashSet<MpcAddr>)>,
+    parties: &[Vec<MpcAddr>],
+    seed: &[u8; 32],
+    mode: FrostMode,
+) -> Outcome<Vec<KeyStore>> {
+    let mut all_ids: Vec<MpcAddr> = Vec::new();
+    for (gid, (_, members)) in key_arch.iter() {
+        for id in members.iter() {
+            assert_throw!(
+                id.group_id() == *gid,
+                format!("{} not in group {}", id, gid)
+            );
+            all_ids.push(*id);
+        }
+    }
+    all_ids.sort();
+    let held: HashSet<MpcAddr> = parties.iter().flatten().cloned().collect();
+    for id in all_ids.iter() {
+        assert_throw!(held.contains(id), "NoHolder", id.to_string());
+    }
+
+    let mut rng = OsRng;
+    let mut secret = ed25519_expand_seed(seed);
+    let main_pk = &constants::ED25519_BASEPOINT_TABLE * &secret;
+
+    // Every member deals as in keygen, the last one with the remaining secret.
+    let mut channel_sk_dict: HashMap<MpcAddr, Scalar> = HashMap::new();
+    let mut channel_pk_grid: HashMap<u16, HashMap<MpcAddr, EdwardsPoint>> = HashMap::new();
+    let mut xi_dict: HashMap<MpcAddr, Scalar> = HashMap::new();
+    let mut vss_com_grid: HashMap<u16, HashMap<MpcAddr, Vec<EdwardsPoint>>> = HashMap::new();
+    for (k, my_id) in all_ids.iter().enumerate() {
+        let my_id = *my_id;
+        let gid = my_id.group_id();
+        let (th, members) = key_arch.get(&gid).ifnone_()?;
+        let party_key = if k + 1 < all_ids.len() {
+            let party_key: PartyKey = PartyKey::new(&mut rng);
+            secret -= party_key.u_i;
+            party_key
+        } else {
+            PartyKey::import(secret, &mut rng)
+        };
+
+        let _obj: _ = generate_vss_share(&party_key.u_i, my_id, members, *th, &mut rng).catch_()?;
+        let shares_com: Vec<EdwardsPoint> = _obj.0;
+        let mut shares: HashMap<MpcAddr, Scalar> = _obj.1;
+        for (j, share) in shares.iter() {
+            *xi_dict.entry(*j).or_insert_with(Scalar::zero) += share;
+        }
+        for x in shares.values_mut() {
+            x.zeroize();
+        }
+        vss_com_grid
+            .entry(gid)
+            .or_default()
+            .insert(my_id, shares_com);
+        let channel_sk = Scalar::random(&mut rng);
+        channel_pk_grid
+            .entry(gid)
+            .or_default()
+            .insert(my_id, &constants::ED25519_BASEPOINT_TABLE * &channel_sk);
+        channel_sk_dict.insert(my_id, channel_sk);
+    }
+    secret.zeroize();
+
+    let mut chain_code = [0u8; 32];
+    rng.fill_bytes(&mut chain_code);
+    let mut keystores: Vec<KeyStore> = Vec::with_capacity(parties.len());
+    for whoami in parties.iter() {
+        let mut keystore = KeyStore::<Ed25519> {
+            vss_com_grid: vss_com_grid.clone(),
+            channel_pk_grid: channel_pk_grid.clone(),
+            mode,
+            chain_code: Some(chain_code),
+            ..Default::default()
+        };
+        for my_id in whoami.iter() {
+            let gid = my_id.group_id();
+            assert_throw!(
+                !keystore.xi_pergroup.contains_key(&gid),
+                format!("Party holds two shards of group {}", gid)
+            );
+            let x_i = xi_dict
+                .get(my_id)
+                .ifnone("NoSuchMember", my_id.to_string())?;
+            let vss_com_dict = vss_com_grid.get(&gid).ifnone_()?;
+            assert_throw!(
+                eval_xi_com(*my_id, vss_com_dict) == &constants::ED25519_BASEPOINT_TABLE * x_i,
+                "Share does not match the commitments"
+            );
+            keystore
+                .channel_sk_pergroup
+                .insert(gid, *channel_sk_dict.get(my_id).ifnone_()?);
+            keystore.xi_pergroup.insert(gid, *x_i);
+            keystore.ids.insert(*my_id);
+        }
+        keystores.push(keystore);
+    }
+    for x in channel_sk_dict.values_mut().chain(xi_dict.values_mut()) {
+        x.zeroize();
+    }
+
+    // sanity check
+    let pk = keystores.first().ifnone_()?.pk().catch_()?;
+    assert_throw!(pk == main_pk, "Public key changed");
+
+    Ok(keystores)
+}
+
+#[cfg(test)]
+mod tests {
+    use curve25519_dalek::edwards::CompressedEdwardsY;
+
+    use super::*;
+    use crate::biz_algo::testkit::*;
+    use crate::biz_algo::SignMode;
+    use crate::frost::verify_solana;
+
+    /// RFC 8032, Section 7.1, TEST 1.
+    const SEED: &str = "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60";
+    const PUBKEY: &str = "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
+
+    #[test]
+    fn expand_seed() {
+        let seed: [u8; 32] = hex::decode(SEED).unwrap().try_into().unwrap();
+        let pk = &constants::ED25519_BASEPOINT_TABLE * &ed25519_expand_seed(&seed);
+        assert_eq!(hex::encode(pk.compress().as_bytes()), PUBKEY);
+    }
+
+    #[test]
+    fn solana_keypair() {
+        let bytes = hex::decode(format!("{}{}", SEED, PUBKEY)).unwrap();
+        let json = format!("{:?}", bytes);
+        let seed = solana_keypair_seed(&json).unwrap();
+        assert_eq!(hex::encode(seed), SEED);
+
+        let mut bytes = bytes;
+        bytes[63] ^= 1;
+        assert!(solana_keypair_seed(&format!("{:?}", bytes)).is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn imported_key_signs_as_the_original() {
+        let seed: [u8; 32] = hex::decode(SEED).unwrap().try_into().unwrap();
+        let arch = key_arch(&[(1, 2, 3)]);
+        let keystores = algo_import(&arch, &paired(&arch), &seed, FrostMode::Rfc9591).unwrap();
+        for keystore in keystores.iter() {
+            let pk = keystore.pk().unwrap();
+            assert_eq!(hex::encode(pk.compress().as_bytes()), PUBKEY);
+        }
+
+        let pk_bytes: [u8; 32] = hex::decode(PUBKEY).unwrap().try_into().unwrap();
+        let pk = CompressedEdwardsY(pk_bytes).decompress().unwrap();
+        let messenger = MemMessenger::default();
+        let ses_arch = ses_arch(&[(1, &[1, 3])]);
+        let signers = [keystores[0].clone(), keystores[2].clone()];
+        for sig in sign::<Ed25519>(
+            &messenger,
+            &ses_arch,
+            &signers,
+            b"imported",
+            &SignMode::Ed25519,
+        )
+        .await
+        {
+            verify_solana(&sig.unwrap(), &pk).unwrap();
+        }
+    }
+}
@@ -1,27 +1,110 @@
 use std::collections::{HashMap, HashSet}; // keys are in ascending order to avoid deadlock.
+use std::time::Duration;
 
 use curve25519_dalek::edwards::EdwardsPoint;
 use curve25519_dalek::scalar::Scalar;
 use libexception::*;
 use mpc_spec::*;
-use rand::rngs::OsRng;
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha512};
+use tokio::time::{timeout_at, Instant};
 use zeroize::Zeroize;
 
 use super::aes::*;
 use crate::frost::{
-    generate_dkg_challenge, generate_vss_share, keygen_validate_peers, merge_vss_share,
-    KeyGenDKGProposedCommitment, KeyGenZKP, PartyKey,
+    blame_invalid_sub_shares, generate_dkg_challenge, generate_weighted_vss_share,
+    keygen_validate_peers, merge_weighted_vss_share, weight_of, Blame, Ciphersuite, Ed25519,
+    Evidence, KeyGenDKGProposedCommitment, KeyGenZKP, PartyKey, SubShareDict,
 };
 
-pub type KeyStore = MultiShard<Scalar, EdwardsPoint>;
+pub type KeyStore<C = Ed25519> = MultiShard<<C as Ciphersuite>::Scalar, <C as Ciphersuite>::Point>;
 
-pub async fn algo_keygen(
+/// How long the dealers complained against have to reveal the shares in
+/// question, before they are disqualified as if they revealed none.
+pub const DKG_REVEAL_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Message of the `dkg_com` round: the polynomial commitment of a dealer, and
+/// the public key of its channels to the other members.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
+struct DkgCom<C: Ciphersuite> {
+    com: KeyGenDKGProposedCommitment<C>,
+    channel_pk: C::Point,
+}
+
+/// Split the `dkg_com` messages into the commitments and the channel keys.
+fn split_dkg_coms<C: Ciphersuite>(
+    dkg_com_dict: HashMap<MpcAddr, DkgCom<C>>,
+) -> (
+    HashMap<MpcAddr, KeyGenDKGProposedCommitment<C>>,
+    HashMap<MpcAddr, C::Point>,
+) {
+    let mut proposed_com_dict = HashMap::new();
+    let mut channel_pk_dict = HashMap::new();
+    for (j, dkg_com) in dkg_com_dict.into_iter() {
+        proposed_com_dict.insert(j, dkg_com.com);
+        channel_pk_dict.insert(j, dkg_com.channel_pk);
+    }
+    (proposed_com_dict, channel_pk_dict)
+}
+
+/// Keygen over the ciphersuite `C`, e.g. `algo_keygen::<Ed25519>(..)`.
+pub async fn algo_keygen<C: Ciphersuite>(
     messenger: &impl Messenger,
+    sid: SessionId,
     key_arch: &HashMap<u16 /*group_id*/, (usize /*th*/, HashSet<MpcAddr>)>,
     whoami: &[MpcAddr], // My shard_ids
     context: &str,      // Other parties challenge against this ctx
-) -> Outcome<KeyStore> {
-    let mut keystore = KeyStore::default();
+    mode: FrostMode,
+) -> Outcome<KeyStore<C>> {
+    algo_keygen_weighted::<C>(
+        messenger,
+        sid,
+        key_arch,
+        &HashMap::new(),
+        whoami,
+        context,
+        mode,
+    )
+    .await
+}
+
+/// `algo_keygen()` where a member of weight w gets w sub-shares, and counts
+/// as w signers, i.e. `th` of a group is the number of sub-shares needed.
+/// Members absent from `weights` weigh 1.
+///
+/// A dealer of bad shares who fails to answer the complaints against it is
+/// left out of the key, and of `vss_com_grid`, rather than aborting keygen,
+/// as long as the rest of its group weighs at least `th`.
+pub async fn algo_keygen_weighted<C: Ciphersuite>(
+    messenger: &impl Messenger,
+    sid: SessionId,
+    key_arch: &HashMap<u16 /*group_id*/, (usize /*th*/, HashSet<MpcAddr>)>,
+    weights: &HashMap<MpcAddr, u16>,
+    whoami: &[MpcAddr], // My shard_ids
+    context: &str,      // Other parties challenge against this ctx
+    mode: FrostMode,
+) -> Outcome<KeyStore<C>> {
+    // Mailboxes are namespaced by `sid`, and every challenge, commitment
+    // and AAD of the session is bound to it through the context.
+    let messenger = &messenger.scoped(sid);
+    let context = &format!("{}/{}", sid, context);
+
+    let mut keystore = KeyStore::<C> {
+        mode,
+        ..Default::default()
+    };
+    for (id, w) in weights.iter() {
+        let in_arch = key_arch
+            .get(&id.group_id())
+            .is_some_and(|(_, members)| members.contains(id));
+        assert_throw!(in_arch, "NoMember", id.to_string());
+        assert_throw!(*w >= 1, "InvalidWeight", format!("{} weighs {}", id, w));
+        if *w > 1 {
+            keystore.weights.insert(*id, *w);
+        }
+    }
 
     // shard_id should be traversed in ascending order to avoid deadlock.
     for my_id in whoami.iter() {
@@ -38,23 +121,28 @@ pub async fn algo_keygen(
         }
         println!();
 
-        // generate party key $u_i$ and ephemeral key $k_i$.
+        // generate party key $u_i$ and ephemeral key $k_i$, and the key of
+        // my channels, which is kept apart from $u_i$.
         let mut rng = OsRng;
-        let party_key = PartyKey::new(&mut rng);
-        if false {
-            use bip32::{Language, Mnemonic};
-            let mnemonic = Mnemonic::from_entropy(party_key.u_i.to_bytes(), Language::English);
-            let phrase = mnemonic.phrase().to_string();
-            drop(phrase);
-        }
+        let party_key = PartyKey::<C>::new(&mut rng);
+        let channel_sk = C::random_scalar(&mut rng);
 
         // generate vss commmitment and vss shares
-        let _obj: _ = generate_vss_share(&party_key.u_i, my_id, members, *th, &mut rng).catch_()?;
-        let shares_com: Vec<EdwardsPoint> = _obj.0;
-        let mut shares: HashMap<MpcAddr, Scalar> = _obj.1;
+        let _obj: _ = generate_weighted_vss_share::<C, _>(
+            &party_key.u_i,
+            my_id,
+            members,
+            &keystore.weights,
+            *th,
+            &mut rng,
+        )
+        .catch_()?;
+        let shares_com: Vec<C::Point> = _obj.0;
+        let mut shares: SubShareDict<C> = _obj.1;
 
         // generate challenge
-        let challenge = generate_dkg_challenge(
+        let challenge = generate_dkg_challenge::<C>(
+            mode,
             my_id,
             context,            // known to all participants
             &party_key.g_u_i(), // public key of shard
@@ -63,84 +151,113 @@ pub async fn algo_keygen(
         .catch_()?;
 
         // construct dkg commitment
-        let dkg_commitment = KeyGenDKGProposedCommitment {
+        let dkg_commitment = KeyGenDKGProposedCommitment::<C> {
             shares_commitment: shares_com,
             zkp: KeyGenZKP {
                 g_k_i: party_key.g_k_i(),
-                sigma: &party_key.k_i + &party_key.u_i * challenge,
+                sigma: party_key.k_i + party_key.u_i * challenge,
             },
         };
 
+        let dkg_com = DkgCom::<C> {
+            com: dkg_commitment,
+            channel_pk: C::base_mul(&channel_sk),
+        };
+
         messenger
-            .send("dkg_com", my_id, gcast_id, &dkg_commitment)
+            .send("dkg_com", my_id, gcast_id, &dkg_com)
             .await
             .catch_()?;
-        let proposed_com_dict: HashMap<MpcAddr, KeyGenDKGProposedCommitment> = messenger
+        let dkg_com_dict: HashMap<MpcAddr, DkgCom<C>> = messenger
             .gather("dkg_com", members, gcast_id)
             .await
             .catch_()?;
+        dkg_echo::<C>(
+            messenger,
+            Some(my_id),
+            gcast_id,
+            members,
+            context,
+            &dkg_com_dict,
+        )
+        .await
+        .catch_()?;
+        let (proposed_com_dict, channel_pk_dict) = split_dkg_coms(dkg_com_dict);
 
         // verify and collect others' vss_com_dict
-        let vss_com_dict: HashMap<MpcAddr, Vec<EdwardsPoint>> =
-            keygen_validate_peers(&proposed_com_dict, &context).catch_()?;
+        let mut vss_com_dict: HashMap<MpcAddr, Vec<C::Point>> =
+            keygen_validate_peers(&proposed_com_dict, &context, mode, *th).catch_()?;
         drop(proposed_com_dict);
-        for (_, vss_com) in vss_com_dict.iter() {
-            assert_throw!(vss_com.len() == *th); // to avoid DKG attack via increasing threshold on the fly.
-        }
 
-        // use others' pubkey to construct aes key
-        let mut aes_key_dict: HashMap<MpcAddr, [u8; 32]> = HashMap::new();
-        for j in members.iter() {
-            let com = vss_com_dict.get(j).ifnone_()?;
-            let aes_key = com[0] * &party_key.u_i; // aes_key = u_j * g_u_i
-            let aes_key = aes_key.compress().to_bytes();
-            aes_key_dict.insert(*j, aes_key);
-        }
+        // use others' channel keys to construct aes key
+        let aes_key_dict: HashMap<MpcAddr, [u8; 32]> =
+            channel_keys::<C>(&channel_sk, members, &channel_pk_dict).catch_()?;
 
-        // scatter vss shares via aes-gcm encrypted channel
-        for id in members.iter() {
-            let aes_key = aes_key_dict.get(id).ifnone_()?;
-            let plaintext = shares.get(id).ifnone_()?.to_bytes();
-            let aead_pack_i = aes_encrypt(aes_key, &plaintext).catch_()?;
-            messenger
-                .send("aead_share", my_id, *id, &aead_pack_i)
-                .await
-                .catch_()?;
+        // exchange vss shares via aes-gcm encrypted channel, and complain
+        // against the dealers of undecryptable or invalid ones
+        let weight = keystore.weight(&my_id);
+        let (mut party_shares, mut complaints) = exchange_sub_shares_or_blame::<C>(
+            messenger,
+            "aead_share",
+            context,
+            my_id,
+            members,
+            &aes_key_dict,
+            &shares,
+            weight,
+        )
+        .await
+        .catch_()?;
+        let invalid =
+            blame_invalid_sub_shares::<C>(&party_shares, &vss_com_dict, my_id, weight).catch_()?;
+        for (j, evidence) in invalid.evidence.into_iter() {
+            complaints.accuse(j, evidence);
         }
-        let aead_dict: HashMap<MpcAddr, AEAD> = messenger
-            .gather("aead_share", members, my_id)
-            .await
-            .catch_()?;
-
+        for j in complaints.culprits() {
+            party_shares.remove(&j);
+        }
+        let revealed: SubShareDict<C> = dkg_complaints::<C>(
+            messenger,
+            Some((my_id, &shares, &complaints)),
+            gcast_id,
+            members,
+            &keystore.weights,
+            *th,
+            &mut vss_com_dict,
+        )
+        .await
+        .catch_()?;
         for x in shares.values_mut() {
             x.zeroize();
         }
         drop(shares);
+        party_shares.extend(revealed);
+        party_shares.retain(|j, _| vss_com_dict.contains_key(j));
+        let disqualified = members.iter().filter(|j| !vss_com_dict.contains_key(j));
+        keystore.disqualified.extend(disqualified);
 
-        // gather vss shares
-        let mut party_shares: HashMap<MpcAddr, Scalar> = HashMap::new();
-        for j in members.iter() {
-            let aes_key = aes_key_dict.get(j).ifnone_()?;
-            let aead_pack = aead_dict.get(j).ifnone_()?;
-            let out = aes_decrypt(aes_key, &aead_pack).catch_()?;
-            assert_throw!(out.len() == 32);
-            let mut out_arr = [0u8; 32];
-            out_arr.copy_from_slice(&out);
-            let out_fe = Scalar::from_bytes_mod_order(out_arr);
-            party_shares.insert(*j, out_fe);
-        }
-
-        // compute x_i
-        let signing_key: Scalar = merge_vss_share(&party_shares, &vss_com_dict, my_id).catch_()?;
+        // compute x_i, and the other sub-shares of a weighted member
+        let mut signing_key: Vec<C::Scalar> =
+            merge_weighted_vss_share::<C>(&party_shares, &vss_com_dict, my_id, weight).catch_()?;
         for x in party_shares.values_mut() {
             x.zeroize();
         }
 
-        keystore.ui_pergroup.insert(gid, party_key.u_i);
-        keystore.xi_pergroup.insert(gid, signing_key);
+        keystore.channel_sk_pergroup.insert(gid, channel_sk);
+        keystore.channel_pk_grid.insert(gid, channel_pk_dict);
+        keystore.xi_pergroup.insert(gid, signing_key.remove(0));
+        if !signing_key.is_empty() {
+            keystore.sub_xi_pergroup.insert(gid, signing_key);
+        }
         keystore.vss_com_grid.insert(gid, vss_com_dict);
     }
 
+    // Flip the chain code together with all members
+    let chain_code = joint_chain_code(messenger, key_arch, whoami, context)
+        .await
+        .catch_()?;
+    keystore.chain_code = Some(chain_code);
+
     // Fetch vss_com of members in other groups
     let mut key_arch = key_arch.clone();
     for my_id in whoami.iter() {
@@ -149,20 +266,35 @@ pub async fn algo_keygen(
     }
     for (gid, (th, members)) in key_arch.iter() {
         let gcast_id = MpcAddr::gcast_id(*gid);
-        let proposed_com_dict: HashMap<MpcAddr, KeyGenDKGProposedCommitment> = messenger
+        let dkg_com_dict: HashMap<MpcAddr, DkgCom<C>> = messenger
             .gather("dkg_com", members, gcast_id)
             .await
             .catch_()?;
+        dkg_echo::<C>(messenger, None, gcast_id, members, context, &dkg_com_dict)
+            .await
+            .catch_()?;
+        let (proposed_com_dict, channel_pk_dict) = split_dkg_coms(dkg_com_dict);
 
-        // verify and collect others' vss_com_dict
-        let vss_com_dict: HashMap<MpcAddr, Vec<EdwardsPoint>> =
-            keygen_validate_peers(&proposed_com_dict, &context).catch_()?;
+        // verify and collect others' vss_com_dict, of the qualified dealers
+        let mut vss_com_dict: HashMap<MpcAddr, Vec<C::Point>> =
+            keygen_validate_peers(&proposed_com_dict, &context, mode, *th).catch_()?;
         drop(proposed_com_dict);
-        for (_, vss_com) in vss_com_dict.iter() {
-            assert_throw!(vss_com.len() == *th); // to avoid DKG attack via increasing threshold on the fly.
-        }
+        dkg_complaints::<C>(
+            messenger,
+            None,
+            gcast_id,
+            members,
+            &keystore.weights,
+            *th,
+            &mut vss_com_dict,
+        )
+        .await
+        .catch_()?;
+        let disqualified = members.iter().filter(|j| !vss_com_dict.contains_key(j));
+        keystore.disqualified.extend(disqualified);
 
         keystore.vss_com_grid.insert(*gid, vss_com_dict);
+        keystore.channel_pk_grid.insert(*gid, channel_pk_dict);
     }
 
     // Archive my shard_ids
@@ -170,3 +302,832 @@ pub async fn algo_keygen(
 
     Ok(keystore)
 }
+
+/// Draw a random chain code among all members of `key_arch` by commit-reveal,
+/// so that no one can bias it once the others' randomness is known.
+/// The chain code is SHA-512 of every revealed randomness, in ascending order
+/// of the member ids; those whose reveal mismatches its commitment are blamed.
+async fn joint_chain_code(
+    messenger: &impl Messenger,
+    key_arch: &HashMap<u16, (usize, HashSet<MpcAddr>)>,
+    whoami: &[MpcAddr],
+    context: &str,
+) -> Outcome<[u8; 32]> {
+    let bcast_id = MpcAddr::bcast_id();
+    let mut all_ids_asc: Vec<MpcAddr> = key_arch
+        .values()
+        .flat_map(|(_, members)| members.iter().cloned())
+        .collect();
+    all_ids_asc.sort();
+    let all_ids: HashSet<MpcAddr> = all_ids_asc.iter().cloned().collect();
+
+    let mut rng = OsRng;
+    let mut my_rand_dict: HashMap<MpcAddr, [u8; 32]> = HashMap::new();
+    for my_id in whoami.iter() {
+        let mut rand = [0u8; 32];
+        rng.fill_bytes(&mut rand);
+        let com = chain_code_commitment(*my_id, context, &rand);
+        messenger
+            .send("chain_code_com", *my_id, bcast_id, &com)
+            .await
+            .catch_()?;
+        my_rand_dict.insert(*my_id, rand);
+    }
+    let com_dict: HashMap<MpcAddr, Vec<u8>> = messenger
+        .gather("chain_code_com", &all_ids, bcast_id)
+        .await
+        .catch_()?;
+
+    for (my_id, rand) in my_rand_dict.iter() {
+        messenger
+            .send("chain_code_rev", *my_id, bcast_id, rand)
+            .await
+            .catch_()?;
+    }
+    let rev_dict: HashMap<MpcAddr, [u8; 32]> = messenger
+        .gather("chain_code_rev", &all_ids, bcast_id)
+        .await
+        .catch_()?;
+
+    let mut hasher = Sha512::new();
+    let mut blame: Blame = Blame::default();
+    for j in all_ids_asc.iter() {
+        let com = com_dict.get(j).ifnone_()?;
+        let rand = rev_dict.get(j).ifnone_()?;
+        if chain_code_commitment(*j, context, rand) != *com {
+            let (com, reveal) = (com.clone(), rand.to_vec());
+            blame.accuse(*j, Evidence::InvalidReveal { com, reveal });
+        }
+        hasher.update(rand);
+    }
+    blame.throw_if_any()?;
+
+    let mut chain_code = [0u8; 32];
+    chain_code.copy_from_slice(&hasher.finalize()[..32]);
+    Ok(chain_code)
+}
+
+/// Echo round after `dkg_com`: `me`, if a member, broadcasts the digests of
+/// the commitments `com_dict` it got, and every member's echo is checked
+/// against them, so that a dealer cannot show different polynomials to
+/// different members, nor to observers from other groups. On a mismatch both
+/// the dealer and the echoer are blamed, as either of them may be lying.
+async fn dkg_echo<C: Ciphersuite>(
+    messenger: &impl Messenger,
+    me: Option<MpcAddr>,
+    gcast_id: MpcAddr,
+    members: &HashSet<MpcAddr>,
+    context: &str,
+    com_dict: &HashMap<MpcAddr, DkgCom<C>>,
+) -> Outcome<()> {
+    let mut members_asc: Vec<MpcAddr> = members.iter().cloned().collect();
+    members_asc.sort();
+    let mut view: Vec<(MpcAddr, Vec<u8>)> = Vec::with_capacity(members_asc.len());
+    for j in members_asc.iter() {
+        let com = com_dict.get(j).ifnone_()?;
+        view.push((*j, dkg_com_digest::<C>(*j, context, com)));
+    }
+    if let Some(my_id) = me {
+        messenger
+            .send("dkg_echo", my_id, gcast_id, &view)
+            .await
+            .catch_()?;
+    }
+    let echo_dict: HashMap<MpcAddr, Vec<(MpcAddr, Vec<u8>)>> = messenger
+        .gather("dkg_echo", members, gcast_id)
+        .await
+        .catch_()?;
+
+    let mut blame = Blame::<C>::default();
+    for k in members_asc.iter() {
+        let echo = echo_dict.get(k).ifnone_()?;
+        for (j, ours) in view.iter() {
+            let theirs = match echo.iter().find(|(id, _)| id == j) {
+                Some((_, digest)) => digest.clone(),
+                None => Vec::new(),
+            };
+            if theirs != *ours {
+                blame.accuse(
+                    *j,
+                    Evidence::Equivocation {
+                        echoer: *k,
+                        ours: ours.clone(),
+                        theirs: theirs.clone(),
+                    },
+                );
+                blame.accuse(
+                    *k,
+                    Evidence::InconsistentEcho {
+                        dealer: *j,
+                        ours: ours.clone(),
+                        theirs,
+                    },
+                );
+            }
+        }
+    }
+    blame.throw_if_any()?;
+    Ok(())
+}
+
+/// Complaint round after `aead_share`, as in GJKR. `me`, if a member,
+/// broadcasts its complaints against dealers, and reveals the shares it
+/// dealt to those who complained against it. A dealer who reveals no or
+/// invalid shares within `DKG_REVEAL_TIMEOUT` is disqualified, i.e. dropped
+/// from `vss_com_dict` though not from the group, and keygen goes on as long
+/// as the remaining members weigh at least `th`. As a reveal may reach some
+/// parties only after their deadline, every member echoes the dealers it
+/// disqualified, and keygen aborts unless all of them agree.
+/// Returns the valid shares revealed to `me` by the dealers it complained against.
+async fn dkg_complaints<C: Ciphersuite>(
+    messenger: &impl Messenger,
+    me: Option<(MpcAddr, &SubShareDict<C>, &Blame<C>)>, // (my_id, dealt shares, complaints)
+    gcast_id: MpcAddr,
+    members: &HashSet<MpcAddr>,
+    weights: &HashMap<MpcAddr, u16>,
+    th: usize,
+    vss_com_dict: &mut HashMap<MpcAddr, Vec<C::Point>>,
+) -> Outcome<SubShareDict<C>> {
+    if let Some((my_id, _, complaints)) = me {
+        messenger
+            .send("dkg_complaint", my_id, gcast_id, &complaints.culprits())
+            .await
+            .catch_()?;
+    }
+    let complaint_dict: HashMap<MpcAddr, Vec<MpcAddr>> = messenger
+        .gather("dkg_complaint", members, gcast_id)
+        .await
+        .catch_()?;
+    let mut complainers: HashMap<MpcAddr, Vec<MpcAddr>> = HashMap::new();
+    for (k, accused) in complaint_dict.iter() {
+        for j in accused.iter().filter(|j| members.contains(j)) {
+            complainers.entry(*j).or_default().push(*k);
+        }
+    }
+    let mut revealed: SubShareDict<C> = HashMap::new();
+    if complainers.is_empty() {
+        return Ok(revealed);
+    }
+
+    if let Some((my_id, dealt, _)) = me {
+        if let Some(ks) = complainers.get(&my_id) {
+            let mut reveal: SubShareDict<C> = HashMap::new();
+            for k in ks.iter() {
+                reveal.insert(*k, dealt.get(k).ifnone_()?.clone());
+            }
+            messenger
+                .send("dkg_reveal", my_id, gcast_id, &reveal)
+                .await
+                .catch_()?;
+        }
+    }
+    // a dealer who never answers must not stall the others
+    let deadline = Instant::now() + DKG_REVEAL_TIMEOUT;
+    let mut reveal_dict: HashMap<MpcAddr, SubShareDict<C>> = HashMap::new();
+    for j in complainers.keys() {
+        let reveal = messenger.receive("dkg_reveal", *j, gcast_id);
+        if let Ok(reveal) = timeout_at(deadline, reveal).await {
+            reveal_dict.insert(*j, reveal.catch_()?);
+        }
+    }
+
+    let mut blame = Blame::<C>::default();
+    let unanswered: SubShareDict<C> = HashMap::new();
+    for (j, ks) in complainers.iter() {
+        let reveal = reveal_dict.get(j).unwrap_or(&unanswered);
+        for k in ks.iter() {
+            let weight = weight_of(k, weights);
+            let shares = match reveal.get(k) {
+                Some(shares) if shares.len() == weight as usize => shares,
+                _ => {
+                    blame.accuse(*j, Evidence::UnansweredComplaint { complainer: *k });
+                    break;
+                }
+            };
+            let single: SubShareDict<C> = HashMap::from([(*j, shares.clone())]);
+            let invalid =
+                blame_invalid_sub_shares::<C>(&single, vss_com_dict, *k, weight).catch_()?;
+            if let Some(evidence) = invalid.evidence.into_values().next() {
+                blame.accuse(*j, evidence);
+                break;
+            }
+            if me.is_some_and(|(my_id, _, _)| my_id == *k) {
+                revealed.insert(*j, shares.clone());
+            }
+        }
+    }
+
+    // agree on the disqualified dealers before dropping any
+    let disqualified = blame.culprits();
+    if let Some((my_id, _, _)) = me {
+        messenger
+            .send("dkg_disqualified", my_id, gcast_id, &disqualified)
+            .await
+            .catch_()?;
+    }
+    let disqualified_dict: HashMap<MpcAddr, Vec<MpcAddr>> = messenger
+        .gather("dkg_disqualified", members, gcast_id)
+        .await
+        .catch_()?;
+    let mut members_asc: Vec<MpcAddr> = members.iter().cloned().collect();
+    members_asc.sort();
+    let mut disputes = Blame::<C>::default();
+    for k in members_asc.iter() {
+        let theirs = disqualified_dict.get(k).ifnone_()?;
+        if *theirs == disqualified {
+            continue;
+        }
+        for j in members_asc.iter() {
+            if theirs.contains(j) == disqualified.contains(j) {
+                continue;
+            }
+            // either the dealer answered late, or the member lies
+            let evidence = match (blame.evidence.get(j), complainers.get(j)) {
+                (Some(evidence), _) => evidence.clone(),
+                (None, Some(ks)) => Evidence::UnansweredComplaint { complainer: ks[0] },
+                (None, None) => continue,
+            };
+            disputes.accuse(*j, evidence);
+        }
+        disputes.accuse(
+            *k,
+            Evidence::InconsistentDisqualification {
+                ours: disqualified.clone(),
+                theirs: theirs.clone(),
+            },
+        );
+    }
+    disputes.throw_if_any()?;
+
+    // drop the disqualified dealers
+    for j in disqualified.iter() {
+        vss_com_dict.remove(j);
+        revealed.remove(j);
+    }
+    let qualified: usize = members
+        .iter()
+        .filter(|i| vss_com_dict.contains_key(i))
+        .map(|i| weight_of(i, weights) as usize)
+        .sum();
+    if qualified < th {
+        blame.throw_if_any()?;
+    }
+    Ok(revealed)
+}
+
+fn dkg_com_digest<C: Ciphersuite>(id: MpcAddr, context: &str, dkg_com: &DkgCom<C>) -> Vec<u8> {
+    let com = &dkg_com.com;
+    let mut hasher = Sha512::new();
+    hasher.update(b"mpc_eddsa dkg_echo");
+    hasher.update(id.to_be_bytes());
+    hasher.update((context.len() as u64).to_be_bytes());
+    hasher.update(context.as_bytes());
+    hasher.update((com.shares_commitment.len() as u64).to_be_bytes());
+    for point in com.shares_commitment.iter() {
+        hasher.update(C::serialize_point(point));
+    }
+    hasher.update(C::serialize_point(&com.zkp.g_k_i));
+    hasher.update(C::serialize_scalar(&com.zkp.sigma));
+    hasher.update(C::serialize_point(&dkg_com.channel_pk));
+    hasher.finalize()[..32].to_vec()
+}
+
+fn chain_code_commitment(id: MpcAddr, context: &str, rand: &[u8; 32]) -> Vec<u8> {
+    let mut hasher = Sha512::new();
+    hasher.update(b"mpc_eddsa chain code");
+    hasher.update(id.to_be_bytes());
+    hasher.update(context.as_bytes());
+    hasher.update(rand);
+    hasher.finalize().to_vec()
+}
+
+/// Keys of the aes-gcm channels between me and each of `members`, derived with
+/// HKDF from the ECDH secret $s_i s_j G$ of my channel secret key $s_i$ and
+/// their channel public key $s_j G$ in `channel_pk_dict`.
+pub(super) fn channel_keys<C: Ciphersuite>(
+    channel_sk: &C::Scalar,
+    members: &HashSet<MpcAddr>,
+    channel_pk_dict: &HashMap<MpcAddr, C::Point>,
+) -> Outcome<HashMap<MpcAddr, [u8; 32]>> {
+    let mut aes_key_dict: HashMap<MpcAddr, [u8; 32]> = HashMap::new();
+    for j in members.iter() {
+        let channel_pk = channel_pk_dict
+            .get(j)
+            .ifnone("NoChannelKey", j.to_string())?;
+        let shared = *channel_pk * *channel_sk; // s_j * g_s_i
+        aes_key_dict.insert(*j, channel_key(&C::serialize_point(&shared)));
+    }
+    Ok(aes_key_dict)
+}
+
+/// `channel_keys()` of my shard of group `gid` in `keystore`, which also
+/// works for keystores created before channel keys were kept apart.
+pub(super) fn keystore_channel_keys<C: Ciphersuite>(
+    keystore: &KeyStore<C>,
+    gid: u16,
+    members: &HashSet<MpcAddr>,
+) -> Outcome<HashMap<MpcAddr, [u8; 32]>> {
+    let channel_sk = keystore
+        .channel_sk(gid)
+        .ifnone("NoChannelKey", format!("No channel key of group {}", gid))?;
+    let mut channel_pk_dict: HashMap<MpcAddr, C::Point> = HashMap::new();
+    for j in members.iter() {
+        let channel_pk = keystore
+            .channel_pk(j)
+            .ifnone("NoChannelKey", j.to_string())?;
+        channel_pk_dict.insert(*j, channel_pk);
+    }
+    channel_keys::<C>(channel_sk, members, &channel_pk_dict)
+}
+
+/// Send `shares[j]` to every `j` of `members` through the aes-gcm channel keyed
+/// by `aes_key_dict[j]`, and collect the shares sent to `my_id` in turn. Packs
+/// are bound to their sender, receiver, `topic` and `context` by `channel_aad()`.
+/// Senders of undecryptable or non-canonical shares are blamed.
+pub(super) async fn exchange_shares(
+    messenger: &impl Messenger,
+    topic: &str,
+    context: &str,
+    my_id: MpcAddr,
+    members: &HashSet<MpcAddr>,
+    aes_key_dict: &HashMap<MpcAddr, [u8; 32]>,
+    shares: &HashMap<MpcAddr, Scalar>,
+) -> Outcome<HashMap<MpcAddr, Scalar>> {
+    let shares: HashMap<MpcAddr, Vec<Scalar>> =
+        shares.iter().map(|(j, x)| (*j, vec![*x])).collect();
+    let mut party_shares = exchange_sub_shares::<Ed25519>(
+        messenger,
+        topic,
+        context,
+        my_id,
+        members,
+        aes_key_dict,
+        &shares,
+        1,
+    )
+    .await
+    .catch_()?;
+    Ok(party_shares
+        .iter_mut()
+        .map(|(j, x)| (*j, x.remove(0)))
+        .collect())
+}
+
+/// `exchange_shares()` of the sub-shares of weighted members, sent together
+/// in one aes-gcm pack. I expect `weight` sub-shares from everyone.
+pub(super) async fn exchange_sub_shares<C: Ciphersuite>(
+    messenger: &impl Messenger,
+    topic: &str,
+    context: &str,
+    my_id: MpcAddr,
+    members: &HashSet<MpcAddr>,
+    aes_key_dict: &HashMap<MpcAddr, [u8; 32]>,
+    shares: &SubShareDict<C>,
+    weight: u16,
+) -> Outcome<SubShareDict<C>> {
+    let (party_shares, blame) = exchange_sub_shares_or_blame::<C>(
+        messenger,
+        topic,
+        context,
+        my_id,
+        members,
+        aes_key_dict,
+        shares,
+        weight,
+    )
+    .await
+    .catch_()?;
+    blame.throw_if_any()?;
+    Ok(party_shares)
+}
+
+/// `exchange_sub_shares()` which returns the blame of the senders of bad
+/// packs along with the good shares, instead of aborting.
+async fn exchange_sub_shares_or_blame<C: Ciphersuite>(
+    messenger: &impl Messenger,
+    topic: &str,
+    context: &str,
+    my_id: MpcAddr,
+    members: &HashSet<MpcAddr>,
+    aes_key_dict: &HashMap<MpcAddr, [u8; 32]>,
+    shares: &SubShareDict<C>,
+    weight: u16,
+) -> Outcome<(SubShareDict<C>, Blame<C>)> {
+    for id in members.iter() {
+        let aes_key = aes_key_dict.get(id).ifnone_()?;
+        let mut plaintext: Vec<u8> = Vec::new();
+        for x in shares.get(id).ifnone_()?.iter() {
+            plaintext.extend(C::serialize_scalar(x));
+        }
+        let aad = channel_aad(my_id, *id, topic, context);
+        let aead_pack_i = aes_encrypt(aes_key, &plaintext, &aad).catch_()?;
+        plaintext.zeroize();
+        messenger
+            .send(topic, my_id, *id, &aead_pack_i)
+            .await
+            .catch_()?;
+    }
+    let aead_dict: HashMap<MpcAddr, AEAD> =
+        messenger.gather(topic, members, my_id).await.catch_()?;
+
+    let mut party_shares: SubShareDict<C> = HashMap::new();
+    let mut blame = Blame::<C>::default();
+    for j in members.iter() {
+        let aes_key = aes_key_dict.get(j).ifnone_()?;
+        let aead_pack = aead_dict.get(j).ifnone_()?;
+        let aad = channel_aad(*j, my_id, topic, context);
+        let out_fe: Option<Vec<C::Scalar>> = match aes_decrypt(aes_key, aead_pack, &aad) {
+            Ok(mut out) if out.len() == C::SCALAR_LEN * weight as usize => {
+                let out_fe = out
+                    .chunks(C::SCALAR_LEN)
+                    .map(C::deserialize_scalar)
+                    .collect();
+                out.zeroize();
+                out_fe
+            }
+            _ => None,
+        };
+        match out_fe {
+            Some(out_fe) => {
+                party_shares.insert(*j, out_fe);
+            }
+            None => {
+                let ciphertext = aead_pack.ciphertext.clone();
+                let nonce = aead_pack.tag.clone();
+                blame.accuse(*j, Evidence::UndecryptableShare { ciphertext, nonce });
+            }
+        }
+    }
+
+    Ok((party_shares, blame))
+}
+
+/// Protocols which rewrite or combine the shares one per member, i.e. all but
+/// keygen and signing, do not handle the sub-shares of weighted members yet.
+pub(super) fn assert_unweighted(keystore: &KeyStore) -> Outcome<()> {
+    assert_throw!(
+        keystore.weights.is_empty(),
+        "WeightedShares",
+        "Not supported for a keystore with weighted members"
+    );
+    Ok(())
+}
+
+/// Public part of a keystore, relayed to a party who has none.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub(super) struct KeyStorePublic {
+    pub vss_com_grid: HashMap<u16, HashMap<MpcAddr, Vec<EdwardsPoint>>>,
+    pub aux: Option<Vec<u8>>,
+    pub mode: FrostMode,
+    pub epoch: u64,
+    pub chain_code: Option<[u8; 32]>,
+    pub policy: Option<AccessPolicy>,
+    pub policy_pk: Option<EdwardsPoint>,
+    pub channel_pk_grid: HashMap<u16, HashMap<MpcAddr, EdwardsPoint>>,
+    pub disqualified: HashSet<MpcAddr>,
+}
+
+impl KeyStorePublic {
+    pub fn of(keystore: &KeyStore) -> Self {
+        KeyStorePublic {
+            vss_com_grid: keystore.vss_com_grid.clone(),
+            aux: keystore.aux.clone(),
+            mode: keystore.mode,
+            epoch: keystore.epoch,
+            chain_code: keystore.chain_code,
+            policy: keystore.policy.clone(),
+            policy_pk: keystore.policy_pk,
+            channel_pk_grid: keystore.channel_pk_grid.clone(),
+            disqualified: keystore.disqualified.clone(),
+        }
+    }
+
+    /// A keystore of this public part, without any shard.
+    pub fn into_keystore(self) -> KeyStore {
+        KeyStore::<Ed25519> {
+            vss_com_grid: self.vss_com_grid,
+            aux: self.aux,
+            mode: self.mode,
+            epoch: self.epoch,
+            chain_code: self.chain_code,
+            policy: self.policy,
+            policy_pk: self.policy_pk,
+            channel_pk_grid: self.channel_pk_grid,
+            disqualified: self.disqualified,
+            ..Default::default()
+        }
+    }
+
+    /// Gather the public part sent by every one of `members` to `dst`,
+    /// which must be the same.
+    pub async fn gather(
+        messenger: &impl Messenger,
+        topic: &str,
+        members: &HashSet<MpcAddr>,
+        dst: MpcAddr,
+    ) -> Outcome<Self> {
+        let public_dict: HashMap<MpcAddr, KeyStorePublic> =
+            messenger.gather(topic, members, dst).await.catch_()?;
+        let mut public: Option<KeyStorePublic> = None;
+        for (j, pub_j) in public_dict.into_iter() {
+            match public.as_ref() {
+                Some(p) => assert_throw!(
+                    *p == pub_j,
+                    "InconsistentKeystore",
+                    format!("{} disagrees with other members", j)
+                ),
+                None => public = Some(pub_j),
+            }
+        }
+        let public = public.ifnone("NoMembers", topic)?;
+        Ok(public)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::future::join_all;
+
+    use super::*;
+    use crate::biz_algo::testkit::*;
+    use crate::biz_algo::{
+        chain_code_of, derive_child, eval_chain_code, DerivationScheme, HdScheme, SignMode,
+    };
+    use crate::frost::verify_signature;
+
+    /// Keygen in session `sid`, the `k`-th of `parties` over `messengers[k]`.
+    async fn keygen_over(
+        messengers: &[MemMessenger],
+        sid: SessionId,
+        arch: &KeyArch,
+        weights: &HashMap<MpcAddr, u16>,
+        parties: &[Vec<MpcAddr>],
+    ) -> Vec<Outcome<KeyStore>> {
+        let tasks = parties.iter().zip(messengers.iter()).map(|(whoami, m)| {
+            algo_keygen_weighted::<Ed25519>(
+                m,
+                sid,
+                arch,
+                weights,
+                whoami,
+                "test",
+                FrostMode::Rfc9591,
+            )
+        });
+        join_all(tasks).await
+    }
+
+    /// A messenger on which `dealer` sends `victim` an undecryptable share,
+    /// and, if `bad_reveal`, reveals shares off its commitment on complaint.
+    fn bad_dealer(dealer: MpcAddr, victim: MpcAddr, bad_reveal: bool) -> MemMessenger {
+        MemMessenger::default().tampered(move |topic, src, dst, value| {
+            if topic.ends_with("/aead_share") && src == dealer && dst == victim {
+                retype(value, |pack: &mut AEAD| pack.ciphertext[0] ^= 1);
+            }
+            if topic.ends_with("/dkg_reveal") && src == dealer && bad_reveal {
+                retype(value, |reveal: &mut SubShareDict<Ed25519>| {
+                    for shares in reveal.values_mut() {
+                        shares[0] += Scalar::one();
+                    }
+                });
+            }
+            true
+        })
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn bad_share_below_threshold_aborts() {
+        let arch = key_arch(&[(1, 3, 3)]);
+        let parties = paired(&arch);
+        let dealer = MpcAddr::new(1, 1);
+        let weights = HashMap::from([(dealer, 2)]);
+        let messenger = bad_dealer(dealer, MpcAddr::new(1, 2), true);
+        let messengers = vec![messenger; parties.len()];
+        let results =
+            keygen_over(&messengers, SessionId::random(), &arch, &weights, &parties).await;
+        for result in results.into_iter() {
+            let err = result.err().unwrap();
+            let blame = Blame::of(&err).unwrap();
+            assert_eq!(blame.culprits(), vec![dealer]);
+            assert!(matches!(
+                blame.evidence[&dealer],
+                Evidence::InvalidShare { .. }
+            ));
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn invalid_zkp_is_blamed() {
+        let arch = key_arch(&[(1, 2, 3)]);
+        let parties = paired(&arch);
+        let dealer = MpcAddr::new(1, 2);
+        let messenger = MemMessenger::default().tampered(move |topic, src, _, value| {
+            if topic.ends_with("/dkg_com") && src == dealer {
+                retype(value, |com: &mut DkgCom<Ed25519>| {
+                    com.com.zkp.sigma += Scalar::one();
+                });
+            }
+            true
+        });
+        let messengers = vec![messenger; parties.len()];
+        let results = keygen_over(
+            &messengers,
+            SessionId::random(),
+            &arch,
+            &HashMap::new(),
+            &parties,
+        )
+        .await;
+        for result in results.into_iter() {
+            let err = result.err().unwrap();
+            let blame = Blame::of(&err).unwrap();
+            assert_eq!(blame.culprits(), vec![dealer]);
+            assert!(matches!(
+                blame.evidence[&dealer],
+                Evidence::InvalidZkp { .. }
+            ));
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn chain_code_is_joint_and_older_keystores_derive_as_before() {
+        let messenger = MemMessenger::default();
+        let arch = key_arch(&[(1, 2, 3), (2, 2, 3)]);
+        let keystores =
+            keygen::<Ed25519>(&messenger, &arch, &paired(&arch), FrostMode::Rfc9591).await;
+        let pk = keystores[0].pk().unwrap();
+        let chain_code = keystores[0].chain_code.unwrap();
+        for keystore in keystores.iter() {
+            assert_eq!(keystore.chain_code, Some(chain_code));
+        }
+        assert_ne!(chain_code, eval_chain_code(&pk));
+
+        // A keystore from before the joint chain code derives from the pk alone.
+        let mut older = keystores[0].clone();
+        older.chain_code = None;
+        assert_eq!(
+            chain_code_of::<Ed25519>(&older).unwrap(),
+            eval_chain_code(&pk)
+        );
+        let scheme = HdScheme::Legacy;
+        let (_, child_pk) = scheme.derive("m/1", &pk, &eval_chain_code(&pk)).unwrap();
+        assert_eq!(
+            derive_child::<Ed25519>(&older, "m/1", &scheme).unwrap().0,
+            child_pk
+        );
+        assert_ne!(
+            derive_child::<Ed25519>(&keystores[0], "m/1", &scheme)
+                .unwrap()
+                .0,
+            child_pk
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn weighted_member_counts_as_its_weight() {
+        let messenger = MemMessenger::default();
+        let arch = key_arch(&[(1, 3, 3)]);
+        let parties = paired(&arch);
+        let heavy = MpcAddr::new(1, 1);
+        let weights = HashMap::from([(heavy, 2)]);
+        let messengers = vec![messenger.clone(); parties.len()];
+        let results =
+            keygen_over(&messengers, SessionId::random(), &arch, &weights, &parties).await;
+        let keystores: Vec<KeyStore> = results.into_iter().map(|ks| ks.unwrap()).collect();
+        let pk = keystores[0].pk().unwrap();
+        assert_eq!(keystores[0].weight(&heavy), 2);
+        assert_eq!(keystores[0].sub_xi_pergroup[&1].len(), 1);
+
+        // 1.1 and 1.2 hold 3 sub-shares together, while 1.1 alone holds 2.
+        let signers = [keystores[0].clone(), keystores[1].clone()];
+        let ses_arch_12 = ses_arch(&[(1, &[1, 2])]);
+        for sig in
+            sign::<Ed25519>(&messenger, &ses_arch_12, &signers, b"w", &SignMode::Ed25519).await
+        {
+            verify_signature(&sig.unwrap(), &pk).unwrap();
+        }
+        let signers = [keystores[0].clone()];
+        let ses_arch_1 = ses_arch(&[(1, &[1])]);
+        for sig in
+            sign::<Ed25519>(&messenger, &ses_arch_1, &signers, b"w", &SignMode::Ed25519).await
+        {
+            assert!(sig.is_err());
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn equivocation_is_blamed_by_members_and_observers() {
+        let arch = key_arch(&[(1, 2, 3), (2, 2, 2)]);
+        let (fooled, dealer) = (MpcAddr::new(1, 1), MpcAddr::new(1, 3));
+        let honest = MemMessenger::default();
+        let fooled_view = honest.tampered(move |topic, src, _, value| {
+            if topic.ends_with("/dkg_com") && src == dealer {
+                retype(value, |com: &mut DkgCom<Ed25519>| {
+                    com.com.zkp.sigma += Scalar::one();
+                });
+            }
+            true
+        });
+
+        // Members of group 1 abort at the echo round.
+        let sid = SessionId::random();
+        let parties: Vec<Vec<MpcAddr>> = (1..=3).map(|i| vec![MpcAddr::new(1, i)]).collect();
+        let messengers = [fooled_view, honest.clone(), honest.clone()];
+        let results = keygen_over(&messengers, sid, &arch, &HashMap::new(), &parties).await;
+        let mut blames: Vec<Blame> = Vec::new();
+        for result in results.into_iter() {
+            blames.push(Blame::of(&result.err().unwrap()).unwrap().clone());
+        }
+
+        // An observer from group 2 checks the same echoes.
+        let messenger = honest.scoped(sid);
+        let (members, gcast_id) = (arch[&1].1.clone(), MpcAddr::gcast_id(1));
+        let com_dict: HashMap<MpcAddr, DkgCom<Ed25519>> = messenger
+            .gather("dkg_com", &members, gcast_id)
+            .await
+            .unwrap();
+        let context = format!("{}/{}", sid, "test");
+        let err = dkg_echo(&messenger, None, gcast_id, &members, &context, &com_dict)
+            .await
+            .unwrap_err();
+        blames.push(Blame::of(&err).unwrap().clone());
+
+        // The fooled member sees the others disagree, the rest see it disagree.
+        assert_eq!(blames[0].culprits(), vec![MpcAddr::new(1, 2), dealer]);
+        for blame in blames[1..].iter() {
+            assert_eq!(blame.culprits(), vec![fooled, dealer]);
+            assert!(matches!(
+                blame.evidence[&dealer],
+                Evidence::Equivocation { echoer, .. } if echoer == fooled
+            ));
+            assert!(matches!(
+                blame.evidence[&fooled],
+                Evidence::InconsistentEcho { dealer: j, .. } if j == dealer
+            ));
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn answered_complaint_keeps_every_dealer() {
+        let arch = key_arch(&[(1, 2, 3)]);
+        let parties = paired(&arch);
+        let messenger = bad_dealer(MpcAddr::new(1, 3), MpcAddr::new(1, 1), false);
+        let messengers = vec![messenger; parties.len()];
+        let results = keygen_over(
+            &messengers,
+            SessionId::random(),
+            &arch,
+            &HashMap::new(),
+            &parties,
+        )
+        .await;
+        let keystores: Vec<KeyStore> = results.into_iter().map(|ks| ks.unwrap()).collect();
+        let pk = keystores[0].pk().unwrap();
+        for keystore in keystores.iter() {
+            assert_eq!(keystore.pk().unwrap(), pk);
+            assert!(keystore.disqualified.is_empty());
+            assert_eq!(keystore.vss_com_grid[&1].len(), 3);
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn bad_reveal_disqualifies_the_dealer() {
+        let arch = key_arch(&[(1, 2, 3)]);
+        let parties = paired(&arch);
+        let dealer = MpcAddr::new(1, 3);
+        let messenger = bad_dealer(dealer, MpcAddr::new(1, 1), true);
+        let messengers = vec![messenger; parties.len()];
+        let results = keygen_over(
+            &messengers,
+            SessionId::random(),
+            &arch,
+            &HashMap::new(),
+            &parties,
+        )
+        .await;
+        let keystores: Vec<KeyStore> = results.into_iter().map(|ks| ks.unwrap()).collect();
+        let pk = keystores[0].pk().unwrap();
+        for keystore in keystores.iter() {
+            assert_eq!(keystore.pk().unwrap(), pk);
+            assert_eq!(keystore.disqualified, HashSet::from([dealer]));
+            assert!(!keystore.vss_com_grid[&1].contains_key(&dealer));
+            assert!(keystore.channel_pk(&dealer).is_some());
+        }
+
+        // The disqualified dealer still holds a share of the key.
+        let ses_arch = ses_arch(&[(1, &[1, 3])]);
+        let signers = [keystores[0].clone(), keystores[2].clone()];
+        let messenger = MemMessenger::default();
+        for sig in sign::<Ed25519>(
+            &messenger,
+            &ses_arch,
+            &signers,
+            b"qualified",
+            &SignMode::Ed25519,
+        )
+        .await
+        {
+            verify_signature(&sig.unwrap(), &pk).unwrap();
+        }
+    }
+}
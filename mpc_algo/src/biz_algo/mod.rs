@@ -3,8 +3,38 @@ pub use keygen::*;
 
 mod aes;
 
+mod auth;
+pub use auth::*;
+
 mod sign;
 pub use sign::*;
 
+mod preprocess;
+pub use preprocess::*;
+
+mod roast;
+pub use roast::*;
+
+mod refresh;
+pub use refresh::*;
+
+mod repair;
+pub use repair::*;
+
+mod reshare;
+pub use reshare::*;
+
+mod import;
+pub use import::*;
+
+mod export;
+pub use export::*;
+
 mod hd;
 pub use hd::*;
+
+mod policy;
+pub use policy::*;
+
+#[cfg(test)]
+pub(crate) mod testkit;
@@ -0,0 +1,425 @@
+use std::collections::{HashMap, HashSet};
+
+use curve25519_dalek::{constants, edwards::EdwardsPoint, scalar::Scalar, traits::Identity};
+use libexception::*;
+use mpc_spec::*;
+use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroize;
+
+use super::aes::{aes_decrypt, aes_encrypt, channel_aad, channel_key, AEAD};
+use super::{assert_unweighted, KeyStore};
+use crate::frost::{
+    deal_policy_share, deal_vss_share, eval_policy_com, eval_xi_com, lagrange_lambda,
+    merge_vss_share, policy_coefs, Blame, Ciphersuite, Ed25519, Evidence,
+};
+
+/// Commitments of a dealing along the access policy.
+#[derive(Clone, Serialize, Deserialize)]
+struct PolicyDeal {
+    gate_coms: Vec<Vec<EdwardsPoint>>,
+    group_coms: HashMap<u16, Vec<EdwardsPoint>>,
+}
+
+/// Coefficient of each group of a session in the secret, by which the
+/// Lagrange coefficients within the group are multiplied. Without a policy,
+/// every group of the keystore must take part, with a coefficient of one.
+pub(crate) fn group_coefs<C: Ciphersuite>(
+    keystore: &KeyStore<C>,
+    groups: &HashSet<u16>,
+) -> Outcome<HashMap<u16, C::Scalar>> {
+    match keystore.policy.as_ref() {
+        Some(policy) => policy_coefs::<C>(policy, groups),
+        None => {
+            for gid in keystore.vss_com_grid.keys() {
+                assert_throw!(
+                    groups.contains(gid),
+                    "NotQualified",
+                    format!("Group {} is absent", gid)
+                );
+            }
+            Ok(groups
+                .iter()
+                .map(|gid| (*gid, C::scalar_from_u64(1)))
+                .collect())
+        }
+    }
+}
+
+/// Share the key across groups by `policy`, and then within each group by its
+/// own threshold, keeping `MultiShard::pk()` unchanged. Once done, any set of
+/// groups satisfying `policy` can sign without the others.
+///
+/// `holders` must satisfy the current policy of the keystore, or hold every
+/// group if there is none, with at least `th` members of each group. Each of
+/// them deals its weighted share, first along the gates of `policy`, then
+/// among the members of every group. Every keystore holder must run this
+/// function, since all of its shards are replaced.
+///
+/// The commitments of the new shares of each group are kept under
+/// `MpcAddr::gcast_id(gid)`, as `algo_reshare()` does, while the channel keys
+/// of its members move to `channel_pk_grid`.
+pub async fn algo_set_policy(
+    messenger: &impl Messenger,
+    sid: SessionId,
+    policy: &AccessPolicy,
+    holders: &HashMap<u16 /*group_id*/, HashSet<MpcAddr>>,
+    keystore: &KeyStore,
+) -> Outcome<KeyStore> {
+    let messenger = &messenger.scoped(sid);
+    assert_unweighted(keystore).catch_()?;
+    policy.validate().catch_()?;
+    let mut keystore = keystore.clone();
+    let bcast_id = MpcAddr::bcast_id();
+    let mut rng = OsRng;
+    let mut group_ids_asc: Vec<u16> = keystore.vss_com_grid.keys().cloned().collect();
+    group_ids_asc.sort();
+    let mut policy_groups = policy.groups();
+    policy_groups.sort();
+    assert_throw!(
+        policy_groups == group_ids_asc,
+        "InvalidPolicy",
+        "The policy must name every group of the keystore"
+    );
+    let main_pk = keystore.pk().catch_()?;
+    let epoch = keystore.epoch + 1;
+    let com_topic = format!("policy_com/{}", epoch);
+    let share_topic = format!("policy_share/{}", epoch);
+
+    // Holders of the groups which the current policy chooses deal.
+    let holder_groups: HashSet<u16> = holders.keys().cloned().collect();
+    let coefs = group_coefs::<Ed25519>(&keystore, &holder_groups).catch_()?;
+    let mut dealers: HashSet<MpcAddr> = HashSet::new();
+    for (gid, members) in holders.iter() {
+        if coefs.get(gid) == Some(&Scalar::zero()) {
+            continue;
+        }
+        let th = keystore.th(*gid).catch_()?;
+        assert_throw!(
+            members.len() >= th,
+            "NotEnoughHolders",
+            format!("{} holders in group {}, {} needed", members.len(), gid, th)
+        );
+        for id in members.iter() {
+            assert_throw!(
+                id.group_id() == *gid && id.member_id() != 0,
+                format!("{} is not a member of group {}", id, gid)
+            );
+        }
+        dealers.extend(members.iter().cloned());
+    }
+
+    // Members of each group, along with their channel keys.
+    let mut th_dict: HashMap<u16, usize> = HashMap::new();
+    let mut member_grid: HashMap<u16, HashSet<MpcAddr>> = HashMap::new();
+    let mut channel_dict: HashMap<MpcAddr, EdwardsPoint> = HashMap::new();
+    for gid in group_ids_asc.iter() {
+        let members = keystore.members(*gid);
+        for j in members.iter() {
+            channel_dict.insert(*j, keystore.channel_pk(j).ifnone_()?);
+        }
+        member_grid.insert(*gid, members);
+        th_dict.insert(*gid, keystore.th(*gid).catch_()?);
+    }
+    let mut whoami_asc: Vec<MpcAddr> = keystore.ids.iter().cloned().collect();
+    whoami_asc.sort();
+
+    // Dealers share $c_g \lambda_i x_i$ along the policy, then within each group.
+    for my_id in whoami_asc.iter().filter(|id| dealers.contains(id)) {
+        let gid = my_id.group_id();
+        let channel_sk = keystore.channel_sk(gid).ifnone_()?;
+        let x_i = keystore.xi_pergroup.get(&gid).ifnone_()?;
+        let signers = holders.get(&gid).ifnone_()?;
+        let coef = coefs.get(&gid).ifnone_()?;
+        let mut w_i = coef * lagrange_lambda(*my_id, signers).catch_()? * x_i;
+        let (gate_coms, mut values) = deal_policy_share(&w_i, policy, &mut rng);
+        w_i.zeroize();
+
+        let mut deal = PolicyDeal {
+            gate_coms,
+            group_coms: HashMap::new(),
+        };
+        for h in group_ids_asc.iter() {
+            let members = member_grid.get(h).ifnone_()?;
+            let value = values.get(h).ifnone_()?;
+            let th = *th_dict.get(h).ifnone_()?;
+            let _obj: _ = deal_vss_share(value, members, th, &mut rng).catch_()?;
+            let shares_com: Vec<EdwardsPoint> = _obj.0;
+            let mut shares: HashMap<MpcAddr, Scalar> = _obj.1;
+            for (j, share) in shares.iter() {
+                let shared = channel_dict.get(j).ifnone_()? * channel_sk;
+                let aes_key = channel_key(&shared.compress().to_bytes());
+                let aad = channel_aad(*my_id, *j, &share_topic, &sid.to_string());
+                let aead_pack = aes_encrypt(&aes_key, &share.to_bytes(), &aad).catch_()?;
+                messenger
+                    .send(&share_topic, *my_id, *j, &aead_pack)
+                    .await
+                    .catch_()?;
+            }
+            for x in shares.values_mut() {
+                x.zeroize();
+            }
+            deal.group_coms.insert(*h, shares_com);
+        }
+        for x in values.values_mut() {
+            x.zeroize();
+        }
+        messenger
+            .send(&com_topic, *my_id, bcast_id, &deal)
+            .await
+            .catch_()?;
+    }
+
+    // Every dealing must hide the weighted share of its dealer.
+    let deal_dict: HashMap<MpcAddr, PolicyDeal> = messenger
+        .gather(&com_topic, &dealers, bcast_id)
+        .await
+        .catch_()?;
+    let mut blame: Blame = Blame::default();
+    for (i, deal) in deal_dict.iter() {
+        let gid = i.group_id();
+        let vss_com_dict = keystore.vss_com_grid.get(&gid).ifnone_()?;
+        let signers = holders.get(&gid).ifnone_()?;
+        let coef = coefs.get(&gid).ifnone_()?;
+        let root =
+            eval_xi_com(*i, vss_com_dict) * (coef * lagrange_lambda(*i, signers).catch_()?);
+        let valid = match eval_policy_com(&root, policy, &deal.gate_coms) {
+            Some(value_coms) => {
+                deal.group_coms.len() == group_ids_asc.len()
+                    && group_ids_asc.iter().all(|h| {
+                        match (deal.group_coms.get(h), value_coms.get(h), th_dict.get(h)) {
+                            (Some(com), Some(value_com), Some(th)) => {
+                                com.len() == *th && com[0] == *value_com
+                            }
+                            _ => false,
+                        }
+                    })
+            }
+            None => false,
+        };
+        if !valid {
+            let (gate_coms, group_coms) = (deal.gate_coms.clone(), deal.group_coms.clone());
+            blame.accuse(
+                *i,
+                Evidence::InvalidPolicyShare {
+                    gate_coms,
+                    group_coms,
+                },
+            );
+        }
+    }
+    blame.throw_if_any()?;
+
+    // Collect my new shares
+    let mut new_xi_pergroup: HashMap<u16, Scalar> = HashMap::new();
+    for my_id in whoami_asc.iter() {
+        let gid = my_id.group_id();
+        let channel_sk = keystore.channel_sk(gid).ifnone_()?;
+        let aead_dict: HashMap<MpcAddr, AEAD> = messenger
+            .gather(&share_topic, &dealers, *my_id)
+            .await
+            .catch_()?;
+        let mut party_shares: HashMap<MpcAddr, Scalar> = HashMap::new();
+        let mut blame: Blame = Blame::default();
+        for (i, aead_pack) in aead_dict.iter() {
+            let shared = channel_dict.get(i).ifnone_()? * channel_sk;
+            let aes_key = channel_key(&shared.compress().to_bytes());
+            let aad = channel_aad(*i, *my_id, &share_topic, &sid.to_string());
+            let share = match aes_decrypt(&aes_key, aead_pack, &aad) {
+                Ok(mut out) if out.len() == 32 => {
+                    let mut out_arr = [0u8; 32];
+                    out_arr.copy_from_slice(&out);
+                    let share = Scalar::from_canonical_bytes(out_arr);
+                    out.zeroize();
+                    out_arr.zeroize();
+                    share
+                }
+                _ => None,
+            };
+            match share {
+                Some(share) => {
+                    party_shares.insert(*i, share);
+                }
+                None => {
+                    let ciphertext = aead_pack.ciphertext.clone();
+                    let nonce = aead_pack.tag.clone();
+                    blame.accuse(*i, Evidence::UndecryptableShare { ciphertext, nonce });
+                }
+            }
+        }
+        blame.throw_if_any()?;
+        let mut com_dict: HashMap<MpcAddr, Vec<EdwardsPoint>> = HashMap::new();
+        for (i, deal) in deal_dict.iter() {
+            com_dict.insert(*i, deal.group_coms.get(&gid).ifnone_()?.clone());
+        }
+        let x_i = merge_vss_share(&party_shares, &com_dict, *my_id).catch_()?;
+        for x in party_shares.values_mut() {
+            x.zeroize();
+        }
+        new_xi_pergroup.insert(gid, x_i);
+    }
+
+    // New commitments of each group, apart from the channel keys of its members
+    for h in group_ids_asc.iter() {
+        let th = *th_dict.get(h).ifnone_()?;
+        let mut agg_com: Vec<EdwardsPoint> = vec![EdwardsPoint::identity(); th];
+        for deal in deal_dict.values() {
+            let com = deal.group_coms.get(h).ifnone_()?;
+            for (c, dc) in agg_com.iter_mut().zip(com.iter()) {
+                *c += dc;
+            }
+        }
+        let mut vss_com_dict: HashMap<MpcAddr, Vec<EdwardsPoint>> = HashMap::new();
+        let mut channel_pk_dict: HashMap<MpcAddr, EdwardsPoint> = HashMap::new();
+        for j in member_grid.get(h).ifnone_()?.iter() {
+            channel_pk_dict.insert(*j, *channel_dict.get(j).ifnone_()?);
+        }
+        vss_com_dict.insert(MpcAddr::gcast_id(*h), agg_com);
+        keystore.vss_com_grid.insert(*h, vss_com_dict);
+        keystore.channel_pk_grid.insert(*h, channel_pk_dict);
+    }
+    for (gid, x_i) in new_xi_pergroup.into_iter() {
+        keystore.xi_pergroup.insert(gid, x_i);
+    }
+    keystore.policy = Some(policy.clone());
+    keystore.policy_pk = Some(main_pk);
+    keystore.epoch = epoch;
+
+    // sanity check
+    let all_groups: HashSet<u16> = group_ids_asc.iter().cloned().collect();
+    let mut pk = EdwardsPoint::identity();
+    for (gid, coef) in group_coefs::<Ed25519>(&keystore, &all_groups)
+        .catch_()?
+        .iter()
+    {
+        let vss_com_dict = keystore.vss_com_grid.get(gid).ifnone_()?;
+        let group_pk: EdwardsPoint = vss_com_dict.values().map(|com| com[0]).sum();
+        pk += group_pk * coef;
+    }
+    assert_throw!(pk == main_pk, "Public key changed");
+    for my_id in whoami_asc.iter() {
+        let gid = my_id.group_id();
+        let x_i = keystore.xi_pergroup.get(&gid).ifnone_()?;
+        let vss_com_dict = keystore.vss_com_grid.get(&gid).ifnone_()?;
+        assert_throw!(
+            eval_xi_com(*my_id, vss_com_dict) == &constants::ED25519_BASEPOINT_TABLE * x_i,
+            "New share does not match the commitments"
+        );
+    }
+
+    Ok(keystore)
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::future::join_all;
+
+    use super::*;
+    use crate::biz_algo::testkit::*;
+    use crate::biz_algo::{algo_sign, derive_child, HdScheme, SignMode};
+    use crate::frost::verify_signature;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn policy_keeps_pk_and_chooses_signers() {
+        let messenger = MemMessenger::default();
+        let arch = key_arch(&[(1, 2, 3), (2, 2, 3), (3, 2, 3)]);
+        // One party per member, so that groups can sign without each other.
+        let mut parties: Vec<Vec<MpcAddr>> = arch
+            .values()
+            .flat_map(|(_, members)| members.iter().map(|id| vec![*id]))
+            .collect();
+        parties.sort();
+        let old_keystores =
+            keygen::<Ed25519>(&messenger, &arch, &parties, FrostMode::Rfc9591).await;
+        let pk = old_keystores[0].pk().unwrap();
+
+        let policy = AccessPolicy::or(vec![
+            AccessPolicy::Group(1),
+            AccessPolicy::and(vec![AccessPolicy::Group(2), AccessPolicy::Group(3)]),
+        ]);
+        let holders: SesArch = arch
+            .iter()
+            .map(|(gid, (_, members))| (*gid, members.clone()))
+            .collect();
+        let sid = SessionId::random();
+        let tasks = old_keystores
+            .iter()
+            .map(|keystore| algo_set_policy(&messenger, sid, &policy, &holders, keystore));
+        let keystores: Vec<KeyStore> = join_all(tasks)
+            .await
+            .into_iter()
+            .map(|keystore| keystore.unwrap())
+            .collect();
+        for keystore in keystores.iter() {
+            assert_eq!(keystore.pk().unwrap(), pk);
+            assert_eq!(keystore.policy.as_ref(), Some(&policy));
+        }
+
+        let signers_of = |spec: &[(u16, &[u16])]| -> Vec<KeyStore> {
+            let mut signers = Vec::new();
+            for (gid, member_ids) in spec.iter() {
+                for i in member_ids.iter() {
+                    let id = MpcAddr::new(*gid, *i);
+                    let keystore = keystores.iter().find(|ks| ks.ids.contains(&id));
+                    signers.push(keystore.unwrap().clone());
+                }
+            }
+            signers
+        };
+        for spec in [
+            vec![(1, [1, 2].as_slice())],
+            vec![(2, [1, 3].as_slice()), (3, [2, 3].as_slice())],
+        ] {
+            let ses_arch = ses_arch(&spec);
+            let signers = signers_of(&spec);
+            for sig in sign::<Ed25519>(
+                &messenger,
+                &ses_arch,
+                &signers,
+                b"policy",
+                &SignMode::Ed25519,
+            )
+            .await
+            {
+                verify_signature(&sig.unwrap(), &pk).unwrap();
+            }
+        }
+
+        // Group 2 alone satisfies neither branch.
+        let spec = [(2, [1, 2].as_slice())];
+        for sig in sign::<Ed25519>(
+            &messenger,
+            &ses_arch(&spec),
+            &signers_of(&spec),
+            b"policy",
+            &SignMode::Ed25519,
+        )
+        .await
+        {
+            let err = sig.err().unwrap();
+            assert!(err.to_string().contains("\"NotQualified\""));
+        }
+
+        // A child key is tweaked along the policy as well.
+        let spec = [(1, [2, 3].as_slice())];
+        let (child_pk, _) =
+            derive_child::<Ed25519>(&keystores[0], "m/7", &HdScheme::Legacy).unwrap();
+        let (sid, ses_arch) = (SessionId::random(), ses_arch(&spec));
+        let signers = signers_of(&spec);
+        let tasks = signers.iter().map(|keystore| {
+            algo_sign::<Ed25519>(
+                &messenger,
+                sid,
+                &ses_arch,
+                "m/7",
+                b"child",
+                &SignMode::Ed25519,
+                &HdScheme::Legacy,
+                keystore,
+            )
+        });
+        for sig in join_all(tasks).await {
+            verify_signature(&sig.unwrap(), &child_pk).unwrap();
+        }
+    }
+}
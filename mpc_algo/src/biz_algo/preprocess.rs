@@ -0,0 +1,273 @@
+use libexception::*;
+use mpc_spec::*;
+use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use super::{sign_with_nonces, DerivationScheme, EpochMsg, KeyStore, SignMode, Signature};
+use crate::frost::{
+    sign_preprocess, Blame, Ed25519, Evidence, SigningCommitmentPair, SigningNoncePair,
+};
+
+/// Nonces committed to ahead of signing.
+/// The secret nonces must stay local and be used at most once, so the pool
+/// should be persisted right after each `algo_sign_pooled()` takes from it,
+/// and never be restored from an older copy.
+/// Not `Clone`, as a copy would hand out every nonce a second time.
+#[derive(Default, Serialize, Deserialize)]
+pub struct NoncePool {
+    /// Session id of the preprocessing, which namespaces every signing with the pool.
+    pub id: SessionId,
+    /// Keystore epoch of every preprocessing shard. A refresh invalidates the pool.
+    pub epoch: u64,
+    /// Secret nonce pairs of my shards, indexed by slot.
+    nonces: HashMap<MpcAddr, HashMap<usize, SigningNoncePair>>,
+    /// Published commitments of every preprocessing shard, indexed by slot.
+    pub coms: HashMap<MpcAddr, Vec<SigningCommitmentPair>>,
+}
+
+impl NoncePool {
+    /// Slots not yet consumed by my shard `id`, in ascending order.
+    pub fn unused_slots(&self, id: MpcAddr) -> Vec<usize> {
+        let mut slots: Vec<usize> = match self.nonces.get(&id) {
+            Some(nonce_dict) => nonce_dict.keys().cloned().collect(),
+            None => Vec::new(),
+        };
+        slots.sort();
+        slots
+    }
+
+    fn take(&mut self, id: MpcAddr, slot: usize) -> Outcome<SigningNoncePair> {
+        let nonce_dict = self.nonces.get_mut(&id).ifnone_()?;
+        let nonce = nonce_dict.remove(&slot).ifnone(
+            "NonceReuse",
+            format!("Slot {} of {} is consumed or does not exist", slot, id),
+        )?;
+        Ok(nonce)
+    }
+}
+
+/// Generate `count` nonce pairs for each of my shards, publish their
+/// commitments, and collect those of the other shards in `pre_arch`.
+/// Any later signing session with the returned pool must be a subset of `pre_arch`.
+/// `sid` becomes the id of the pool, and must be fresh like any session id.
+pub async fn algo_sign_preprocess(
+    messenger: &impl Messenger,
+    sid: SessionId,
+    pre_arch: &HashMap<u16, HashSet<MpcAddr>>,
+    count: usize,
+    keystore: &KeyStore,
+) -> Outcome<NoncePool> {
+    assert_throw!(count > 0, "Nothing to preprocess");
+    let messenger = &messenger.scoped(sid);
+    let bcast_id = MpcAddr::bcast_id();
+    let mut whoami_asc: Vec<MpcAddr> = keystore.ids.iter().cloned().collect();
+    whoami_asc.sort();
+    let mut rng = OsRng;
+    let mut group_ids_asc: Vec<u16> = pre_arch.keys().cloned().collect();
+    group_ids_asc.sort();
+
+    let mut pool = NoncePool {
+        id: sid,
+        epoch: keystore.epoch,
+        ..Default::default()
+    };
+    for my_id in whoami_asc.iter() {
+        let x_i = keystore.xi_pergroup.get(&my_id.group_id()).ifnone_()?;
+        let mut nonce_com_list: Vec<SigningCommitmentPair> = Vec::with_capacity(count);
+        let mut nonce_dict: HashMap<usize, SigningNoncePair> = HashMap::new();
+        for slot in 0..count {
+            let _obj: _ = sign_preprocess(keystore.mode, x_i, &mut rng).catch_()?;
+            nonce_com_list.push(_obj.0);
+            nonce_dict.insert(slot, _obj.1);
+        }
+        pool.nonces.insert(*my_id, nonce_dict);
+        let msg = EpochMsg::new::<Ed25519>(keystore, nonce_com_list);
+        messenger
+            .send("nonce_pool", *my_id, bcast_id, &msg)
+            .await
+            .catch_()?;
+        println!("{} published {} nonce commitments", my_id, count);
+    }
+
+    for gid in group_ids_asc.iter() {
+        let group_members = pre_arch.get(gid).ifnone_()?;
+        let obj: HashMap<MpcAddr, EpochMsg<Vec<SigningCommitmentPair>>> = messenger
+            .gather("nonce_pool", group_members, bcast_id)
+            .await
+            .catch_()?;
+        let mut blame: Blame = Blame::default();
+        for (j, msg) in obj.iter() {
+            msg.check(keystore, *j, &mut blame);
+            let got = msg.body.len();
+            if got != count {
+                blame.accuse(
+                    *j,
+                    Evidence::WrongItemCount {
+                        expected: count,
+                        got,
+                    },
+                );
+            }
+        }
+        blame.throw_if_any()?;
+        for (j, msg) in obj.into_iter() {
+            pool.coms.insert(j, msg.body);
+        }
+    }
+
+    Ok(pool)
+}
+
+/// Sign with nonces from the pool, which only takes the `sign_resp` round.
+/// All signers must agree on `slot`, e.g. by a counter kept by the caller.
+/// The slot is consumed even if signing fails afterwards.
+pub async fn algo_sign_pooled(
+    messenger: &impl Messenger,
+    sid: SessionId,
+    ses_arch: &HashMap<u16, HashSet<MpcAddr>>,
+    drv_path: &str,
+    msg: &[u8],
+    sign_mode: &SignMode,
+    hd_scheme: &impl DerivationScheme,
+    keystore: &KeyStore,
+    pool: &mut NoncePool,
+    slot: usize,
+) -> Outcome<Signature> {
+    let messenger = &messenger.scoped(sid);
+    sign_mode.validate().catch_()?;
+    assert_throw!(
+        pool.epoch == keystore.epoch,
+        "The pool was preprocessed before the last refresh"
+    );
+
+    let mut my_nonce_dict: HashMap<MpcAddr, Vec<SigningNoncePair>> = HashMap::new();
+    for my_id in keystore.ids.iter() {
+        let nonce = pool.take(*my_id, slot).catch_()?;
+        my_nonce_dict.insert(*my_id, vec![nonce]);
+    }
+
+    let mut nonce_com_dict: HashMap<MpcAddr, SigningCommitmentPair> = HashMap::new();
+    for group_members in ses_arch.values() {
+        for j in group_members.iter() {
+            let nonce_com_list = pool.coms.get(j).ifnone(
+                "NoCommitment",
+                format!("{} did not take part in preprocessing", j),
+            )?;
+            let nonce_com = nonce_com_list.get(slot).ifnone_()?;
+            nonce_com_dict.insert(*j, nonce_com.clone());
+        }
+    }
+
+    // Slots restart at 0 in every pool, so only the pool id and the slot
+    // together tell the sessions apart.
+    let topic = format!("sign_resp/{}/{}", pool.id, slot);
+    let mut sigs = sign_with_nonces(
+        messenger,
+        &topic,
+        ses_arch,
+        &[(drv_path, msg)],
+        sign_mode,
+        hd_scheme,
+        keystore,
+        my_nonce_dict,
+        vec![nonce_com_dict],
+    )
+    .await
+    .catch_()?;
+    let sig = sigs.pop().ifnone_()?;
+    Ok(sig)
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::future::join_all;
+
+    use super::*;
+    use crate::biz_algo::testkit::*;
+    use crate::biz_algo::{chain_code_of, HdScheme};
+    use crate::frost::{verify_signature, Ciphersuite};
+
+    async fn preprocess_all(
+        messenger: &MemMessenger,
+        pre_arch: &SesArch,
+        count: usize,
+        keystores: &[KeyStore],
+    ) -> Vec<NoncePool> {
+        let sid = SessionId::random();
+        let tasks = keystores
+            .iter()
+            .map(|keystore| algo_sign_preprocess(messenger, sid, pre_arch, count, keystore));
+        join_all(tasks)
+            .await
+            .into_iter()
+            .map(|pool| pool.unwrap())
+            .collect()
+    }
+
+    async fn sign_pooled_all(
+        messenger: &MemMessenger,
+        sid: SessionId,
+        ses_arch: &SesArch,
+        keystores: &[KeyStore],
+        pools: &mut [NoncePool],
+        slot: usize,
+    ) -> Vec<Outcome<Signature>> {
+        let tasks = keystores
+            .iter()
+            .zip(pools.iter_mut())
+            .map(|(keystore, pool)| {
+                algo_sign_pooled(
+                    messenger,
+                    sid,
+                    ses_arch,
+                    "m/3",
+                    b"pooled",
+                    &SignMode::Ed25519,
+                    &HdScheme::Legacy,
+                    keystore,
+                    pool,
+                    slot,
+                )
+            });
+        join_all(tasks).await
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn pooled_signature_verifies_and_slot_is_not_reused() {
+        let messenger = MemMessenger::default();
+        let arch = key_arch(&[(1, 2, 3)]);
+        let parties = paired(&arch);
+        let keystores = keygen::<Ed25519>(&messenger, &arch, &parties, FrostMode::Rfc9591).await;
+        let pre_arch = ses_arch(&[(1, &[1, 3])]);
+        let ses_arch = ses_arch(&[(1, &[1, 3])]);
+        let signers = [keystores[0].clone(), keystores[2].clone()];
+        let mut pools = preprocess_all(&messenger, &pre_arch, 2, &signers).await;
+        let mut other_pools = preprocess_all(&messenger, &pre_arch, 2, &signers).await;
+        let (_, child_pk) = HdScheme::Legacy
+            .derive(
+                "m/3",
+                &keystores[0].pk().unwrap(),
+                &chain_code_of::<Ed25519>(&keystores[0]).unwrap(),
+            )
+            .unwrap();
+
+        // Both pools sign at slot 0, even within the same session, and neither
+        // may read the responses of the other.
+        let sid = SessionId::random();
+        for pools in [&mut pools, &mut other_pools] {
+            for sig in sign_pooled_all(&messenger, sid, &ses_arch, &signers, pools, 0).await {
+                let sig = sig.unwrap();
+                verify_signature(&sig, &child_pk).unwrap();
+                Ed25519::cross_check(&sig, &child_pk).unwrap();
+            }
+        }
+
+        let my_id = MpcAddr::new(1, 1);
+        assert_eq!(pools[0].unused_slots(my_id), vec![1]);
+        let err = pools[0].take(my_id, 0).err().unwrap();
+        assert_eq!(err.get_name(), "NonceReuse");
+        assert!(pools[0].take(my_id, 1).is_ok());
+        assert!(pools[0].take(my_id, 1).is_err());
+    }
+}
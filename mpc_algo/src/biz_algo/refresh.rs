@@ -0,0 +1,238 @@
+use std::collections::{HashMap, HashSet};
+
+use curve25519_dalek::edwards::EdwardsPoint;
+use curve25519_dalek::{constants, scalar::Scalar};
+use libexception::*;
+use mpc_spec::*;
+use rand::rngs::OsRng;
+use zeroize::Zeroize;
+
+use super::{assert_unweighted, exchange_shares, keystore_channel_keys, KeyStore};
+use crate::frost::{
+    eval_xi_com, generate_vss_share, merge_vss_share, refresh_validate_peers, Ed25519,
+};
+
+/// Proactively refresh the shares of every group in `key_arch`, keeping
+/// `MultiShard::pk()` unchanged. Every member of those groups must take part.
+///
+/// Each member deals a polynomial with zero constant term; its shares are
+/// added to `xi_pergroup`, and its commitment to the dealer's entry of
+/// `vss_com_grid`. The keystore then moves to the next epoch, whose shares
+/// do not combine with those of earlier epochs.
+pub async fn algo_refresh(
+    messenger: &impl Messenger,
+    sid: SessionId,
+    key_arch: &HashMap<u16 /*group_id*/, (usize /*th*/, HashSet<MpcAddr>)>,
+    keystore: &KeyStore,
+) -> Outcome<KeyStore> {
+    let messenger = &messenger.scoped(sid);
+    assert_unweighted(keystore).catch_()?;
+    let mut keystore = keystore.clone();
+    let main_pk = keystore.pk().catch_()?;
+    let epoch = keystore.epoch + 1;
+    // Topics are namespaced by epoch, lest messages of an earlier refresh be read.
+    let com_topic = format!("refresh_com/{}", epoch);
+    let share_topic = format!("refresh_share/{}", epoch);
+    let mut whoami_asc: Vec<MpcAddr> = keystore.ids.iter().cloned().collect();
+    whoami_asc.sort();
+    let mut rng = OsRng;
+
+    for (gid, (th, _)) in key_arch.iter() {
+        assert_throw!(
+            *th == keystore.th(*gid).catch_()?,
+            format!("Threshold of group {} differs from the keystore", gid)
+        );
+    }
+
+    let mut refresh_com_grid: HashMap<u16, HashMap<MpcAddr, Vec<EdwardsPoint>>> = HashMap::new();
+    for my_id in whoami_asc.iter() {
+        let my_id = *my_id;
+        let gid = my_id.group_id();
+        let (th, members) = key_arch.get(&gid).ifnone("NoGroup", gid.to_string())?;
+        let gcast_id = MpcAddr::gcast_id(gid);
+
+        // deal a polynomial with zero constant term
+        let _obj: _ =
+            generate_vss_share(&Scalar::zero(), my_id, members, *th, &mut rng).catch_()?;
+        let shares_com: Vec<EdwardsPoint> = _obj.0;
+        let mut shares: HashMap<MpcAddr, Scalar> = _obj.1;
+        messenger
+            .send(&com_topic, my_id, gcast_id, &shares_com)
+            .await
+            .catch_()?;
+        let refresh_com_dict: HashMap<MpcAddr, Vec<EdwardsPoint>> = messenger
+            .gather(&com_topic, members, gcast_id)
+            .await
+            .catch_()?;
+        refresh_validate_peers(&refresh_com_dict, *th).catch_()?;
+
+        let aes_key_dict = keystore_channel_keys::<Ed25519>(&keystore, gid, members).catch_()?;
+        let mut party_shares: HashMap<MpcAddr, Scalar> = exchange_shares(
+            messenger,
+            &share_topic,
+            &sid.to_string(),
+            my_id,
+            members,
+            &aes_key_dict,
+            &shares,
+        )
+        .await
+        .catch_()?;
+        for x in shares.values_mut() {
+            x.zeroize();
+        }
+        drop(shares);
+
+        // update x_i
+        let delta = merge_vss_share(&party_shares, &refresh_com_dict, my_id).catch_()?;
+        for x in party_shares.values_mut() {
+            x.zeroize();
+        }
+        let x_i = keystore.xi_pergroup.get_mut(&gid).ifnone_()?;
+        *x_i += delta;
+
+        refresh_com_grid.insert(gid, refresh_com_dict);
+    }
+
+    // Fetch refresh commitments of members in other groups
+    for (gid, (th, members)) in key_arch.iter() {
+        if refresh_com_grid.contains_key(gid) {
+            continue;
+        }
+        let gcast_id = MpcAddr::gcast_id(*gid);
+        let refresh_com_dict: HashMap<MpcAddr, Vec<EdwardsPoint>> = messenger
+            .gather(&com_topic, members, gcast_id)
+            .await
+            .catch_()?;
+        refresh_validate_peers(&refresh_com_dict, *th).catch_()?;
+        refresh_com_grid.insert(*gid, refresh_com_dict);
+    }
+
+    // Add refresh commitments to those of the same dealer.
+    for (gid, refresh_com_dict) in refresh_com_grid.into_iter() {
+        let vss_com_dict = keystore.vss_com_grid.entry(gid).or_default();
+        for (j, refresh_com) in refresh_com_dict.into_iter() {
+            match vss_com_dict.get_mut(&j) {
+                Some(vss_com) => {
+                    for (c, rc) in vss_com.iter_mut().zip(refresh_com.iter()) {
+                        *c += rc;
+                    }
+                }
+                None => {
+                    vss_com_dict.insert(j, refresh_com);
+                }
+            }
+        }
+    }
+
+    // sanity check
+    assert_throw!(keystore.pk().catch_()? == main_pk, "Public key changed");
+    for my_id in whoami_asc.iter() {
+        let gid = my_id.group_id();
+        let x_i = keystore.xi_pergroup.get(&gid).ifnone_()?;
+        let vss_com_dict = keystore.vss_com_grid.get(&gid).ifnone_()?;
+        assert_throw!(
+            eval_xi_com(*my_id, vss_com_dict) == &constants::ED25519_BASEPOINT_TABLE * x_i,
+            "Refreshed share does not match the commitments"
+        );
+    }
+
+    keystore.epoch = epoch;
+    Ok(keystore)
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::future::join_all;
+
+    use super::*;
+    use crate::biz_algo::aes::AEAD;
+    use crate::biz_algo::testkit::*;
+    use crate::biz_algo::SignMode;
+    use crate::frost::{verify_signature, Blame, Evidence};
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn refresh_keeps_pk_and_moves_epoch() {
+        let messenger = MemMessenger::default();
+        let arch = key_arch(&[(1, 2, 3)]);
+        let old_keystores =
+            keygen::<Ed25519>(&messenger, &arch, &paired(&arch), FrostMode::Rfc9591).await;
+        let pk = old_keystores[0].pk().unwrap();
+
+        let sid = SessionId::random();
+        let tasks = old_keystores
+            .iter()
+            .map(|keystore| algo_refresh(&messenger, sid, &arch, keystore));
+        let new_keystores: Vec<KeyStore> = join_all(tasks)
+            .await
+            .into_iter()
+            .map(|keystore| keystore.unwrap())
+            .collect();
+        for (old, new) in old_keystores.iter().zip(new_keystores.iter()) {
+            assert_eq!(new.pk().unwrap(), pk);
+            assert_eq!(new.epoch, old.epoch + 1);
+            assert_ne!(new.xi_pergroup, old.xi_pergroup);
+        }
+
+        let ses_arch = ses_arch(&[(1, &[1, 3])]);
+        let signers = [new_keystores[0].clone(), new_keystores[2].clone()];
+        for sig in
+            sign::<Ed25519>(&messenger, &ses_arch, &signers, b"new", &SignMode::Ed25519).await
+        {
+            verify_signature(&sig.unwrap(), &pk).unwrap();
+        }
+
+        // An old share mixed with new ones is caught by its epoch.
+        let signers = [old_keystores[0].clone(), new_keystores[2].clone()];
+        let results = sign::<Ed25519>(
+            &messenger,
+            &ses_arch,
+            &signers,
+            b"mixed",
+            &SignMode::Ed25519,
+        )
+        .await;
+        let Err(ex) = &results[1] else {
+            panic!("Signed with shares of two epochs");
+        };
+        let blame = Blame::of(ex).unwrap();
+        assert_eq!(blame.culprits(), vec![MpcAddr::new(1, 1)]);
+        match &blame.evidence[&MpcAddr::new(1, 1)] {
+            Evidence::EpochMismatch { expected, got } => {
+                assert_eq!(
+                    (*expected, *got),
+                    (new_keystores[2].epoch, old_keystores[0].epoch)
+                )
+            }
+            evidence => panic!("Unexpected evidence {:?}", evidence),
+        }
+        assert!(results[0].is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn undecryptable_share_is_blamed() {
+        let messenger = MemMessenger::default();
+        let arch = key_arch(&[(1, 2, 3)]);
+        let keystores =
+            keygen::<Ed25519>(&messenger, &arch, &paired(&arch), FrostMode::Rfc9591).await;
+
+        let (dealer, victim) = (MpcAddr::new(1, 2), MpcAddr::new(1, 3));
+        let garbled = messenger.tampered(move |topic, src, dst, value| {
+            if topic.contains("refresh_share/") && src == dealer && dst == victim {
+                retype(value, |pack: &mut AEAD| pack.ciphertext[0] ^= 1);
+            }
+            true
+        });
+        let sid = SessionId::random();
+        let tasks = keystores
+            .iter()
+            .map(|keystore| algo_refresh(&garbled, sid, &arch, keystore));
+        let results = join_all(tasks).await;
+        let blame = Blame::of(results[2].as_ref().unwrap_err()).unwrap();
+        assert_eq!(blame.culprits(), vec![dealer]);
+        assert!(matches!(
+            blame.evidence[&dealer],
+            Evidence::UndecryptableShare { .. }
+        ));
+    }
+}
@@ -0,0 +1,444 @@
+use std::collections::{HashMap, HashSet};
+
+use curve25519_dalek::{constants, edwards::EdwardsPoint, scalar::Scalar};
+use libexception::*;
+use mpc_spec::*;
+use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroize;
+
+use super::aes::{aes_decrypt, aes_encrypt, channel_aad, channel_key, AEAD};
+use super::{assert_unweighted, exchange_shares, keystore_channel_keys, KeyStore, KeyStorePublic};
+use crate::frost::{eval_xi_com, repair_split, Ed25519};
+
+/// What every helper of a repair consents to. The target draws a `RepairKey`
+/// and shows its approval out of band, e.g. as a QR code, to each helper, who
+/// constructs the same approval on its own. No sub-share leaves a helper
+/// unless all helpers of the group and the target hold the very same approval.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RepairApproval {
+    /// The lost shard.
+    pub target: MpcAddr,
+    /// `MultiShard::pk()` of the key to repair.
+    pub pk: EdwardsPoint,
+    /// New channel key of the target, which the helpers encrypt to, and which
+    /// replaces its old one in every keystore.
+    pub channel_pk: EdwardsPoint,
+}
+
+impl RepairApproval {
+    /// Record the new channel key of the target in `keystore`, as every
+    /// keystore holder must once the repair is done. `algo_repair_help()`
+    /// does so for the helpers.
+    pub fn record(&self, keystore: &KeyStore) -> Outcome<KeyStore> {
+        assert_throw!(
+            self.pk == keystore.pk().catch_()?,
+            "RepairNotApproved",
+            "The approval is for another key"
+        );
+        let mut keystore = keystore.clone();
+        record_channel_pk(&mut keystore, self.target, self.channel_pk).catch_()?;
+        Ok(keystore)
+    }
+}
+
+/// Channel key drawn by the target of a repair, which becomes its own once
+/// `algo_repair_recover()` is done.
+pub struct RepairKey {
+    pub target: MpcAddr,
+    channel_sk: Scalar,
+}
+
+impl RepairKey {
+    pub fn new(target: MpcAddr) -> Self {
+        RepairKey {
+            target,
+            channel_sk: Scalar::random(&mut OsRng),
+        }
+    }
+
+    /// The approval of repairing `target` in the key `pk` with this channel key.
+    pub fn approval(&self, pk: &EdwardsPoint) -> RepairApproval {
+        RepairApproval {
+            target: self.target,
+            pk: *pk,
+            channel_pk: &constants::ED25519_BASEPOINT_TABLE * &self.channel_sk,
+        }
+    }
+}
+
+impl Drop for RepairKey {
+    fn drop(&mut self) {
+        self.channel_sk.zeroize();
+    }
+}
+
+/// Set the channel key of `id` in `keystore`, listing those of the other
+/// members of its group first if the keystore predates `channel_pk_grid`.
+fn record_channel_pk(
+    keystore: &mut KeyStore,
+    id: MpcAddr,
+    channel_pk: EdwardsPoint,
+) -> Outcome<()> {
+    let gid = id.group_id();
+    let members = keystore.members(gid);
+    assert_throw!(
+        members.contains(&id),
+        "NoSuchMember",
+        format!("{} is not a member of group {}", id, gid)
+    );
+    if !keystore.channel_pk_grid.contains_key(&gid) {
+        let mut channel_pk_dict: HashMap<MpcAddr, EdwardsPoint> = HashMap::new();
+        for j in members.iter() {
+            channel_pk_dict.insert(*j, keystore.channel_pk(j).ifnone_()?);
+        }
+        keystore.channel_pk_grid.insert(gid, channel_pk_dict);
+    }
+    let channel_pk_dict = keystore.channel_pk_grid.get_mut(&gid).ifnone_()?;
+    channel_pk_dict.insert(id, channel_pk);
+    Ok(())
+}
+
+/// The helpers of the target's group and the target must all have sent an
+/// approval equal to mine.
+async fn check_approvals(
+    messenger: &impl Messenger,
+    members: &HashSet<MpcAddr>,
+    approval: &RepairApproval,
+) -> Outcome<()> {
+    let topic = format!("repair_approval/{}", approval.target);
+    let mut parties = members.clone();
+    parties.insert(approval.target);
+    let approval_dict: HashMap<MpcAddr, RepairApproval> = messenger
+        .gather(&topic, &parties, MpcAddr::bcast_id())
+        .await
+        .catch_()?;
+    let mut disagree: Vec<MpcAddr> = approval_dict
+        .iter()
+        .filter(|(_, appr)| *appr != approval)
+        .map(|(id, _)| *id)
+        .collect();
+    disagree.sort();
+    if !disagree.is_empty() {
+        let disagree: Vec<String> = disagree.iter().map(|id| id.to_string()).collect();
+        throw!(
+            "RepairNotApproved",
+            format!("Not approved by {}", disagree.join(", "))
+        );
+    }
+    Ok(())
+}
+
+/// Help to rebuild the lost shards of `approvals`.
+/// For each target, `helpers[gid]` must hold at least `th` members of its group,
+/// not including the target itself. Every helper runs this function, while the
+/// party under repair runs `algo_repair_recover()` with the same helpers.
+///
+/// Each helper splits its Lagrange-weighted share into sub-shares among the
+/// helpers, and sends the sum of those it receives to the target, encrypted to
+/// the approved channel key. Neither the helpers nor the target learn any
+/// other member's share. Returns my keystore with the new channel keys of
+/// the targets, see `RepairApproval::record()`.
+pub async fn algo_repair_help(
+    messenger: &impl Messenger,
+    sid: SessionId,
+    helpers: &HashMap<u16 /*group_id*/, HashSet<MpcAddr>>,
+    approvals: &[RepairApproval],
+    keystore: &KeyStore,
+) -> Outcome<KeyStore> {
+    let messenger = &messenger.scoped(sid);
+    assert_unweighted(keystore).catch_()?;
+    let mut approvals_asc: Vec<&RepairApproval> = approvals.iter().collect();
+    approvals_asc.sort_by_key(|appr| appr.target);
+    let mut rng = OsRng;
+    let public = KeyStorePublic::of(keystore);
+    let mut new_keystore = keystore.clone();
+
+    for approval in approvals_asc.iter() {
+        let target = approval.target;
+        let gid = target.group_id();
+        new_keystore = approval.record(&new_keystore).catch_()?;
+        let members = helpers.get(&gid).ifnone("NoHelpers", target.to_string())?;
+        let my_id = match keystore.ids.iter().find(|id| members.contains(id)) {
+            Some(id) => *id,
+            None => continue,
+        };
+        assert_throw!(!members.contains(&target), "The target cannot help itself");
+        let th = keystore.th(gid).catch_()?;
+        assert_throw!(
+            members.len() >= th,
+            "NotEnoughHelpers",
+            format!("{} helpers for {}, {} needed", members.len(), target, th)
+        );
+        let approval_topic = format!("repair_approval/{}", target);
+        let pub_topic = format!("repair_pub/{}", target);
+        let sub_topic = format!("repair_sub/{}", target);
+        let sum_topic = format!("repair_sum/{}", target);
+
+        // The target has lost its channel key, so it is reached by the
+        // approved one, and nothing is sent unless everyone approved it.
+        messenger
+            .send(&approval_topic, my_id, MpcAddr::bcast_id(), *approval)
+            .await
+            .catch_()?;
+        check_approvals(messenger, members, approval)
+            .await
+            .catch_()?;
+        messenger
+            .send(&pub_topic, my_id, target, &public)
+            .await
+            .catch_()?;
+
+        let x_i = keystore.xi_pergroup.get(&gid).ifnone_()?;
+        let aes_key_dict = keystore_channel_keys::<Ed25519>(keystore, gid, members).catch_()?;
+        let mut sub_shares = repair_split(my_id, x_i, target, members, &mut rng).catch_()?;
+        let mut recv_shares: HashMap<MpcAddr, Scalar> = exchange_shares(
+            messenger,
+            &sub_topic,
+            &sid.to_string(),
+            my_id,
+            members,
+            &aes_key_dict,
+            &sub_shares,
+        )
+        .await
+        .catch_()?;
+        for x in sub_shares.values_mut() {
+            x.zeroize();
+        }
+
+        let mut sigma: Scalar = recv_shares.values().sum();
+        for x in recv_shares.values_mut() {
+            x.zeroize();
+        }
+        let channel_sk = keystore.channel_sk(gid).ifnone_()?;
+        let aes_key = channel_key(&(approval.channel_pk * channel_sk).compress().to_bytes());
+        let aad = channel_aad(my_id, target, &sum_topic, &sid.to_string());
+        let aead_pack = aes_encrypt(&aes_key, &sigma.to_bytes(), &aad).catch_()?;
+        sigma.zeroize();
+        messenger
+            .send(&sum_topic, my_id, target, &aead_pack)
+            .await
+            .catch_()?;
+    }
+
+    Ok(new_keystore)
+}
+
+/// Rebuild the lost shards of `repair_keys` in the key `pk` with the help of
+/// `helpers`, who run `algo_repair_help()` with the approvals of those keys.
+/// Every recovered share is checked against the commitments before the
+/// keystore is returned, which holds the repair keys as its channel keys.
+pub async fn algo_repair_recover(
+    messenger: &impl Messenger,
+    sid: SessionId,
+    helpers: &HashMap<u16 /*group_id*/, HashSet<MpcAddr>>,
+    repair_keys: &[RepairKey],
+    pk: &EdwardsPoint,
+) -> Outcome<KeyStore> {
+    let messenger = &messenger.scoped(sid);
+    let mut repair_keys_asc: Vec<&RepairKey> = repair_keys.iter().collect();
+    repair_keys_asc.sort_by_key(|key| key.target);
+    let mut xi_pergroup: HashMap<u16, Scalar> = HashMap::new();
+    let mut public: Option<KeyStorePublic> = None;
+
+    for repair_key in repair_keys_asc.iter() {
+        let target = repair_key.target;
+        let gid = target.group_id();
+        let members = helpers.get(&gid).ifnone("NoHelpers", target.to_string())?;
+        assert_throw!(!members.contains(&target), "The target cannot help itself");
+        let approval_topic = format!("repair_approval/{}", target);
+        let pub_topic = format!("repair_pub/{}", target);
+        let sum_topic = format!("repair_sum/{}", target);
+
+        let approval = repair_key.approval(pk);
+        messenger
+            .send(&approval_topic, target, MpcAddr::bcast_id(), &approval)
+            .await
+            .catch_()?;
+        check_approvals(messenger, members, &approval)
+            .await
+            .catch_()?;
+
+        // Helpers must agree on the public part of the keystore.
+        let pub_t = KeyStorePublic::gather(messenger, &pub_topic, members, target)
+            .await
+            .catch_()?;
+        if let Some(p) = public.as_ref() {
+            assert_throw!(
+                *p == pub_t,
+                "InconsistentKeystore",
+                "Helpers of groups disagree"
+            );
+        }
+        let pubstore = public.get_or_insert(pub_t).clone().into_keystore();
+        assert_throw!(pubstore.pk().catch_()? == *pk, "Wrong key");
+        let vss_com_dict = pubstore
+            .vss_com_grid
+            .get(&gid)
+            .ifnone("NoSuchGroup", gid.to_string())?;
+        let th = pubstore.th(gid).catch_()?;
+        assert_throw!(
+            members.len() >= th,
+            "NotEnoughHelpers",
+            format!("{} helpers for {}, {} needed", members.len(), target, th)
+        );
+
+        let aead_dict: HashMap<MpcAddr, AEAD> = messenger
+            .gather(&sum_topic, members, target)
+            .await
+            .catch_()?;
+        let mut x_i = Scalar::zero();
+        for j in members.iter() {
+            let channel_pk = pubstore
+                .channel_pk(j)
+                .ifnone("NoSuchMember", j.to_string())?;
+            let aes_key = channel_key(&(channel_pk * repair_key.channel_sk).compress().to_bytes());
+            let aead_pack = aead_dict.get(j).ifnone_()?;
+            let aad = channel_aad(*j, target, &sum_topic, &sid.to_string());
+            let out = aes_decrypt(&aes_key, aead_pack, &aad).catch_()?;
+            assert_throw!(out.len() == 32, "Invalid plaintext length");
+            let mut out_arr = [0u8; 32];
+            out_arr.copy_from_slice(&out);
+            x_i += Scalar::from_canonical_bytes(out_arr)
+                .ifnone("NonCanonicalScalar", j.to_string())?;
+            out_arr.zeroize();
+        }
+
+        // sanity check
+        assert_throw!(
+            eval_xi_com(target, vss_com_dict) == &constants::ED25519_BASEPOINT_TABLE * &x_i,
+            "RepairFailed",
+            format!(
+                "Recovered share of {} does not match the commitments",
+                target
+            )
+        );
+        xi_pergroup.insert(gid, x_i);
+    }
+
+    let p = public.ifnone("NoTargets", "")?;
+    let mut keystore = p.into_keystore();
+    for repair_key in repair_keys_asc.iter() {
+        let target = repair_key.target;
+        let gid = target.group_id();
+        let approval = repair_key.approval(pk);
+        record_channel_pk(&mut keystore, target, approval.channel_pk).catch_()?;
+        keystore
+            .channel_sk_pergroup
+            .insert(gid, repair_key.channel_sk);
+        keystore
+            .xi_pergroup
+            .insert(gid, *xi_pergroup.get(&gid).ifnone_()?);
+        keystore.ids.insert(target);
+    }
+    for x in xi_pergroup.values_mut() {
+        x.zeroize();
+    }
+    Ok(keystore)
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::future::join_all;
+
+    use super::*;
+    use crate::biz_algo::testkit::*;
+    use crate::biz_algo::{algo_refresh, SignMode};
+    use crate::frost::verify_signature;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn repaired_member_refreshes_and_signs() {
+        let messenger = MemMessenger::default();
+        let arch = key_arch(&[(1, 2, 3)]);
+        let mut keystores =
+            keygen::<Ed25519>(&messenger, &arch, &paired(&arch), FrostMode::Rfc9591).await;
+        let pk = keystores[0].pk().unwrap();
+        let target = MpcAddr::new(1, 3);
+
+        let helpers = ses_arch(&[(1, &[1, 2])]);
+        let repair_key = RepairKey::new(target);
+        let approvals = [repair_key.approval(&pk)];
+        let repair_keys = [repair_key];
+        let sid = SessionId::random();
+        let tasks = keystores[..2]
+            .iter()
+            .map(|keystore| algo_repair_help(&messenger, sid, &helpers, &approvals, keystore));
+        let (helped, repaired) = tokio::join!(
+            join_all(tasks),
+            algo_repair_recover(&messenger, sid, &helpers, &repair_keys, &pk)
+        );
+        let repaired = repaired.unwrap();
+        assert_eq!(repaired.xi_pergroup, keystores[2].xi_pergroup);
+        assert_eq!(repaired.ids, keystores[2].ids);
+        assert_ne!(
+            repaired.channel_pk(&target),
+            keystores[2].channel_pk(&target)
+        );
+        for (i, keystore) in helped.into_iter().enumerate() {
+            keystores[i] = keystore.unwrap();
+            assert_eq!(keystores[i].channel_pk_grid, repaired.channel_pk_grid);
+        }
+        keystores[2] = repaired;
+
+        // The new channel key carries the shares of a refresh.
+        let sid = SessionId::random();
+        let tasks = keystores
+            .iter()
+            .map(|keystore| algo_refresh(&messenger, sid, &arch, keystore));
+        let keystores: Vec<KeyStore> = join_all(tasks)
+            .await
+            .into_iter()
+            .map(|keystore| keystore.unwrap())
+            .collect();
+        let ses_arch = ses_arch(&[(1, &[1, 3])]);
+        let signers = [keystores[0].clone(), keystores[2].clone()];
+        for sig in sign::<Ed25519>(
+            &messenger,
+            &ses_arch,
+            &signers,
+            b"repaired",
+            &SignMode::Ed25519,
+        )
+        .await
+        {
+            verify_signature(&sig.unwrap(), &pk).unwrap();
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn mismatched_approval_blocks_every_share() {
+        let messenger = MemMessenger::default();
+        let arch = key_arch(&[(1, 2, 3)]);
+        let keystores =
+            keygen::<Ed25519>(&messenger, &arch, &paired(&arch), FrostMode::Rfc9591).await;
+        let pk = keystores[0].pk().unwrap();
+        let target = MpcAddr::new(1, 3);
+
+        // Helper 1.2 is shown a key the target does not hold.
+        let helpers = ses_arch(&[(1, &[1, 2])]);
+        let repair_key = RepairKey::new(target);
+        let approvals = [
+            [repair_key.approval(&pk)],
+            [RepairKey::new(target).approval(&pk)],
+        ];
+        let repair_keys = [repair_key];
+        let sid = SessionId::random();
+        let tasks = keystores[..2]
+            .iter()
+            .zip(approvals.iter())
+            .map(|(keystore, approvals)| {
+                algo_repair_help(&messenger, sid, &helpers, approvals, keystore)
+            });
+        let (helped, repaired) = tokio::join!(
+            join_all(tasks),
+            algo_repair_recover(&messenger, sid, &helpers, &repair_keys, &pk)
+        );
+        assert!(repaired.is_err());
+        for keystore in helped.iter() {
+            assert!(keystore.is_err());
+        }
+        assert!(!messenger.has_topic(&format!("{}/repair_sub/{}", sid, target)));
+        assert!(!messenger.has_topic(&format!("{}/repair_sum/{}", sid, target)));
+    }
+}
@@ -0,0 +1,285 @@
+use std::collections::{HashMap, HashSet};
+
+use curve25519_dalek::{constants, edwards::EdwardsPoint, scalar::Scalar, traits::Identity};
+use libexception::*;
+use mpc_spec::*;
+use rand::rngs::OsRng;
+use zeroize::Zeroize;
+
+use super::aes::{aes_decrypt, aes_encrypt, channel_aad, channel_key, AEAD};
+use super::{assert_unweighted, KeyStore, KeyStorePublic};
+use crate::frost::{
+    deal_vss_share, eval_xi_com, lagrange_lambda, merge_vss_share, reshare_validate_peers, Blame,
+    Evidence,
+};
+
+/// Reshare the key of group `gid` to `members`, with a new threshold `th`,
+/// keeping `MultiShard::pk()` unchanged.
+///
+/// At least `th` of the current members, namely `holders`, share their
+/// Lagrange-weighted $x_i$ among the new members. Every keystore holder of
+/// the key must run this function, so as to learn the new commitments of the
+/// group. A new member without any keystore passes `keystore = None`, and
+/// `whoami` is its member id in the group, if any.
+///
+/// Each new member draws a fresh channel key, which replaces the old ones of
+/// the group in `channel_pk_grid`. The commitments of the resharing polynomials
+/// are kept under `MpcAddr::gcast_id(gid)`.
+pub async fn algo_reshare(
+    messenger: &impl Messenger,
+    sid: SessionId,
+    gid: u16,
+    holders: &HashSet<MpcAddr>,
+    th: usize,
+    members: &HashSet<MpcAddr>,
+    whoami: Option<MpcAddr>,
+    keystore: Option<&KeyStore>,
+) -> Outcome<KeyStore> {
+    let messenger = &messenger.scoped(sid);
+    if let Some(keystore) = keystore {
+        assert_unweighted(keystore).catch_()?;
+    }
+    let bcast_id = MpcAddr::bcast_id();
+    let mut rng = OsRng;
+    for id in holders.iter().chain(members.iter()) {
+        assert_throw!(
+            id.group_id() == gid && id.member_id() != 0,
+            format!("{} is not a member of group {}", id, gid)
+        );
+    }
+    if let Some(my_id) = whoami {
+        assert_throw!(members.contains(&my_id), "I am not a new member");
+    }
+
+    // Parties without a keystore learn its public part from the holders.
+    let pub_topic = format!("reshare_pub/{}", gid);
+    let mut keystore: KeyStore = match keystore {
+        Some(keystore) => {
+            let mut hold = keystore.ids.iter().filter(|id| holders.contains(id));
+            if let Some(my_id) = hold.next() {
+                messenger
+                    .send(&pub_topic, *my_id, bcast_id, &KeyStorePublic::of(keystore))
+                    .await
+                    .catch_()?;
+            }
+            keystore.clone()
+        }
+        None => {
+            assert_throw!(whoami.is_some(), "Neither a keystore nor a new member");
+            let p = KeyStorePublic::gather(messenger, &pub_topic, holders, bcast_id)
+                .await
+                .catch_()?;
+            p.into_keystore()
+        }
+    };
+    let old_th = keystore.th(gid).catch_()?;
+    assert_throw!(
+        holders.len() >= old_th,
+        "NotEnoughHolders",
+        format!(
+            "{} holders in group {}, {} needed",
+            holders.len(),
+            gid,
+            old_th
+        )
+    );
+    let old_vss_com_dict = keystore.vss_com_grid.get(&gid).ifnone_()?.clone();
+    let mut old_channel_pk_dict: HashMap<MpcAddr, EdwardsPoint> = HashMap::new();
+    for j in holders.iter() {
+        let channel_pk = keystore
+            .channel_pk(j)
+            .ifnone("NoSuchMember", j.to_string())?;
+        old_channel_pk_dict.insert(*j, channel_pk);
+    }
+    let main_pk = keystore.pk().catch_()?;
+    let epoch = keystore.epoch + 1;
+    let key_topic = format!("reshare_key/{}", epoch);
+    let com_topic = format!("reshare_com/{}", epoch);
+    let share_topic = format!("reshare_share/{}", epoch);
+
+    // New members publish their channel keys
+    let mut new_channel_sk: Option<Scalar> = None;
+    if let Some(my_id) = whoami {
+        let channel_sk = Scalar::random(&mut rng);
+        let channel_pk = &constants::ED25519_BASEPOINT_TABLE * &channel_sk;
+        messenger
+            .send(&key_topic, my_id, bcast_id, &channel_pk)
+            .await
+            .catch_()?;
+        new_channel_sk = Some(channel_sk);
+    }
+    let key_dict: HashMap<MpcAddr, EdwardsPoint> = messenger
+        .gather(&key_topic, members, bcast_id)
+        .await
+        .catch_()?;
+
+    // Holders deal their Lagrange-weighted shares to new members
+    let my_holder_id = keystore.ids.iter().find(|id| holders.contains(id)).cloned();
+    if let Some(my_id) = my_holder_id {
+        let channel_sk = keystore.channel_sk(gid).ifnone_()?;
+        let x_i = keystore.xi_pergroup.get(&gid).ifnone_()?;
+        let mut w_i = lagrange_lambda(my_id, holders).catch_()? * x_i;
+        let _obj: _ = deal_vss_share(&w_i, members, th, &mut rng).catch_()?;
+        w_i.zeroize();
+        let shares_com: Vec<EdwardsPoint> = _obj.0;
+        let mut shares: HashMap<MpcAddr, Scalar> = _obj.1;
+        messenger
+            .send(&com_topic, my_id, bcast_id, &shares_com)
+            .await
+            .catch_()?;
+        for (j, channel_pk) in key_dict.iter() {
+            let aes_key = channel_key(&(channel_pk * channel_sk).compress().to_bytes());
+            let plaintext = shares.get(j).ifnone_()?.to_bytes();
+            let aad = channel_aad(my_id, *j, &share_topic, &sid.to_string());
+            let aead_pack = aes_encrypt(&aes_key, &plaintext, &aad).catch_()?;
+            messenger
+                .send(&share_topic, my_id, *j, &aead_pack)
+                .await
+                .catch_()?;
+        }
+        for x in shares.values_mut() {
+            x.zeroize();
+        }
+    }
+    let reshare_com_dict: HashMap<MpcAddr, Vec<EdwardsPoint>> = messenger
+        .gather(&com_topic, holders, bcast_id)
+        .await
+        .catch_()?;
+    reshare_validate_peers(&reshare_com_dict, &old_vss_com_dict, th).catch_()?;
+    let old_group_pk: EdwardsPoint = old_vss_com_dict.values().map(|com| com[0]).sum();
+    let new_group_pk: EdwardsPoint = reshare_com_dict.values().map(|com| com[0]).sum();
+    assert_throw!(new_group_pk == old_group_pk, "Group key changed");
+
+    // New commitments of the group
+    let mut agg_com: Vec<EdwardsPoint> = vec![EdwardsPoint::identity(); th];
+    for com in reshare_com_dict.values() {
+        for (c, rc) in agg_com.iter_mut().zip(com.iter()) {
+            *c += rc;
+        }
+    }
+    let mut vss_com_dict: HashMap<MpcAddr, Vec<EdwardsPoint>> = HashMap::new();
+    vss_com_dict.insert(MpcAddr::gcast_id(gid), agg_com);
+
+    // Replace my shard of the group
+    keystore.ids.retain(|id| id.group_id() != gid);
+    keystore.ui_pergroup.remove(&gid);
+    keystore.channel_sk_pergroup.remove(&gid);
+    keystore.xi_pergroup.remove(&gid);
+    if let Some(my_id) = whoami {
+        let aead_dict: HashMap<MpcAddr, AEAD> = messenger
+            .gather(&share_topic, holders, my_id)
+            .await
+            .catch_()?;
+        let channel_sk = new_channel_sk.ifnone_()?;
+        let mut party_shares: HashMap<MpcAddr, Scalar> = HashMap::new();
+        let mut blame: Blame = Blame::default();
+        for (j, aead_pack) in aead_dict.iter() {
+            let channel_pk = old_channel_pk_dict.get(j).ifnone_()?;
+            let aes_key = channel_key(&(channel_pk * channel_sk).compress().to_bytes());
+            let aad = channel_aad(*j, my_id, &share_topic, &sid.to_string());
+            let share = match aes_decrypt(&aes_key, aead_pack, &aad) {
+                Ok(mut out) if out.len() == 32 => {
+                    let mut out_arr = [0u8; 32];
+                    out_arr.copy_from_slice(&out);
+                    let share = Scalar::from_canonical_bytes(out_arr);
+                    out.zeroize();
+                    out_arr.zeroize();
+                    share
+                }
+                _ => None,
+            };
+            match share {
+                Some(share) => {
+                    party_shares.insert(*j, share);
+                }
+                None => {
+                    let ciphertext = aead_pack.ciphertext.clone();
+                    let nonce = aead_pack.tag.clone();
+                    blame.accuse(*j, Evidence::UndecryptableShare { ciphertext, nonce });
+                }
+            }
+        }
+        blame.throw_if_any()?;
+        let x_i = merge_vss_share(&party_shares, &reshare_com_dict, my_id).catch_()?;
+        for x in party_shares.values_mut() {
+            x.zeroize();
+        }
+
+        keystore.ids.insert(my_id);
+        keystore.channel_sk_pergroup.insert(gid, channel_sk);
+        keystore.xi_pergroup.insert(gid, x_i);
+    }
+    keystore.vss_com_grid.insert(gid, vss_com_dict);
+    keystore.channel_pk_grid.insert(gid, key_dict);
+
+    // sanity check
+    assert_throw!(keystore.pk().catch_()? == main_pk, "Public key changed");
+    if let Some(my_id) = whoami {
+        let x_i = keystore.xi_pergroup.get(&gid).ifnone_()?;
+        let vss_com_dict = keystore.vss_com_grid.get(&gid).ifnone_()?;
+        assert_throw!(
+            eval_xi_com(my_id, vss_com_dict) == &constants::ED25519_BASEPOINT_TABLE * x_i,
+            "Reshared share does not match the commitments"
+        );
+    }
+
+    keystore.epoch = epoch;
+    Ok(keystore)
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::future::join_all;
+
+    use super::*;
+    use crate::biz_algo::testkit::*;
+    use crate::biz_algo::SignMode;
+    use crate::frost::{verify_signature, Ed25519};
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn reshare_2_of_3_to_3_of_4() {
+        let messenger = MemMessenger::default();
+        let arch = key_arch(&[(1, 2, 3)]);
+        let old_keystores =
+            keygen::<Ed25519>(&messenger, &arch, &paired(&arch), FrostMode::Rfc9591).await;
+        let pk = old_keystores[0].pk().unwrap();
+
+        let holders = ses_arch(&[(1, &[1, 2])])[&1].clone();
+        let members = ses_arch(&[(1, &[1, 2, 3, 4])])[&1].clone();
+        let sid = SessionId::random();
+        let tasks = (1..=4).map(|i| {
+            let keystore = old_keystores.get(i - 1);
+            let whoami = Some(MpcAddr::new(1, i as u16));
+            algo_reshare(&messenger, sid, 1, &holders, 3, &members, whoami, keystore)
+        });
+        let new_keystores: Vec<KeyStore> = join_all(tasks)
+            .await
+            .into_iter()
+            .map(|keystore| keystore.unwrap())
+            .collect();
+        for keystore in new_keystores.iter() {
+            assert_eq!(keystore.pk().unwrap(), pk);
+            assert_eq!(keystore.th(1).unwrap(), 3);
+            assert_eq!(keystore.members(1), members);
+        }
+
+        // The new member signs with two of the old ones.
+        let ses_arch = ses_arch(&[(1, &[1, 3, 4])]);
+        let signers = [
+            new_keystores[0].clone(),
+            new_keystores[2].clone(),
+            new_keystores[3].clone(),
+        ];
+        for sig in sign::<Ed25519>(
+            &messenger,
+            &ses_arch,
+            &signers,
+            b"3-of-4",
+            &SignMode::Ed25519,
+        )
+        .await
+        {
+            verify_signature(&sig.unwrap(), &pk).unwrap();
+        }
+    }
+}
@@ -0,0 +1,728 @@
+//! Robust signing following ROAST (Ruffing et al., CCS 2022).
+//!
+//! A coordinator is given more than `th` candidates per group. Every candidate
+//! keeps one fresh nonce commitment at the coordinator. As soon as candidates
+//! of every group weighing at least `th` are ready, the coordinator starts a
+//! session with the earliest ones; a signer replies with its response and its
+//! next commitment, which makes it ready again. Sessions run concurrently, and
+//! the first one completing with valid responses yields the signature.
+//!
+//! A signer sending an invalid response is excluded for good, and so is a
+//! signer not responding within `timeout` of its session's start.
+//! The coordinator is addressed as `MpcAddr::bcast_id()`, whoever runs it.
+
+use curve25519_dalek::{edwards::EdwardsPoint, scalar::Scalar};
+use futures_util::stream::{FuturesUnordered, StreamExt};
+use libexception::*;
+use mpc_spec::*;
+use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+use tokio::time::{sleep_until, Instant};
+
+use zeroize::Zeroize;
+
+use super::{
+    derive_child, group_coefs, sub_shares, DerivationScheme, KeyStore, SignMode, Signature,
+};
+use crate::frost::{
+    agg_nonce_com, eval_signer_com, gen_rho_dict, generate_challenge, is_valid_response,
+    sign_and_respond, sign_preprocess, verify_signature, Blame, Ciphersuite, Ed25519, Evidence,
+    SigningCommitmentPair,
+};
+
+/// The `k`-th message of a signer, on topic `roast_reply/k`.
+/// The initial one carries no response.
+#[derive(Clone, Serialize, Deserialize)]
+struct RoastReply {
+    epoch: u64,
+    resp: Option<Scalar>,
+    nonce_com: SigningCommitmentPair,
+}
+
+/// Sent to a signer on topic `roast_req/k`, after its `k`-th reply.
+#[derive(Clone, Serialize, Deserialize)]
+struct RoastRequest {
+    epoch: u64,
+    signers: HashMap<u16, HashSet<MpcAddr>>,
+    nonce_com_dict: HashMap<MpcAddr, SigningCommitmentPair>,
+}
+
+/// A ROAST signature, and the signers excluded on the way to it.
+#[derive(Clone, Debug)]
+pub struct RoastReport {
+    pub sig: Signature,
+    /// Signers which timed out, sent an undecodable reply, or are accused in `blame`.
+    pub excluded: HashSet<MpcAddr>,
+    /// Evidence against the excluded signers which sent invalid replies.
+    pub blame: Blame,
+}
+
+struct RoastSession {
+    signers: HashMap<u16, HashSet<MpcAddr>>,
+    nonce_com_dict: HashMap<MpcAddr, SigningCommitmentPair>,
+    rho_dict: HashMap<MpcAddr, Scalar>,
+    sig_r: EdwardsPoint,
+    challenge: Scalar,
+    resp_dict: HashMap<MpcAddr, Scalar>,
+    deadline: Instant,
+}
+
+async fn receive_reply(
+    messenger: &impl Messenger,
+    j: MpcAddr,
+    k: usize,
+) -> (MpcAddr, usize, Outcome<Box<RoastReply>>) {
+    let topic = format!("roast_reply/{}", k);
+    let coord_id = MpcAddr::bcast_id();
+    let reply = messenger.receive(&topic, j, coord_id).await.catch_();
+    let reply = reply.map(Box::new);
+    (j, k, reply)
+}
+
+pub async fn algo_sign_roast_coordinator(
+    messenger: &impl Messenger,
+    sid: SessionId,
+    candidates: &HashMap<u16, HashSet<MpcAddr>>,
+    drv_path: &str,
+    msg: &[u8],
+    sign_mode: &SignMode,
+    hd_scheme: &impl DerivationScheme,
+    keystore: &KeyStore, // Only public data is used.
+    timeout: Duration,
+) -> Outcome<RoastReport> {
+    let messenger = &messenger.scoped(sid);
+    let coord_id = MpcAddr::bcast_id();
+    let result = roast_coordinate(
+        messenger, candidates, drv_path, msg, sign_mode, hd_scheme, keystore, timeout,
+    )
+    .await;
+
+    // Let the signers stop, with or without a signature.
+    let all_candidates: HashSet<MpcAddr> = candidates.values().flatten().cloned().collect();
+    let done: Option<Signature> = result.as_ref().ok().map(|report| report.sig.clone());
+    messenger
+        .scatter("roast_done", coord_id, &all_candidates, &done)
+        .await
+        .catch_()?;
+    result
+}
+
+enum CoordinatorEvent {
+    Reply(MpcAddr, usize, Outcome<Box<RoastReply>>),
+    NoReplyLeft,
+    Timeout,
+}
+
+/// Number of sub-shares of `ids` together.
+fn weight_sum<'a>(keystore: &KeyStore, ids: impl Iterator<Item = &'a MpcAddr>) -> usize {
+    ids.map(|j| keystore.weight(j) as usize).sum()
+}
+
+async fn roast_coordinate(
+    messenger: &Scoped<'_, impl Messenger>,
+    candidates: &HashMap<u16, HashSet<MpcAddr>>,
+    drv_path: &str,
+    msg: &[u8],
+    sign_mode: &SignMode,
+    hd_scheme: &impl DerivationScheme,
+    keystore: &KeyStore,
+    timeout: Duration,
+) -> Outcome<RoastReport> {
+    sign_mode.validate().catch_()?;
+    let coord_id = MpcAddr::bcast_id();
+    let (child_pk, child_keystore) = derive_child(keystore, drv_path, hd_scheme).catch_()?;
+    let signed_bytes = sign_mode.signed_bytes(msg);
+    let coef_dict =
+        group_coefs::<Ed25519>(keystore, &candidates.keys().cloned().collect()).catch_()?;
+
+    // Compute the threshold of each group, in sub-shares.
+    let mut th_dict: HashMap<u16, usize> = HashMap::new();
+    for (gid, group_members) in candidates.iter() {
+        let th = keystore.th(*gid).catch_()?;
+        assert_throw!(
+            weight_sum(keystore, group_members.iter()) >= th,
+            format!("Group {} has fewer than {} candidates", gid, th)
+        );
+        th_dict.insert(*gid, th);
+    }
+
+    let mut inbox = FuturesUnordered::new();
+    for j in candidates.values().flatten() {
+        inbox.push(receive_reply(messenger, *j, 0));
+    }
+    let mut ready: HashMap<u16, Vec<MpcAddr>> = HashMap::new(); // in order of arrival
+    let mut nonce_com_of: HashMap<MpcAddr, (usize, SigningCommitmentPair)> = HashMap::new();
+    let mut busy: HashMap<MpcAddr, usize> = HashMap::new(); // signer -> session
+    let mut sessions: HashMap<usize, RoastSession> = HashMap::new();
+    let mut excluded: HashSet<MpcAddr> = HashSet::new();
+    let mut blame: Blame = Blame::default();
+    let mut sid_next = 0usize;
+    let mut idle_deadline = Instant::now() + timeout;
+
+    loop {
+        // Start a session whenever every group has `th` ready signers.
+        while th_dict
+            .iter()
+            .all(|(gid, th)| ready.get(gid).map_or(0, |v| weight_sum(keystore, v.iter())) >= *th)
+        {
+            let sid = sid_next;
+            sid_next += 1;
+            let mut signers: HashMap<u16, HashSet<MpcAddr>> = HashMap::new();
+            let mut nonce_com_dict: HashMap<MpcAddr, SigningCommitmentPair> = HashMap::new();
+            let mut req_topic_dict: HashMap<MpcAddr, String> = HashMap::new();
+            for (gid, th) in th_dict.iter() {
+                let queue = ready.get_mut(gid).ifnone_()?;
+                let mut n = 0;
+                while weight_sum(keystore, queue[..n].iter()) < *th {
+                    n += 1;
+                }
+                let group_signers: HashSet<MpcAddr> = queue.drain(..n).collect();
+                for j in group_signers.iter() {
+                    let (k, nonce_com) = nonce_com_of.remove(j).ifnone_()?;
+                    nonce_com_dict.insert(*j, nonce_com);
+                    req_topic_dict.insert(*j, format!("roast_req/{}", k));
+                    busy.insert(*j, sid);
+                }
+                signers.insert(*gid, group_signers);
+            }
+            let rho_dict = gen_rho_dict(
+                keystore.mode,
+                messenger.sid(),
+                &child_pk,
+                &signed_bytes,
+                &nonce_com_dict,
+            );
+            let sig_r = agg_nonce_com(&nonce_com_dict, &rho_dict).catch_()?;
+            let challenge = generate_challenge::<Ed25519>(sign_mode, msg, &sig_r, &child_pk);
+
+            let req = RoastRequest {
+                epoch: keystore.epoch,
+                signers: signers.clone(),
+                nonce_com_dict: nonce_com_dict.clone(),
+            };
+            for (j, topic) in req_topic_dict.iter() {
+                messenger.send(topic, coord_id, *j, &req).await.catch_()?;
+            }
+            println!("Started ROAST session {}", sid);
+            let session = RoastSession {
+                signers,
+                nonce_com_dict,
+                rho_dict,
+                sig_r,
+                challenge,
+                resp_dict: HashMap::new(),
+                deadline: Instant::now() + timeout,
+            };
+            sessions.insert(sid, session);
+        }
+
+        // Give up once some group can never gather a quorum again.
+        for (gid, th) in th_dict.iter() {
+            let group_members = candidates.get(gid).ifnone_()?;
+            let remaining = weight_sum(keystore, group_members.difference(&excluded));
+            if remaining < *th {
+                blame.throw_if_any()?;
+                throw!(
+                    "NotEnoughSigners",
+                    format!("Only {} of group {} remain, {} needed", remaining, gid, th)
+                );
+            }
+        }
+
+        let deadline = match sessions.values().map(|ses| ses.deadline).min() {
+            Some(deadline) => deadline,
+            None => idle_deadline,
+        };
+        let event = tokio::select! {
+            reply = inbox.next() => match reply {
+                Some((j, k, reply)) => CoordinatorEvent::Reply(j, k, reply),
+                None => CoordinatorEvent::NoReplyLeft,
+            },
+            _ = sleep_until(deadline) => CoordinatorEvent::Timeout,
+        };
+
+        let (j, k, reply) = match event {
+            CoordinatorEvent::Reply(j, k, Ok(reply)) => (j, k, *reply),
+            CoordinatorEvent::Reply(j, _, Err(e)) => {
+                // e.g. an undecodable message, which must not abort the others.
+                println!("Excluded {} for a bad reply: {}", j, e);
+                excluded.insert(j);
+                if let Some(sid) = busy.remove(&j) {
+                    sessions.remove(&sid);
+                }
+                continue;
+            }
+            CoordinatorEvent::NoReplyLeft => {
+                blame.throw_if_any()?;
+                throw!("NotEnoughSigners", "Nobody is left to reply");
+            }
+            CoordinatorEvent::Timeout => {
+                if sessions.is_empty() {
+                    blame.throw_if_any()?;
+                    throw!("NotEnoughSigners", "No quorum became ready in time");
+                }
+                let now = Instant::now();
+                let expired: Vec<usize> = sessions
+                    .iter()
+                    .filter(|(_, ses)| ses.deadline <= now)
+                    .map(|(sid, _)| *sid)
+                    .collect();
+                for sid in expired {
+                    let ses = sessions.remove(&sid).ifnone_()?;
+                    for j in ses.nonce_com_dict.keys() {
+                        if !ses.resp_dict.contains_key(j) {
+                            println!("Excluded {} for timing out in session {}", j, sid);
+                            excluded.insert(*j);
+                            busy.remove(j);
+                        }
+                    }
+                }
+                // The signers left over get another `timeout` to form a quorum.
+                idle_deadline = now + timeout;
+                continue;
+            }
+        };
+        idle_deadline = Instant::now() + timeout;
+        if excluded.contains(&j) {
+            continue;
+        }
+        if reply.epoch != keystore.epoch {
+            let (expected, got) = (keystore.epoch, reply.epoch);
+            println!("Excluded {} for being at epoch {}", j, got);
+            blame.accuse(j, Evidence::EpochMismatch { expected, got });
+            excluded.insert(j);
+            if let Some(sid) = busy.remove(&j) {
+                sessions.remove(&sid);
+            }
+            continue;
+        }
+
+        match busy.remove(&j) {
+            Some(sid) => {
+                // Validate the response, if its session is still alive.
+                let mut valid = reply.resp.is_some();
+                if let Some(ses) = sessions.get_mut(&sid) {
+                    let rho_j = ses.rho_dict.get(&j).ifnone_()?;
+                    let signers = ses.signers.get(&j.group_id()).ifnone_()?;
+                    let lam_j = coef_dict.get(&j.group_id()).ifnone_()?;
+                    let nonce_com = ses.nonce_com_dict.get(&j).ifnone_()?;
+                    let com = nonce_com.g_d + (nonce_com.g_e * rho_j);
+                    let vss_com_dict = child_keystore.vss_com_grid.get(&j.group_id()).ifnone_()?;
+                    let xjg /* $\lambda_j x_j * G$ */ =
+                        eval_signer_com::<Ed25519>(j, signers, &keystore.weights, vss_com_dict)
+                            .catch_()?;
+                    match reply.resp {
+                        Some(resp) => {
+                            valid = is_valid_response::<Ed25519>(
+                                &resp,
+                                &xjg,
+                                lam_j,
+                                &com,
+                                &ses.challenge,
+                            );
+                            if valid {
+                                ses.resp_dict.insert(j, resp);
+                            } else {
+                                let nonce_com = nonce_com.clone();
+                                let evidence = Evidence::InvalidResponse {
+                                    item: 0,
+                                    resp,
+                                    nonce_com,
+                                };
+                                blame.accuse(j, evidence);
+                            }
+                        }
+                        None => {
+                            let evidence = Evidence::WrongItemCount {
+                                expected: 1,
+                                got: 0,
+                            };
+                            blame.accuse(j, evidence);
+                        }
+                    }
+                }
+                if !valid {
+                    println!("Excluded {} for an invalid response", j);
+                    excluded.insert(j);
+                    sessions.remove(&sid);
+                    continue;
+                }
+
+                // Aggregate once all signers of the session have responded.
+                if let Some(ses) = sessions.get(&sid) {
+                    let n_signers: usize = ses.signers.values().map(|v| v.len()).sum();
+                    if ses.resp_dict.len() == n_signers {
+                        let sig = Signature {
+                            r: ses.sig_r,
+                            s: ses.resp_dict.values().sum(),
+                            msg: msg.to_vec(),
+                            mode: sign_mode.clone(),
+                        };
+                        verify_signature(&sig, &child_pk)
+                            .catch("InvalidSignature", "Most probably lack of signers")?;
+                        Ed25519::cross_check(&sig, &child_pk)
+                            .catch("", "Failed at cross_check()")?;
+                        println!("Finished ROAST session {}", sid);
+                        let report = RoastReport {
+                            sig,
+                            excluded,
+                            blame,
+                        };
+                        return Ok(report);
+                    }
+                }
+            }
+            None if k > 0 => {
+                // A response nobody asked for.
+                excluded.insert(j);
+                continue;
+            }
+            None => {}
+        }
+
+        // The signer is ready again with its next commitment.
+        nonce_com_of.insert(j, (k, reply.nonce_com));
+        ready.entry(j.group_id()).or_default().push(j);
+        inbox.push(receive_reply(messenger, j, k + 1));
+    }
+}
+
+/// Take part in the sessions of `algo_sign_roast_coordinator()` until it
+/// announces the outcome. Each shard of the keystore acts as a signer.
+pub async fn algo_sign_roast_signer(
+    messenger: &impl Messenger,
+    sid: SessionId,
+    drv_path: &str,
+    msg: &[u8],
+    sign_mode: &SignMode,
+    hd_scheme: &impl DerivationScheme,
+    keystore: &KeyStore,
+) -> Outcome<Signature> {
+    let messenger = &messenger.scoped(sid);
+    sign_mode.validate().catch_()?;
+    let (child_pk, child_keystore) = derive_child(keystore, drv_path, hd_scheme).catch_()?;
+    let mut whoami_asc: Vec<MpcAddr> = keystore.ids.iter().cloned().collect();
+    whoami_asc.sort();
+
+    let mut tasks = Vec::new();
+    for my_id in whoami_asc.iter() {
+        let task = roast_sign_as(
+            messenger,
+            *my_id,
+            &child_pk,
+            msg,
+            sign_mode,
+            keystore,
+            &child_keystore,
+        );
+        tasks.push(task);
+    }
+    let mut sigs = futures_util::future::try_join_all(tasks).await.catch_()?;
+    let sig = sigs.pop().ifnone_()?;
+    Ok(sig)
+}
+
+enum SignerEvent {
+    Request(Outcome<RoastRequest>),
+    Done(Outcome<Option<Signature>>),
+}
+
+async fn roast_sign_as(
+    messenger: &Scoped<'_, impl Messenger>,
+    my_id: MpcAddr,
+    child_pk: &EdwardsPoint,
+    msg: &[u8],
+    sign_mode: &SignMode,
+    keystore: &KeyStore,
+    child_keystore: &KeyStore,
+) -> Outcome<Signature> {
+    let coord_id = MpcAddr::bcast_id();
+    let mut rng = OsRng;
+    let my_gid = my_id.group_id();
+    let x_i = keystore.xi_pergroup.get(&my_gid).ifnone_()?;
+    let signed_bytes = sign_mode.signed_bytes(msg);
+
+    let (mut my_nonce_com, mut my_nonce) =
+        sign_preprocess(keystore.mode, x_i, &mut rng).catch_()?;
+    let reply = RoastReply {
+        epoch: keystore.epoch,
+        resp: None,
+        nonce_com: my_nonce_com.clone(),
+    };
+    messenger
+        .send("roast_reply/0", my_id, coord_id, &reply)
+        .await
+        .catch_()?;
+
+    let mut k = 0usize;
+    loop {
+        let req_topic = format!("roast_req/{}", k);
+        let event = tokio::select! {
+            req = messenger.receive(&req_topic, coord_id, my_id) => {
+                SignerEvent::Request(req.catch_())
+            }
+            done = messenger.receive("roast_done", coord_id, my_id) => {
+                SignerEvent::Done(done.catch_())
+            }
+        };
+        let req: RoastRequest = match event {
+            SignerEvent::Request(req) => req?,
+            SignerEvent::Done(done) => {
+                let sig = done?.ifnone("RoastAborted", "No quorum completed")?;
+                assert_throw!(
+                    sig.msg == msg && &sig.mode == sign_mode,
+                    "Signed something else"
+                );
+                verify_signature(&sig, child_pk).catch_()?;
+                return Ok(sig);
+            }
+        };
+
+        assert_throw!(
+            req.epoch == keystore.epoch,
+            "The coordinator is at another epoch"
+        );
+        let nonce_com = req.nonce_com_dict.get(&my_id).ifnone_()?;
+        assert_throw!(
+            nonce_com.g_d == my_nonce_com.g_d && nonce_com.g_e == my_nonce_com.g_e,
+            "The coordinator altered my nonce commitment"
+        );
+        for (gid, signers) in req.signers.iter() {
+            let th = keystore.th(*gid).catch_()?;
+            assert_throw!(
+                weight_sum(keystore, signers.iter()) >= th,
+                "Not enough signers"
+            );
+        }
+        let group_members = req.signers.get(&my_gid).ifnone_()?;
+        let coef_dict =
+            group_coefs::<Ed25519>(keystore, &req.signers.keys().cloned().collect()).catch_()?;
+        let coef = coef_dict.get(&my_gid).ifnone_()?;
+        let rho_dict = gen_rho_dict(
+            keystore.mode,
+            messenger.sid(),
+            child_pk,
+            &signed_bytes,
+            &req.nonce_com_dict,
+        );
+        let sig_r = agg_nonce_com(&req.nonce_com_dict, &rho_dict).catch_()?;
+        let mut child_x_i = sub_shares::<Ed25519>(child_keystore, my_gid, coef).catch_()?;
+        let resp = sign_and_respond(
+            my_id,
+            &child_x_i,
+            &rho_dict,
+            &sig_r,
+            &my_nonce,
+            group_members,
+            &keystore.weights,
+            child_pk,
+            sign_mode,
+            msg,
+        )
+        .catch_()?;
+        child_x_i.zeroize();
+
+        // Each nonce is used exactly once, so refresh it before replying.
+        (my_nonce_com, my_nonce) = sign_preprocess(keystore.mode, x_i, &mut rng).catch_()?;
+        let reply = RoastReply {
+            epoch: keystore.epoch,
+            resp: Some(resp),
+            nonce_com: my_nonce_com.clone(),
+        };
+        k += 1;
+        let reply_topic = format!("roast_reply/{}", k);
+        messenger
+            .send(&reply_topic, my_id, coord_id, &reply)
+            .await
+            .catch_()?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::future::join_all;
+
+    use super::*;
+    use crate::biz_algo::testkit::*;
+    use crate::biz_algo::{chain_code_of, HdScheme};
+
+    const TIMEOUT: Duration = Duration::from_millis(500);
+
+    /// Publish the initial commitment of `keystore`'s shard, for a signer
+    /// which misbehaves afterwards.
+    async fn commit_only(messenger: &MemMessenger, sid: SessionId, keystore: &KeyStore) -> MpcAddr {
+        let messenger = &messenger.scoped(sid);
+        let my_id = *keystore.ids.iter().next().unwrap();
+        let x_i = &keystore.xi_pergroup[&my_id.group_id()];
+        let (nonce_com, _nonce) = sign_preprocess(keystore.mode, x_i, &mut OsRng).unwrap();
+        let reply = RoastReply {
+            epoch: keystore.epoch,
+            resp: None,
+            nonce_com,
+        };
+        let coord_id = MpcAddr::bcast_id();
+        messenger
+            .send("roast_reply/0", my_id, coord_id, &reply)
+            .await
+            .unwrap();
+        my_id
+    }
+
+    /// Run the coordinator with the honest `signers`, and check that every
+    /// party ends up with the same valid signature. If `first` is given, only
+    /// `signers[0]` starts at once and the others some delay after `first` got
+    /// a request, so that the first session is made of `first` and `signers[0]`.
+    async fn sign_roast(
+        messenger: &MemMessenger,
+        sid: SessionId,
+        candidates: &SesArch,
+        signers: &[KeyStore],
+        first: Option<(MpcAddr, Duration)>,
+    ) -> RoastReport {
+        let coordinator = algo_sign_roast_coordinator(
+            messenger,
+            sid,
+            candidates,
+            "m/5",
+            b"roast",
+            &SignMode::Ed25519,
+            &HdScheme::Legacy,
+            &signers[0],
+            TIMEOUT,
+        );
+        let signers_tasks = signers.iter().enumerate().map(|(i, keystore)| async move {
+            if let (true, Some((first, delay))) = (i > 0, first) {
+                let coord_id = MpcAddr::bcast_id();
+                let _: RoastRequest = messenger
+                    .scoped(sid)
+                    .receive("roast_req/0", coord_id, first)
+                    .await
+                    .unwrap();
+                tokio::time::sleep(delay).await;
+            }
+            algo_sign_roast_signer(
+                messenger,
+                sid,
+                "m/5",
+                b"roast",
+                &SignMode::Ed25519,
+                &HdScheme::Legacy,
+                keystore,
+            )
+            .await
+        });
+        let (report, sigs) = tokio::join!(coordinator, join_all(signers_tasks));
+        let report = report.unwrap();
+
+        let (_, child_pk) = HdScheme::Legacy
+            .derive(
+                "m/5",
+                &signers[0].pk().unwrap(),
+                &chain_code_of::<Ed25519>(&signers[0]).unwrap(),
+            )
+            .unwrap();
+        verify_signature(&report.sig, &child_pk).unwrap();
+        Ed25519::cross_check(&report.sig, &child_pk).unwrap();
+        for sig in sigs {
+            assert_eq!(sig.unwrap().r, report.sig.r);
+        }
+        report
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn silent_signer_is_excluded() {
+        let messenger = MemMessenger::default();
+        let arch = key_arch(&[(1, 2, 3)]);
+        let keystores =
+            keygen::<Ed25519>(&messenger, &arch, &paired(&arch), FrostMode::Rfc9591).await;
+        let candidates = ses_arch(&[(1, &[1, 2, 3])]);
+
+        // 1.2 is in the first session, but never responds, and the session
+        // times out before 1.3 is ready for another.
+        let sid = SessionId::random();
+        let silent_id = commit_only(&messenger, sid, &keystores[1]).await;
+        let signers = [keystores[0].clone(), keystores[2].clone()];
+        let report = sign_roast(
+            &messenger,
+            sid,
+            &candidates,
+            &signers,
+            Some((silent_id, TIMEOUT + TIMEOUT / 2)),
+        )
+        .await;
+
+        assert_eq!(report.excluded, HashSet::from([silent_id]));
+        assert!(report.blame.evidence.is_empty());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn invalid_response_is_blamed() {
+        let messenger = MemMessenger::default();
+        let arch = key_arch(&[(1, 2, 3)]);
+        let keystores =
+            keygen::<Ed25519>(&messenger, &arch, &paired(&arch), FrostMode::Legacy).await;
+        let candidates = ses_arch(&[(1, &[1, 2, 3])]);
+
+        // 1.2 is in the first session, and answers with a bogus response.
+        let sid = SessionId::random();
+        let liar_id = commit_only(&messenger, sid, &keystores[1]).await;
+        let liar = async {
+            let messenger = messenger.scoped(sid);
+            let coord_id = MpcAddr::bcast_id();
+            let req: RoastRequest = messenger
+                .receive("roast_req/0", coord_id, liar_id)
+                .await
+                .unwrap();
+            let reply = RoastReply {
+                epoch: req.epoch,
+                resp: Some(Scalar::one()),
+                nonce_com: req.nonce_com_dict[&liar_id].clone(),
+            };
+            messenger
+                .send("roast_reply/1", liar_id, coord_id, &reply)
+                .await
+                .unwrap();
+        };
+        let signers = [keystores[0].clone(), keystores[2].clone()];
+        let (report, _) = tokio::join!(
+            sign_roast(
+                &messenger,
+                sid,
+                &candidates,
+                &signers,
+                Some((liar_id, Duration::ZERO))
+            ),
+            liar
+        );
+
+        assert_eq!(report.excluded, HashSet::from([liar_id]));
+        assert_eq!(report.blame.culprits(), vec![liar_id]);
+        match &report.blame.evidence[&liar_id] {
+            Evidence::InvalidResponse { resp, .. } => assert_eq!(*resp, Scalar::one()),
+            evidence => panic!("Unexpected evidence {:?}", evidence),
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn more_than_th_honest_signers() {
+        let messenger = MemMessenger::default();
+        let arch = key_arch(&[(1, 2, 4), (2, 2, 3)]);
+        let keystores =
+            keygen::<Ed25519>(&messenger, &arch, &paired(&arch), FrostMode::Rfc9591).await;
+        let candidates = ses_arch(&[(1, &[1, 2, 3, 4]), (2, &[1, 2, 3])]);
+
+        let report = sign_roast(
+            &messenger,
+            SessionId::random(),
+            &candidates,
+            &keystores,
+            None,
+        )
+        .await;
+
+        assert!(report.excluded.is_empty());
+        assert!(report.blame.evidence.is_empty());
+    }
+}
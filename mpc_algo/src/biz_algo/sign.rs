@@ -1,24 +1,94 @@
-use curve25519_dalek::edwards::EdwardsPoint;
-use curve25519_dalek::{constants, scalar::Scalar};
 use libexception::*;
 use mpc_spec::*;
 use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
+use zeroize::Zeroize;
 
-use super::{hd::*, KeyStore};
+use super::{group_coefs, hd::*, KeyStore};
 use crate::frost::{
-    agg_nonce_com, eval_xi_com, gen_rho_i, generate_challenge, is_valid_response, lagrange_lambda,
-    sign_and_respond, sign_preprocess, verify_signature, verify_solana, Signature,
+    agg_nonce_com, eval_signer_com, gen_rho_dict, generate_challenge, is_valid_response,
+    sign_and_respond, sign_preprocess, verify_signature, Blame, Ciphersuite, Evidence,
     SigningCommitmentPair, SigningNoncePair,
 };
+pub use crate::frost::{SignMode, Signature};
 
-pub async fn algo_sign(
+/// A message tagged with the keystore epoch of its sender, so that shares of
+/// different epochs are never combined.
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct EpochMsg<T> {
+    pub epoch: u64,
+    pub body: T,
+}
+
+impl<T> EpochMsg<T> {
+    pub fn new<C: Ciphersuite>(keystore: &KeyStore<C>, body: T) -> Self {
+        EpochMsg {
+            epoch: keystore.epoch,
+            body,
+        }
+    }
+
+    /// Accuse `src` if it is at another epoch than `keystore`.
+    pub fn check<C: Ciphersuite>(
+        &self,
+        keystore: &KeyStore<C>,
+        src: MpcAddr,
+        blame: &mut Blame<C>,
+    ) {
+        if self.epoch != keystore.epoch {
+            let (expected, got) = (keystore.epoch, self.epoch);
+            blame.accuse(src, Evidence::EpochMismatch { expected, got });
+        }
+    }
+}
+
+/// Sign over the ciphersuite `C` of `keystore`, e.g. `algo_sign::<Ed25519>(..)`.
+pub async fn algo_sign<C: Ciphersuite>(
     messenger: &impl Messenger,
+    sid: SessionId,
     ses_arch: &HashMap<u16, HashSet<MpcAddr>>,
     drv_path: &str,
-    msg_hash: &[u8],
-    keystore: &KeyStore,
-) -> Outcome<Signature> {
+    msg: &[u8],
+    sign_mode: &C::SignMode,
+    hd_scheme: &impl DerivationScheme<C>,
+    keystore: &KeyStore<C>,
+) -> Outcome<Signature<C>> {
+    let mut sigs = algo_sign_batch::<C>(
+        messenger,
+        sid,
+        ses_arch,
+        &[(drv_path, msg)],
+        sign_mode,
+        hd_scheme,
+        keystore,
+    )
+    .await
+    .catch_()?;
+    let sig = sigs.pop().ifnone_()?;
+    Ok(sig)
+}
+
+/// Sign every `(drv_path, msg)` item within the same `nonce_com` and `sign_resp`
+/// rounds. Each item consumes a nonce pair of its own, and signatures are
+/// returned in the order of `items`.
+///
+/// Mailboxes are namespaced by `sid`. The challenge is left as `C` defines
+/// it, so that signatures verify as usual; messages of the session are bound
+/// to `sid` by their topics instead, which `AuthMessenger` signs, and by the
+/// binding factors in `FrostMode::Legacy`.
+pub async fn algo_sign_batch<C: Ciphersuite>(
+    messenger: &impl Messenger,
+    sid: SessionId,
+    ses_arch: &HashMap<u16, HashSet<MpcAddr>>,
+    items: &[(&str, &[u8])], // (drv_path, msg)
+    sign_mode: &C::SignMode,
+    hd_scheme: &impl DerivationScheme<C>,
+    keystore: &KeyStore<C>,
+) -> Outcome<Vec<Signature<C>>> {
+    C::validate_mode(sign_mode).catch_()?;
+    assert_throw!(!items.is_empty(), "Nothing to sign");
+    let messenger = &messenger.scoped(sid);
     let bcast_id = MpcAddr::bcast_id();
     let mut whoami_asc: Vec<MpcAddr> = keystore.ids.iter().cloned().collect();
     whoami_asc.sort();
@@ -26,159 +96,474 @@ pub async fn algo_sign(
     let mut group_ids_asc: Vec<u16> = ses_arch.keys().cloned().collect();
     group_ids_asc.sort();
 
-    // Derive child pk
-    let main_pk = keystore.pk().catch_()?;
-    let chain_code = eval_chain_code(&main_pk);
-    let (tweak_sk, child_pk) = match drv_path.is_empty() {
-        true => (Scalar::zero(), main_pk),
-        false => non_hardened_derive(drv_path, &main_pk, &chain_code).catch_()?,
-    };
-
-    // apply bip32 tweak
-    let keystore = {
-        let mut ks = keystore.clone();
-        let xi_pergroup = &mut ks.xi_pergroup;
-        let pivot_gid = xi_pergroup.keys().min().ifnone_()?.clone();
-        let x_i = xi_pergroup.get_mut(&pivot_gid).ifnone_()?;
-        *x_i += tweak_sk;
-
-        let vss_com_grid = &mut ks.vss_com_grid;
-        let pivot_id = vss_com_grid
-            .get(&pivot_gid)
-            .ifnone_()?
-            .keys()
-            .min()
-            .ifnone_()?
-            .clone();
-        let vss_com = vss_com_grid
-            .get_mut(&pivot_gid)
-            .ifnone_()?
-            .get_mut(&pivot_id)
-            .ifnone_()?;
-        vss_com[0] += &constants::ED25519_BASEPOINT_TABLE * &tweak_sk;
-
-        ks
-    };
-
-    // Compute dict of $x_j * G$ without knowing $x_j$
-    let mut xjg_dict: HashMap<MpcAddr, EdwardsPoint> = HashMap::new();
-    for gid in group_ids_asc.iter() {
-        let vss_com_dict = keystore.vss_com_grid.get(gid).ifnone(
-            "InvalidGroup",
-            format!(
-                "Group {} not found in vss_com_grid {:?}",
-                gid,
-                keystore.vss_com_grid.keys().collect::<Vec<&u16>>()
-            ),
-        )?;
-        let group_members = ses_arch.get(gid).ifnone_()?;
-        for j in group_members.iter() {
-            let xjg /* $x_j * G$ */ = eval_xi_com(*j, vss_com_dict);
-            xjg_dict.insert(*j, xjg);
-        }
-    }
-
-    // Generate nonce pair $(d, e)$, and broadcast $(dG, eG)$.
-    let mut my_nonce_dict: HashMap<MpcAddr, SigningNoncePair> = HashMap::new();
+    // Generate one nonce pair $(d, e)$ per item, and broadcast $(dG, eG)$.
+    let mut my_nonce_dict: HashMap<MpcAddr, Vec<SigningNoncePair<C>>> = HashMap::new();
     for my_id in whoami_asc.iter() {
-        let _obj: _ = sign_preprocess(&mut rng).catch_()?;
-        let nonce_com: SigningCommitmentPair = _obj.0;
-        let nonce_pair: SigningNoncePair = _obj.1;
-        my_nonce_dict.insert(*my_id, nonce_pair.clone());
+        let x_i = keystore.xi_pergroup.get(&my_id.group_id()).ifnone_()?;
+        let mut nonce_com_list: Vec<SigningCommitmentPair<C>> = Vec::with_capacity(items.len());
+        let mut nonce_pair_list: Vec<SigningNoncePair<C>> = Vec::with_capacity(items.len());
+        for _ in items.iter() {
+            let _obj: _ = sign_preprocess::<C, _>(keystore.mode, x_i, &mut rng).catch_()?;
+            nonce_com_list.push(_obj.0);
+            nonce_pair_list.push(_obj.1);
+        }
+        my_nonce_dict.insert(*my_id, nonce_pair_list);
+        let msg = EpochMsg::new::<C>(keystore, nonce_com_list);
         messenger
-            .send("nonce_com", *my_id, bcast_id, &nonce_com)
+            .send("nonce_com", *my_id, bcast_id, &msg)
             .await
             .catch_()?;
         println!("{} broadcast nonce_com", my_id);
     }
 
-    // Gather $(dG, eG)$.
-    let mut nonce_com_dict: HashMap<MpcAddr, SigningCommitmentPair> = HashMap::new();
+    // Gather $(dG, eG)$, and arrange them per item.
+    let mut nonce_com_dict_list: Vec<HashMap<MpcAddr, SigningCommitmentPair<C>>> =
+        vec![HashMap::new(); items.len()];
     for gid in group_ids_asc.iter() {
         let group_members = ses_arch.get(gid).ifnone_()?;
-        let obj = messenger
+        let obj: HashMap<MpcAddr, EpochMsg<Vec<SigningCommitmentPair<C>>>> = messenger
             .gather("nonce_com", group_members, bcast_id)
             .await
             .catch_()?;
-        nonce_com_dict.extend(obj);
+        let mut blame = Blame::<C>::default();
+        for (j, msg) in obj.iter() {
+            msg.check(keystore, *j, &mut blame);
+            let nonce_com_list = &msg.body;
+            if nonce_com_list.len() != items.len() {
+                let (expected, got) = (items.len(), nonce_com_list.len());
+                blame.accuse(*j, Evidence::WrongItemCount { expected, got });
+            }
+        }
+        blame.throw_if_any()?;
+        for (j, msg) in obj.into_iter() {
+            let nonce_com_list = msg.body;
+            for (k, nonce_com) in nonce_com_list.into_iter().enumerate() {
+                nonce_com_dict_list[k].insert(j, nonce_com);
+            }
+        }
     }
 
-    // Compute rho dict
-    let mut rho_dict: HashMap<MpcAddr, Scalar> = HashMap::new();
-    for (j, _) in nonce_com_dict.iter() {
-        let rho_i = gen_rho_i(*j, msg_hash, &nonce_com_dict);
-        rho_dict.insert(*j, rho_i);
+    sign_with_nonces(
+        messenger,
+        "sign_resp",
+        ses_arch,
+        items,
+        sign_mode,
+        hd_scheme,
+        keystore,
+        my_nonce_dict,
+        nonce_com_dict_list,
+    )
+    .await
+}
+
+/// The `sign_resp` round, given that every signer has committed to a nonce pair
+/// per item, either in the `nonce_com` round or ahead of time.
+pub(crate) async fn sign_with_nonces<C: Ciphersuite>(
+    messenger: &Scoped<'_, impl Messenger>,
+    topic: &str,
+    ses_arch: &HashMap<u16, HashSet<MpcAddr>>,
+    items: &[(&str, &[u8])], // (drv_path, msg)
+    sign_mode: &C::SignMode,
+    hd_scheme: &impl DerivationScheme<C>,
+    keystore: &KeyStore<C>,
+    my_nonce_dict: HashMap<MpcAddr, Vec<SigningNoncePair<C>>>,
+    nonce_com_dict_list: Vec<HashMap<MpcAddr, SigningCommitmentPair<C>>>,
+) -> Outcome<Vec<Signature<C>>> {
+    let bcast_id = MpcAddr::bcast_id();
+    let mut whoami_asc: Vec<MpcAddr> = keystore.ids.iter().cloned().collect();
+    whoami_asc.sort();
+    let mut group_ids_asc: Vec<u16> = ses_arch.keys().cloned().collect();
+    group_ids_asc.sort();
+    assert_throw!(nonce_com_dict_list.len() == items.len());
+    let coef_dict = group_coefs::<C>(keystore, &ses_arch.keys().cloned().collect()).catch_()?;
+
+    // Derive child pk, and apply bip32 tweak, for each item
+    let mut child_pk_list: Vec<C::Point> = Vec::with_capacity(items.len());
+    let mut keystore_list: Vec<KeyStore<C>> = Vec::with_capacity(items.len());
+    for (drv_path, _) in items.iter() {
+        let (child_pk, child_keystore) =
+            derive_child::<C>(keystore, drv_path, hd_scheme).catch_()?;
+        child_pk_list.push(child_pk);
+        keystore_list.push(child_keystore);
+    }
+
+    // Compute dict of $\lambda_j x_j * G$ without knowing $x_j$, for each item
+    let mut xjg_dict_list: Vec<HashMap<MpcAddr, C::Point>> = Vec::new();
+    for keystore in keystore_list.iter() {
+        let mut xjg_dict: HashMap<MpcAddr, C::Point> = HashMap::new();
+        for gid in group_ids_asc.iter() {
+            let vss_com_dict = keystore.vss_com_grid.get(gid).ifnone(
+                "InvalidGroup",
+                format!(
+                    "Group {} not found in vss_com_grid {:?}",
+                    gid,
+                    keystore.vss_com_grid.keys().collect::<Vec<&u16>>()
+                ),
+            )?;
+            let group_members = ses_arch.get(gid).ifnone_()?;
+            for j in group_members.iter() {
+                let xjg /* $\lambda_j x_j * G$ */ =
+                    eval_signer_com::<C>(*j, group_members, &keystore.weights, vss_com_dict)
+                        .catch_()?;
+                xjg_dict.insert(*j, xjg);
+            }
+        }
+        xjg_dict_list.push(xjg_dict);
     }
 
-    // Aggregate sig.r
-    let sig_r = agg_nonce_com(&nonce_com_dict, &rho_dict).catch_()?;
+    // Compute rho dict and aggregate sig.r, for each item
+    let mut rho_dict_list: Vec<HashMap<MpcAddr, C::Scalar>> = Vec::with_capacity(items.len());
+    let mut sig_r_list: Vec<C::Point> = Vec::with_capacity(items.len());
+    let mut negate_r_list: Vec<bool> = Vec::with_capacity(items.len());
+    for (k, (_, msg)) in items.iter().enumerate() {
+        let signed_bytes = C::signed_bytes(sign_mode, msg);
+        let rho_dict: HashMap<MpcAddr, C::Scalar> = gen_rho_dict(
+            keystore.mode,
+            messenger.sid(),
+            &child_pk_list[k],
+            &signed_bytes,
+            &nonce_com_dict_list[k],
+        );
+        let sig_r = agg_nonce_com(&nonce_com_dict_list[k], &rho_dict).catch_()?;
+        // BIP-340 takes the R of even y, for which every signer negates its nonces.
+        let negate_r = C::has_odd_y(&sig_r);
+        rho_dict_list.push(rho_dict);
+        sig_r_list.push(if negate_r { -sig_r } else { sig_r });
+        negate_r_list.push(negate_r);
+    }
 
-    // Generate and broadcast sign response
+    // Generate and broadcast sign responses
     for my_id in whoami_asc.iter() {
-        let my_nonce = my_nonce_dict.get(my_id).ifnone_()?;
+        let my_nonce_list = my_nonce_dict.get(my_id).ifnone_()?;
         let my_gid = my_id.group_id();
-        let x_i = keystore.xi_pergroup.get(&my_gid).ifnone_()?;
         let group_members = ses_arch.get(&my_gid).ifnone_()?;
-        let sign_resp = sign_and_respond(
-            *my_id,
-            x_i,
-            &rho_dict,
-            &sig_r,
-            my_nonce,
-            group_members,
-            &child_pk,
-            msg_hash,
-        )
-        .catch_()?;
+        let coef = coef_dict.get(&my_gid).ifnone_()?;
+        let mut sign_resp_list: Vec<C::Scalar> = Vec::with_capacity(items.len());
+        for (k, (_, msg)) in items.iter().enumerate() {
+            let mut x_i = sub_shares::<C>(&keystore_list[k], my_gid, coef).catch_()?;
+            let nonce = match negate_r_list[k] {
+                true => my_nonce_list[k].negate(),
+                false => my_nonce_list[k],
+            };
+            let sign_resp = sign_and_respond(
+                *my_id,
+                &x_i,
+                &rho_dict_list[k],
+                &sig_r_list[k],
+                &nonce,
+                group_members,
+                &keystore.weights,
+                &child_pk_list[k],
+                sign_mode,
+                msg,
+            )
+            .catch_()?;
+            x_i.zeroize();
+            sign_resp_list.push(sign_resp);
+        }
         messenger
-            .send("sign_resp", *my_id, bcast_id, &sign_resp)
+            .send(topic, *my_id, bcast_id, &sign_resp_list)
             .await
             .catch_()?;
     }
     drop(my_nonce_dict);
 
-    // Gather sign response
-    let mut resp_dict: HashMap<MpcAddr, Scalar> = HashMap::new();
+    // Gather sign responses, and arrange them per item.
+    let mut resp_dict_list: Vec<HashMap<MpcAddr, C::Scalar>> = vec![HashMap::new(); items.len()];
     for gid in group_ids_asc.iter() {
         let group_members = ses_arch.get(gid).ifnone_()?;
-        let obj = messenger
-            .gather("sign_resp", group_members, bcast_id)
+        let obj: HashMap<MpcAddr, Vec<C::Scalar>> = messenger
+            .gather(topic, group_members, bcast_id)
             .await
             .catch_()?;
-        resp_dict.extend(obj);
+        let mut blame = Blame::<C>::default();
+        for (j, resp_list) in obj.iter() {
+            if resp_list.len() != items.len() {
+                let (expected, got) = (items.len(), resp_list.len());
+                blame.accuse(*j, Evidence::WrongItemCount { expected, got });
+            }
+        }
+        blame.throw_if_any()?;
+        for (j, resp_list) in obj.into_iter() {
+            for (k, resp) in resp_list.into_iter().enumerate() {
+                resp_dict_list[k].insert(j, resp);
+            }
+        }
+    }
+
+    // Validate each participant's responses
+    let mut blame = Blame::<C>::default();
+    for (k, (_, msg)) in items.iter().enumerate() {
+        let (child_pk, sig_r) = (&child_pk_list[k], &sig_r_list[k]);
+        let (rho_dict, nonce_com_dict) = (&rho_dict_list[k], &nonce_com_dict_list[k]);
+        let xjg_dict = &xjg_dict_list[k];
+
+        // Compute challenge
+        let challenge = generate_challenge::<C>(sign_mode, msg, sig_r, child_pk);
+
+        for (j, resp) in resp_dict_list[k].iter() {
+            let rho_j = rho_dict.get(j).ifnone_()?;
+            let lam_i = coef_dict.get(&j.group_id()).ifnone_()?;
+            let nonce_com = nonce_com_dict.get(j).ifnone_()?;
+            let mut com = nonce_com.g_d + (nonce_com.g_e * *rho_j);
+            if negate_r_list[k] {
+                com = -com;
+            }
+            let xjg = xjg_dict.get(j).ifnone_()?; // $x_j * G$
+
+            let resp_is_valid = is_valid_response::<C>(resp, xjg, lam_i, &com, &challenge);
+            if !resp_is_valid {
+                let (resp, nonce_com) = (*resp, nonce_com.clone());
+                let evidence = Evidence::InvalidResponse {
+                    item: k,
+                    resp,
+                    nonce_com,
+                };
+                blame.accuse(*j, evidence);
+            }
+        }
     }
+    blame.throw_if_any()?;
 
-    // Compute challenge
-    let challenge = generate_challenge(msg_hash, &sig_r, &child_pk);
+    let mut sigs: Vec<Signature<C>> = Vec::with_capacity(items.len());
+    for (k, (_, msg)) in items.iter().enumerate() {
+        let child_pk = &child_pk_list[k];
 
-    // Validate each participant's response
-    for (j, resp) in resp_dict.iter() {
-        let rho_j = rho_dict.get(j).ifnone_()?;
-        let signers = ses_arch.get(&j.group_id()).ifnone_()?;
-        let lam_i = lagrange_lambda(*j, signers).catch_()?;
-        let nonce_com = nonce_com_dict.get(j).ifnone_()?;
-        let com = nonce_com.g_d + (nonce_com.g_e * rho_j);
-        let xjg = xjg_dict.get(j).ifnone_()?; // $x_j * G$
+        // Aggregate sig_s
+        let mut sig_s = C::Scalar::default();
+        for resp in resp_dict_list[k].values() {
+            sig_s += *resp;
+        }
+        let sig = Signature {
+            r: sig_r_list[k],
+            s: sig_s,
+            msg: msg.to_vec(),
+            mode: sign_mode.clone(),
+        };
 
-        let resp_is_valid = is_valid_response(resp, xjg, &lam_i, &com, &challenge);
-        assert_throw!(resp_is_valid, "Invalid signer response");
+        verify_signature(&sig, child_pk)
+            .catch("InvalidSignature", "Most probably lack of signers")?;
+        C::cross_check(&sig, child_pk).catch("", "Failed at cross_check()")?;
+        sigs.push(sig);
     }
+    println!("Finished aggregating signature shares");
 
-    // Aggregate sig_s
-    let mut sig_s = Scalar::zero();
-    for resp in resp_dict.values() {
-        sig_s += resp;
+    Ok(sigs)
+}
+
+/// My sub-shares of group `gid`, each multiplied by the group coefficient `coef`.
+pub(crate) fn sub_shares<C: Ciphersuite>(
+    keystore: &KeyStore<C>,
+    gid: u16,
+    coef: &C::Scalar,
+) -> Outcome<Vec<C::Scalar>> {
+    let mut x_i = vec![*coef * *keystore.xi_pergroup.get(&gid).ifnone_()?];
+    if let Some(sub_xi) = keystore.sub_xi_pergroup.get(&gid) {
+        x_i.extend(sub_xi.iter().map(|x| *coef * *x));
     }
-    let sig = Signature {
-        r: sig_r,
-        s: sig_s,
-        hash: msg_hash.to_vec(),
+    Ok(x_i)
+}
+
+/// Derive the child pk of `drv_path` by `hd_scheme`, along with a keystore of
+/// the child key, i.e. with the bip32 tweak applied.
+pub(crate) fn derive_child<C: Ciphersuite>(
+    keystore: &KeyStore<C>,
+    drv_path: &str,
+    hd_scheme: &impl DerivationScheme<C>,
+) -> Outcome<(C::Point, KeyStore<C>)> {
+    let main_pk = keystore.pk().catch_()?;
+    let (tweak_sk, child_pk) = match drv_path.is_empty() && !hd_scheme.derives_root() {
+        true => (C::Scalar::default(), main_pk),
+        false => {
+            let chain_code = chain_code_of::<C>(keystore).catch_()?;
+            hd_scheme.derive(drv_path, &main_pk, &chain_code).catch_()?
+        }
     };
+    let child_keystore = apply_tweak::<C>(keystore, &tweak_sk).catch_()?;
+    if C::has_odd_y(&child_pk) {
+        // BIP-340 signs for the key of even y, i.e. with every share negated.
+        return Ok((-child_pk, negate_key::<C>(&child_keystore)));
+    }
+    Ok((child_pk, child_keystore))
+}
 
-    verify_signature(&sig, &child_pk).catch("InvalidSignature", "Most probably lack of signers")?;
-    verify_solana(&sig, &child_pk).catch("", "Failed at verify_solana()")?;
-    println!("Finished aggregating signature shares");
-    // #endregion
+/// The keystore of $-Y$, i.e. with every share and commitment negated.
+fn negate_key<C: Ciphersuite>(keystore: &KeyStore<C>) -> KeyStore<C> {
+    let mut ks = keystore.clone();
+    for x_i in ks.xi_pergroup.values_mut() {
+        *x_i = -*x_i;
+    }
+    for x_i in ks.sub_xi_pergroup.values_mut().flatten() {
+        *x_i = -*x_i;
+    }
+    for vss_com in ks.vss_com_grid.values_mut().flat_map(|d| d.values_mut()) {
+        for com in vss_com.iter_mut() {
+            *com = -*com;
+        }
+    }
+    if let Some(policy_pk) = ks.policy_pk.as_mut() {
+        *policy_pk = -*policy_pk;
+    }
+    ks
+}
 
-    Ok(sig)
+/// Add the bip32 tweak to the pivot group, i.e. the one with the smallest group id.
+///
+/// With an access policy, the group coefficients of any qualified set sum to
+/// one, so the tweak is added to every group instead.
+fn apply_tweak<C: Ciphersuite>(
+    keystore: &KeyStore<C>,
+    tweak_sk: &C::Scalar,
+) -> Outcome<KeyStore<C>> {
+    let mut ks = keystore.clone();
+    let tweak_pk = C::base_mul(tweak_sk);
+    if ks.policy.is_some() {
+        for x_i in ks.xi_pergroup.values_mut() {
+            *x_i += *tweak_sk;
+        }
+        for x_i in ks.sub_xi_pergroup.values_mut().flatten() {
+            *x_i += *tweak_sk;
+        }
+        for vss_com_dict in ks.vss_com_grid.values_mut() {
+            let pivot_id = *vss_com_dict.keys().min().ifnone_()?;
+            vss_com_dict.get_mut(&pivot_id).ifnone_()?[0] += tweak_pk;
+        }
+        let policy_pk = ks.policy_pk.as_mut().ifnone_()?;
+        *policy_pk += tweak_pk;
+        return Ok(ks);
+    }
+    let xi_pergroup = &mut ks.xi_pergroup;
+    let pivot_gid = xi_pergroup.keys().min().ifnone_()?.clone();
+    let x_i = xi_pergroup.get_mut(&pivot_gid).ifnone_()?;
+    *x_i += *tweak_sk;
+    // so are the other sub-shares of a weighted member
+    if let Some(sub_xi) = ks.sub_xi_pergroup.get_mut(&pivot_gid) {
+        for x_i in sub_xi.iter_mut() {
+            *x_i += *tweak_sk;
+        }
+    }
+
+    let vss_com_grid = &mut ks.vss_com_grid;
+    let pivot_id = vss_com_grid
+        .get(&pivot_gid)
+        .ifnone_()?
+        .keys()
+        .min()
+        .ifnone_()?
+        .clone();
+    let vss_com = vss_com_grid
+        .get_mut(&pivot_gid)
+        .ifnone_()?
+        .get_mut(&pivot_id)
+        .ifnone_()?;
+    vss_com[0] += tweak_pk;
+
+    Ok(ks)
+}
+
+#[cfg(test)]
+mod tests {
+    use curve25519_dalek::scalar::Scalar;
+    use futures_util::future::join_all;
+
+    use super::*;
+    use crate::biz_algo::testkit::*;
+    use crate::frost::Ed25519;
+
+    const ITEMS: [(&str, &[u8]); 2] = [("m/1", b"first"), ("m/2/3", b"second")];
+
+    async fn sign_batch(
+        messenger: &MemMessenger,
+        ses_arch: &SesArch,
+        keystores: &[KeyStore],
+    ) -> Vec<Outcome<Vec<Signature>>> {
+        let sid = SessionId::random();
+        let tasks = keystores.iter().map(|keystore| {
+            algo_sign_batch::<Ed25519>(
+                messenger,
+                sid,
+                ses_arch,
+                &ITEMS,
+                &SignMode::Ed25519,
+                &HdScheme::Legacy,
+                keystore,
+            )
+        });
+        join_all(tasks).await
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn batch_signs_each_item_under_its_own_child_key() {
+        let messenger = MemMessenger::default();
+        let arch = key_arch(&[(1, 2, 3)]);
+        let keystores =
+            keygen::<Ed25519>(&messenger, &arch, &paired(&arch), FrostMode::Rfc9591).await;
+        let ses_arch = ses_arch(&[(1, &[1, 3])]);
+        let signers = [keystores[0].clone(), keystores[2].clone()];
+
+        for sigs in sign_batch(&messenger, &ses_arch, &signers).await {
+            let sigs = sigs.unwrap();
+            assert_eq!(sigs.len(), ITEMS.len());
+            for (sig, (drv_path, msg)) in sigs.iter().zip(ITEMS.iter()) {
+                let (child_pk, _) =
+                    derive_child::<Ed25519>(&keystores[0], drv_path, &HdScheme::Legacy).unwrap();
+                assert_eq!(sig.msg, *msg);
+                verify_signature(sig, &child_pk).unwrap();
+            }
+        }
+
+        // A bad response to the second item is blamed on that item alone.
+        let cheater = MpcAddr::new(1, 3);
+        let tampered = messenger.tampered(move |topic, src, _, value| {
+            if topic.ends_with("/sign_resp") && src == cheater {
+                retype(value, |resps: &mut Vec<Scalar>| resps[1] += Scalar::one());
+            }
+            true
+        });
+        let results = sign_batch(&tampered, &ses_arch, &signers).await;
+        let blame = Blame::of(results[0].as_ref().unwrap_err()).unwrap();
+        assert_eq!(blame.culprits(), vec![cheater]);
+        assert!(matches!(
+            blame.evidence[&cheater],
+            Evidence::InvalidResponse { item: 1, .. }
+        ));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn concurrent_sessions_do_not_cross() {
+        let messenger = MemMessenger::default();
+        let arch = key_arch(&[(1, 2, 3)]);
+        let keystores =
+            keygen::<Ed25519>(&messenger, &arch, &paired(&arch), FrostMode::Legacy).await;
+        let pk = keystores[0].pk().unwrap();
+        let ses_arch = ses_arch(&[(1, &[1, 3])]);
+        let signers = [keystores[0].clone(), keystores[2].clone()];
+
+        // The same signers sign two messages at once, told apart by the sid.
+        let sessions = [
+            (SessionId::random(), b"one".as_slice()),
+            (SessionId::random(), b"two".as_slice()),
+        ];
+        let tasks = sessions.iter().flat_map(|(sid, msg)| {
+            signers.iter().map(|keystore| {
+                algo_sign::<Ed25519>(
+                    &messenger,
+                    *sid,
+                    &ses_arch,
+                    "",
+                    msg,
+                    &SignMode::Ed25519,
+                    &NoDerivation,
+                    keystore,
+                )
+            })
+        });
+        let sigs = join_all(tasks).await;
+        for (k, sig) in sigs.into_iter().enumerate() {
+            let sig = sig.unwrap();
+            assert_eq!(sig.msg, sessions[k / signers.len()].1);
+            verify_signature(&sig, &pk).unwrap();
+        }
+    }
 }
@@ -0,0 +1,240 @@
+//! In-memory messenger and fixtures for the protocol tests of this module.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures_util::future::join_all;
+use libexception::*;
+use mpc_spec::*;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+use super::{algo_keygen, algo_sign, KeyStore, NoDerivation};
+use crate::frost::{Ciphersuite, Signature};
+
+pub type KeyArch = HashMap<u16, (usize, HashSet<MpcAddr>)>;
+pub type SesArch = HashMap<u16, HashSet<MpcAddr>>;
+
+/// Rewrites what is received from `src` to `dst` on a topic, or withholds it
+/// for good if it returns false.
+pub type Tamper = Arc<dyn Fn(&str, MpcAddr, MpcAddr, &mut Value) -> bool + Send + Sync>;
+
+/// Mailboxes shared by every party of a test, keyed by topic, src and dst.
+#[derive(Clone, Default)]
+pub struct MemMessenger {
+    mailbox: Arc<Mutex<HashMap<String, Vec<u8>>>>,
+    tamper: Option<Tamper>,
+}
+
+fn mailbox_key(topic: &str, src: MpcAddr, dst: MpcAddr) -> String {
+    format!("{}|{}|{}", topic, src, dst)
+}
+
+impl MemMessenger {
+    /// A view of the same mailboxes in which what is received goes through
+    /// `tamper` first, e.g. to show one party other messages than the rest.
+    pub fn tampered<F>(&self, tamper: F) -> Self
+    where
+        F: Fn(&str, MpcAddr, MpcAddr, &mut Value) -> bool + Send + Sync + 'static,
+    {
+        MemMessenger {
+            mailbox: self.mailbox.clone(),
+            tamper: Some(Arc::new(tamper)),
+        }
+    }
+
+    /// What `src` sent to `dst` on `topic`, if anything.
+    pub fn peek<T: DeserializeOwned>(&self, topic: &str, src: MpcAddr, dst: MpcAddr) -> Option<T> {
+        let mailbox = self.mailbox.lock().unwrap();
+        let buf = mailbox.get(&mailbox_key(topic, src, dst))?;
+        serde_json::from_slice(buf).ok()
+    }
+
+    /// Whether anything was sent on `topic`.
+    pub fn has_topic(&self, topic: &str) -> bool {
+        let prefix = format!("{}|", topic);
+        let mailbox = self.mailbox.lock().unwrap();
+        mailbox.keys().any(|key| key.starts_with(&prefix))
+    }
+}
+
+#[async_trait]
+impl Messenger for MemMessenger {
+    type E = Box<Exception>;
+
+    async fn send<T>(&self, topic: &str, src: MpcAddr, dst: MpcAddr, obj: &T) -> Outcome<()>
+    where
+        T: Serialize + DeserializeOwned + Send + Sync,
+    {
+        let buf = serde_json::to_vec(obj).catch_()?;
+        let mut mailbox = self.mailbox.lock().unwrap();
+        mailbox.insert(mailbox_key(topic, src, dst), buf);
+        Ok(())
+    }
+
+    async fn receive<T>(&self, topic: &str, src: MpcAddr, dst: MpcAddr) -> Outcome<T>
+    where
+        T: Serialize + DeserializeOwned + Send + Sync,
+    {
+        let key = mailbox_key(topic, src, dst);
+        loop {
+            let buf = self.mailbox.lock().unwrap().get(&key).cloned();
+            if let Some(buf) = buf {
+                let Some(tamper) = self.tamper.as_ref() else {
+                    let obj = serde_json::from_slice(&buf).catch_()?;
+                    return Ok(obj);
+                };
+                let mut value: Value = serde_json::from_slice(&buf).catch_()?;
+                if tamper(topic, src, dst, &mut value) {
+                    let obj = serde_json::from_value(value).catch_()?;
+                    return Ok(obj);
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(2)).await;
+        }
+    }
+
+    async fn scatter<T>(
+        &self,
+        topic: &str,
+        src: MpcAddr,
+        dsts: &HashSet<MpcAddr>,
+        obj: &T,
+    ) -> Outcome<()>
+    where
+        T: Serialize + DeserializeOwned + Send + Sync,
+    {
+        for dst in dsts.iter() {
+            self.send(topic, src, *dst, obj).await?;
+        }
+        Ok(())
+    }
+
+    async fn gather<T>(
+        &self,
+        topic: &str,
+        srcs: &HashSet<MpcAddr>,
+        dst: MpcAddr,
+    ) -> Outcome<HashMap<MpcAddr, T>>
+    where
+        T: Serialize + DeserializeOwned + Send + Sync,
+    {
+        let mut obj_dict = HashMap::new();
+        for src in srcs.iter() {
+            let obj = self.receive(topic, *src, dst).await?;
+            obj_dict.insert(*src, obj);
+        }
+        Ok(obj_dict)
+    }
+}
+
+/// Rewrite `value` as a `T`, for a `Tamper`.
+pub fn retype<T: Serialize + DeserializeOwned>(value: &mut Value, edit: impl FnOnce(&mut T)) {
+    let mut obj: T = serde_json::from_value(value.take()).unwrap();
+    edit(&mut obj);
+    *value = serde_json::to_value(&obj).unwrap();
+}
+
+/// `(group_id, th, n)` to a key arch of members `1..=n`.
+pub fn key_arch(spec: &[(u16, usize, u16)]) -> KeyArch {
+    let mut arch = KeyArch::new();
+    for (gid, th, n) in spec.iter() {
+        let members = (1..=*n).map(|i| MpcAddr::new(*gid, i)).collect();
+        arch.insert(*gid, (*th, members));
+    }
+    arch
+}
+
+/// `(group_id, member_ids)` to a session arch.
+pub fn ses_arch(spec: &[(u16, &[u16])]) -> SesArch {
+    let mut arch = SesArch::new();
+    for (gid, member_ids) in spec.iter() {
+        let members = member_ids.iter().map(|i| MpcAddr::new(*gid, *i)).collect();
+        arch.insert(*gid, members);
+    }
+    arch
+}
+
+/// Party `i` holds member `i` of every group, e.g. a phone and a server
+/// each holding one shard of two groups.
+pub fn paired(arch: &KeyArch) -> Vec<Vec<MpcAddr>> {
+    let n = arch
+        .values()
+        .map(|(_, members)| members.len())
+        .max()
+        .unwrap();
+    let mut group_ids_asc: Vec<u16> = arch.keys().cloned().collect();
+    group_ids_asc.sort();
+    let mut parties = Vec::new();
+    for i in 1..=n as u16 {
+        let whoami: Vec<MpcAddr> = group_ids_asc
+            .iter()
+            .map(|gid| MpcAddr::new(*gid, i))
+            .filter(|id| arch[&id.group_id()].1.contains(id))
+            .collect();
+        parties.push(whoami);
+    }
+    parties
+}
+
+/// Run keygen for every party at once.
+pub async fn keygen<C: Ciphersuite>(
+    messenger: &MemMessenger,
+    arch: &KeyArch,
+    parties: &[Vec<MpcAddr>],
+    mode: FrostMode,
+) -> Vec<KeyStore<C>> {
+    let sid = SessionId::random();
+    let tasks = parties
+        .iter()
+        .map(|whoami| algo_keygen::<C>(messenger, sid, arch, whoami, "test", mode));
+    join_all(tasks)
+        .await
+        .into_iter()
+        .map(|keystore| keystore.unwrap())
+        .collect()
+}
+
+/// Sign `msg` with the root key by every keystore at once. Each keystore
+/// must hold only members of `ses_arch`.
+pub async fn sign<C: Ciphersuite>(
+    messenger: &MemMessenger,
+    ses_arch: &SesArch,
+    keystores: &[KeyStore<C>],
+    msg: &[u8],
+    sign_mode: &C::SignMode,
+) -> Vec<Outcome<Signature<C>>> {
+    let sid = SessionId::random();
+    let tasks = keystores.iter().map(|keystore| {
+        algo_sign::<C>(
+            messenger,
+            sid,
+            ses_arch,
+            "",
+            msg,
+            sign_mode,
+            &NoDerivation,
+            keystore,
+        )
+    });
+    join_all(tasks).await
+}
+
+/// Keygen a 2-of-3 key over `C`, and sign `msg` with members 1 and 3. Returns
+/// the public key and the signature, which every ciphersuite checks against
+/// its own verifier.
+pub async fn keygen_and_sign<C: Ciphersuite>(
+    msg: &[u8],
+    sign_mode: &C::SignMode,
+) -> (C::Point, Signature<C>) {
+    let messenger = MemMessenger::default();
+    let arch = key_arch(&[(1, 2, 3)]);
+    let keystores = keygen::<C>(&messenger, &arch, &paired(&arch), FrostMode::Rfc9591).await;
+    let pk = keystores[0].pk().unwrap();
+    let ses_arch = ses_arch(&[(1, &[1, 3])]);
+    let signers = [keystores[0].clone(), keystores[2].clone()];
+    let mut sigs = sign::<C>(&messenger, &ses_arch, &signers, msg, sign_mode).await;
+    (pk, sigs.remove(0).unwrap())
+}
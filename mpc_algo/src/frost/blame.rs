@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+
+use libexception::*;
+use mpc_spec::MpcAddr;
+use serde::{Deserialize, Serialize};
+
+use super::{Ciphersuite, Ed25519, KeyGenDKGProposedCommitment, SigningCommitmentPair};
+
+/// Name of the exception raised when misbehaving parties are identified.
+/// Its payload is a `Blame`, see `Blame::of()`.
+pub const IDENTIFIABLE_ABORT: &str = "IdentifiableAbort";
+
+/// What a party is accused of, with the data that proves it.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub enum Evidence<C: Ciphersuite = Ed25519> {
+    /// The proof of knowledge of the constant term does not verify.
+    InvalidZkp { com: KeyGenDKGProposedCommitment<C> },
+    /// The polynomial commitment does not have `th` coefficients.
+    MalformedCommitment { com: Vec<C::Point>, th: usize },
+    /// The VSS share is not an evaluation of the committed polynomial.
+    InvalidShare {
+        share: C::Scalar,
+        com: Vec<C::Point>,
+    },
+    /// The encrypted VSS share fails to decrypt, or is not a 32-byte scalar.
+    UndecryptableShare { ciphertext: Vec<u8>, nonce: Vec<u8> },
+    /// A refresh polynomial whose constant term is not zero, which would
+    /// change the public key.
+    NonZeroRefresh { com: Vec<C::Point> },
+    /// A resharing polynomial which does not hide $\lambda_i x_i$ of its
+    /// dealer, and would change the public key.
+    InvalidReshare { com: Vec<C::Point> },
+    /// The dealer's DKG commitment as echoed by `echoer` is not the one we
+    /// got, i.e. `ours` and `theirs` digests differ. The echoer may be lying
+    /// instead, and is accused with `InconsistentEcho`.
+    Equivocation {
+        echoer: MpcAddr,
+        ours: Vec<u8>,
+        theirs: Vec<u8>,
+    },
+    /// The party echoed a DKG commitment of `dealer` other than the one we
+    /// got. Either it or the dealer lies, and no one can tell which.
+    InconsistentEcho {
+        dealer: MpcAddr,
+        ours: Vec<u8>,
+        theirs: Vec<u8>,
+    },
+    /// The dealer did not reveal the shares of `complainer`, who complained
+    /// that they were undecryptable or invalid.
+    UnansweredComplaint { complainer: MpcAddr },
+    /// The party disqualified the dealers `theirs`, but we did `ours`, e.g. as
+    /// a reveal reached one of us only after the deadline. Either it lies or
+    /// the dealers in question answered late, so they are accused too.
+    InconsistentDisqualification {
+        ours: Vec<MpcAddr>,
+        theirs: Vec<MpcAddr>,
+    },
+    /// The revealed randomness does not match its commitment.
+    InvalidReveal { com: Vec<u8>, reveal: Vec<u8> },
+    /// A dealing along the access policy which is malformed, or does not hide
+    /// $c_g \lambda_i x_i$ of its dealer.
+    InvalidPolicyShare {
+        gate_coms: Vec<Vec<C::Point>>,
+        group_coms: HashMap<u16, Vec<C::Point>>,
+    },
+    /// The party holds shares of another epoch than ours.
+    EpochMismatch { expected: u64, got: u64 },
+    /// The sign response of the `item`-th message does not satisfy
+    /// $z_j G = D_j + \rho_j E_j + c \lambda_j X_j$.
+    InvalidResponse {
+        item: usize,
+        resp: C::Scalar,
+        nonce_com: SigningCommitmentPair<C>,
+    },
+    /// The party sent a list of commitments or responses of a wrong length.
+    WrongItemCount { expected: usize, got: usize },
+}
+
+/// Misbehaving parties, identified during a protocol.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct Blame<C: Ciphersuite = Ed25519> {
+    pub evidence: HashMap<MpcAddr, Evidence<C>>,
+}
+
+impl<C: Ciphersuite> Default for Blame<C> {
+    fn default() -> Self {
+        Blame {
+            evidence: HashMap::new(),
+        }
+    }
+}
+
+impl<C: Ciphersuite> Blame<C> {
+    /// Only the first evidence against a party is kept.
+    pub fn accuse(&mut self, id: MpcAddr, evidence: Evidence<C>) {
+        self.evidence.entry(id).or_insert(evidence);
+    }
+
+    /// Accused parties in ascending order.
+    pub fn culprits(&self) -> Vec<MpcAddr> {
+        let mut culprits: Vec<MpcAddr> = self.evidence.keys().cloned().collect();
+        culprits.sort();
+        culprits
+    }
+
+    /// Abort with an `IdentifiableAbort` exception carrying this blame, if
+    /// anyone is accused.
+    #[track_caller]
+    pub fn throw_if_any(self) -> Outcome<()> {
+        if self.evidence.is_empty() {
+            return Ok(());
+        }
+        let culprits = self.culprits();
+        let ctx = format!(
+            "Misbehaving parties: {}",
+            culprits
+                .iter()
+                .map(|id| id.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        let mut ex = exception!(IDENTIFIABLE_ABORT, ctx);
+        ex.set_payload(self);
+        Err(ex)
+    }
+
+    /// `Blame::of()` for the protocols over the ciphersuite `C`.
+    pub fn of_suite(ex: &Exception) -> Option<&Blame<C>> {
+        ex.get_payload::<Blame<C>>()
+    }
+}
+
+impl Blame {
+    /// The blame carried by an exception, however many times it has been caught.
+    pub fn of(ex: &Exception) -> Option<&Blame> {
+        Blame::of_suite(ex)
+    }
+}
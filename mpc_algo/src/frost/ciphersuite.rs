@@ -0,0 +1,264 @@
+//! Ciphersuites of the protocol, after section 6 of RFC 9591: a prime-order
+//! group with its scalar field and encodings, and the hash functions H1-H5.
+//!
+//! Keygen and signing are generic over `Ciphersuite`, while the other
+//! protocols still work on `Ed25519` only. See also `Ed448`, `Ristretto255`
+//! and `Secp256k1`.
+
+use std::{
+    fmt::Debug,
+    iter::Sum,
+    ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign},
+};
+
+use curve25519_dalek::{
+    constants,
+    edwards::{CompressedEdwardsY, EdwardsPoint},
+    scalar::Scalar,
+};
+use libexception::*;
+use rand::{CryptoRng, RngCore};
+use serde::{de::DeserializeOwned, Serialize};
+use zeroize::Zeroize;
+
+use super::{rfc9591, verify_dalek, SignMode, Signature};
+
+pub trait Ciphersuite:
+    Copy + Clone + Debug + Default + PartialEq + Eq + Send + Sync + 'static
+{
+    /// Element of the scalar field, i.e. integers modulo the group order.
+    type Scalar: Copy
+        + Debug
+        + Default // Zero
+        + PartialEq
+        + Eq
+        + Send
+        + Sync
+        + Add<Output = Self::Scalar>
+        + Sub<Output = Self::Scalar>
+        + Mul<Output = Self::Scalar>
+        + Neg<Output = Self::Scalar>
+        + AddAssign
+        + SubAssign
+        + MulAssign
+        + Sum
+        + Mul<Self::Point, Output = Self::Point>
+        + Zeroize
+        + Serialize
+        + DeserializeOwned;
+
+    /// Element of the group.
+    type Point: Copy
+        + Debug
+        + Default // Identity
+        + PartialEq
+        + Eq
+        + Send
+        + Sync
+        + Add<Output = Self::Point>
+        + Sub<Output = Self::Point>
+        + Neg<Output = Self::Point>
+        + AddAssign
+        + SubAssign
+        + Mul<Self::Scalar, Output = Self::Point>
+        + Sum
+        + Zeroize
+        + Serialize
+        + DeserializeOwned;
+
+    /// Variants of the signature scheme, e.g. with a context string.
+    type SignMode: Clone
+        + Debug
+        + Default
+        + PartialEq
+        + Eq
+        + Send
+        + Sync
+        + Serialize
+        + DeserializeOwned;
+
+    /// contextString of the ciphersuite, prefixed to H1 and H3-H5.
+    const CONTEXT_STRING: &'static str;
+
+    /// Length of `serialize_scalar()`.
+    const SCALAR_LEN: usize;
+
+    /// ScalarBaseMult(), i.e. $s G$.
+    fn base_mul(s: &Self::Scalar) -> Self::Point;
+
+    fn scalar_from_u64(x: u64) -> Self::Scalar;
+
+    /// Inverse of a nonzero scalar.
+    fn invert(s: &Self::Scalar) -> Self::Scalar;
+
+    fn random_scalar<R: RngCore + CryptoRng>(rng: &mut R) -> Self::Scalar;
+
+    fn serialize_point(p: &Self::Point) -> Vec<u8>;
+
+    fn deserialize_point(bytes: &[u8]) -> Option<Self::Point>;
+
+    fn serialize_scalar(s: &Self::Scalar) -> Vec<u8>;
+
+    /// Decode `SCALAR_LEN` bytes, failing unless they encode an integer below
+    /// the group order, as DeserializeScalar of RFC 9591 does.
+    fn deserialize_scalar(bytes: &[u8]) -> Option<Self::Scalar>;
+
+    /// A 32-byte little-endian integer modulo the group order, as the hashes
+    /// of `FrostMode::Legacy` are read.
+    fn scalar_from_le_bytes_mod_order(bytes: &[u8; 32]) -> Self::Scalar;
+
+    /// H1, used to derive binding factors.
+    fn h1(m: &[u8]) -> Self::Scalar;
+
+    /// H2, used to derive the challenge.
+    fn h2(m: &[u8]) -> Self::Scalar;
+
+    /// H3, used to derive nonces.
+    fn h3(m: &[u8]) -> Self::Scalar;
+
+    /// H4, used to digest the message before computing binding factors.
+    fn h4(m: &[u8]) -> Vec<u8>;
+
+    /// H5, used to digest the encoded commitment list.
+    fn h5(m: &[u8]) -> Vec<u8>;
+
+    /// Challenge of the DKG proof of knowledge, which RFC 9591 leaves out.
+    fn hdkg(m: &[u8]) -> Self::Scalar;
+
+    /// Whether `p` stands for its negation, as BIP-340 takes the key and R
+    /// with an even y. It never does for suites that encode points in full.
+    fn has_odd_y(_p: &Self::Point) -> bool {
+        false
+    }
+
+    fn validate_mode(_mode: &Self::SignMode) -> Outcome<()> {
+        Ok(())
+    }
+
+    /// Everything the challenge commits to besides R and Y. Binding factors
+    /// are computed over it, so that they cover the mode as well.
+    fn signed_bytes(_mode: &Self::SignMode, msg: &[u8]) -> Vec<u8> {
+        msg.to_vec()
+    }
+
+    /// The challenge $c$ of the signature, i.e. H2(R || Y || m).
+    fn challenge(
+        _mode: &Self::SignMode,
+        msg: &[u8],
+        r: &Self::Point,
+        pk: &Self::Point,
+    ) -> Self::Scalar {
+        let mut m = Self::serialize_point(r);
+        m.extend(Self::serialize_point(pk));
+        m.extend_from_slice(msg);
+        Self::h2(&m)
+    }
+
+    /// Verify a signature with an independent implementation of the scheme,
+    /// where there is one.
+    fn cross_check(_sig: &Signature<Self>, _pk: &Self::Point) -> Outcome<()> {
+        Ok(())
+    }
+}
+
+/// FROST(Ed25519, SHA-512) of RFC 9591, with the Ed25519 variants of RFC 8032.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Ed25519;
+
+impl Ciphersuite for Ed25519 {
+    type Scalar = Scalar;
+    type Point = EdwardsPoint;
+    type SignMode = SignMode;
+
+    const CONTEXT_STRING: &'static str = rfc9591::CONTEXT_STRING;
+    const SCALAR_LEN: usize = 32;
+
+    fn base_mul(s: &Scalar) -> EdwardsPoint {
+        &constants::ED25519_BASEPOINT_TABLE * s
+    }
+
+    fn scalar_from_u64(x: u64) -> Scalar {
+        Scalar::from(x)
+    }
+
+    fn invert(s: &Scalar) -> Scalar {
+        s.invert()
+    }
+
+    fn random_scalar<R: RngCore + CryptoRng>(rng: &mut R) -> Scalar {
+        Scalar::random(rng)
+    }
+
+    fn serialize_point(p: &EdwardsPoint) -> Vec<u8> {
+        p.compress().to_bytes().to_vec()
+    }
+
+    fn deserialize_point(bytes: &[u8]) -> Option<EdwardsPoint> {
+        if bytes.len() != 32 {
+            return None;
+        }
+        CompressedEdwardsY::from_slice(bytes).decompress()
+    }
+
+    fn serialize_scalar(s: &Scalar) -> Vec<u8> {
+        s.to_bytes().to_vec()
+    }
+
+    fn deserialize_scalar(bytes: &[u8]) -> Option<Scalar> {
+        Scalar::from_canonical_bytes(bytes.try_into().ok()?)
+    }
+
+    fn scalar_from_le_bytes_mod_order(bytes: &[u8; 32]) -> Scalar {
+        Scalar::from_bytes_mod_order(*bytes)
+    }
+
+    fn h1(m: &[u8]) -> Scalar {
+        rfc9591::h1(m)
+    }
+
+    fn h2(m: &[u8]) -> Scalar {
+        rfc9591::h2(m)
+    }
+
+    fn h3(m: &[u8]) -> Scalar {
+        rfc9591::h3(m)
+    }
+
+    fn h4(m: &[u8]) -> Vec<u8> {
+        rfc9591::h4(m)
+    }
+
+    fn h5(m: &[u8]) -> Vec<u8> {
+        rfc9591::h5(m)
+    }
+
+    fn hdkg(m: &[u8]) -> Scalar {
+        rfc9591::hdkg(m)
+    }
+
+    fn validate_mode(mode: &SignMode) -> Outcome<()> {
+        mode.validate()
+    }
+
+    fn signed_bytes(mode: &SignMode, msg: &[u8]) -> Vec<u8> {
+        mode.signed_bytes(msg)
+    }
+
+    /// H(dom2 || R || Y || PH(m)) of RFC 8032. For pure Ed25519 dom2 is empty
+    /// and PH is the identity, where it coincides with H2(R || Y || m).
+    fn challenge(mode: &SignMode, msg: &[u8], r: &EdwardsPoint, pk: &EdwardsPoint) -> Scalar {
+        let mut m = mode.dom2();
+        m.extend_from_slice(r.compress().as_bytes());
+        m.extend_from_slice(pk.compress().as_bytes());
+        m.extend(mode.prehash(msg));
+        rfc9591::h2(&m)
+    }
+
+    fn cross_check(sig: &Signature, pk: &EdwardsPoint) -> Outcome<()> {
+        if matches!(sig.mode, SignMode::Ed25519ctx(_)) {
+            // ed25519-dalek has no Ed25519ctx verifier to cross-check with.
+            return Ok(());
+        }
+        verify_dalek(sig, pk)
+    }
+}
@@ -0,0 +1,814 @@
+//! Ed448 of RFC 8032 on edwards448, hashing to scalars as FROST(Ed448,
+//! SHAKE256) of RFC 9591 does. The challenge is the one of RFC 8032, i.e.
+//! SHAKE256(dom4(phflag, context) || R || A || PH(M), 114), so that the group
+//! signatures are Ed448 (or Ed448ph) signatures.
+//!
+//! The curve is implemented here over the constant-time residues of
+//! crypto-bigint, as no Ed448 crate, e.g. ed448-goldilocks, is among the
+//! dependencies. Besides the vectors of RFC 8032, it is checked against
+//! signatures made by OpenSSL.
+
+use std::{
+    fmt,
+    iter::Sum,
+    ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign},
+};
+
+use crypto_bigint::{
+    modular::constant_mod::{Residue, ResidueParams},
+    subtle::{Choice, ConditionallySelectable},
+    Encoding, NonZero, U1024, U448,
+};
+use libexception::*;
+use rand::{CryptoRng, RngCore};
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+use sha3::{
+    digest::{ExtendableOutput, Update, XofReader},
+    Shake256,
+};
+use zeroize::Zeroize;
+
+use super::{Ciphersuite, Signature};
+
+mod modulus {
+    use crypto_bigint::{impl_modulus, U448};
+
+    // p = 2^448 - 2^224 - 1
+    impl_modulus!(
+        FieldModulus,
+        U448,
+        "fffffffffffffffffffffffffffffffffffffffffffffffffffffffeffffffffffffffffffffffffffffffffffffffffffffffffffffffff"
+    );
+
+    // L = 2^446 - 13818066809895115352007386748515426880336692474882178609894547503885
+    impl_modulus!(
+        OrderModulus,
+        U448,
+        "3fffffffffffffffffffffffffffffffffffffffffffffffffffffff7cca23e9c44edb49aed63690216cc2728dc58f552378c292ab5844f3"
+    );
+}
+
+use modulus::{FieldModulus, OrderModulus};
+
+type FieldElement = Residue<FieldModulus, { U448::LIMBS }>;
+
+/// The curve constant $d = -39081$.
+const D: FieldElement = FieldElement::new(&U448::from_be_hex(
+    "fffffffffffffffffffffffffffffffffffffffffffffffffffffffeffffffffffffffffffffffffffffffffffffffffffffffffffff6756",
+));
+
+/// $(p - 3) / 4$, the exponent of the square root in `decompress()`.
+const SQRT_EXP: U448 = U448::from_be_hex(
+    "3fffffffffffffffffffffffffffffffffffffffffffffffffffffffbfffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+);
+
+/// Length of encoded points and scalars.
+pub const ED448_LEN: usize = 57;
+
+/// An integer modulo the order $L$ of the base point.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub struct Ed448Scalar(Residue<OrderModulus, { U448::LIMBS }>);
+
+impl Ed448Scalar {
+    pub const ZERO: Ed448Scalar = Ed448Scalar(Residue::ZERO);
+    pub const ONE: Ed448Scalar = Ed448Scalar(Residue::ONE);
+
+    /// A little-endian integer of up to 128 bytes, reduced modulo $L$.
+    pub fn from_bytes_mod_order(bytes: &[u8]) -> Ed448Scalar {
+        let mut wide = [0u8; 128];
+        wide[..bytes.len()].copy_from_slice(bytes);
+        let mut order = [0u8; 128];
+        order[..56].copy_from_slice(&OrderModulus::MODULUS.to_le_bytes());
+
+        let reduced = U1024::from_le_bytes(wide)
+            .rem(&NonZero::from_uint(U1024::from_le_bytes(order)))
+            .to_le_bytes();
+        Ed448Scalar(Residue::new(&U448::from_le_slice(&reduced[..56])))
+    }
+
+    /// Decode 57 little-endian bytes, rejecting integers not below $L$ as
+    /// RFC 8032 does for S.
+    pub fn from_canonical_bytes(bytes: &[u8; ED448_LEN]) -> Option<Ed448Scalar> {
+        if bytes[56] != 0 {
+            return None;
+        }
+        let s = U448::from_le_slice(&bytes[..56]);
+        if s >= OrderModulus::MODULUS {
+            return None;
+        }
+        Some(Ed448Scalar(Residue::new(&s)))
+    }
+
+    pub fn to_bytes(&self) -> [u8; ED448_LEN] {
+        let mut bytes = [0u8; ED448_LEN];
+        bytes[..56].copy_from_slice(&self.0.retrieve().to_le_bytes());
+        bytes
+    }
+
+    pub fn invert(&self) -> Ed448Scalar {
+        Ed448Scalar(self.0.invert().0)
+    }
+}
+
+impl fmt::Debug for Ed448Scalar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Ed448Scalar({})", hex::encode(self.to_bytes()))
+    }
+}
+
+impl Add for Ed448Scalar {
+    type Output = Ed448Scalar;
+    fn add(self, rhs: Ed448Scalar) -> Ed448Scalar {
+        Ed448Scalar(self.0 + rhs.0)
+    }
+}
+
+impl Sub for Ed448Scalar {
+    type Output = Ed448Scalar;
+    fn sub(self, rhs: Ed448Scalar) -> Ed448Scalar {
+        Ed448Scalar(self.0 - rhs.0)
+    }
+}
+
+impl Mul for Ed448Scalar {
+    type Output = Ed448Scalar;
+    fn mul(self, rhs: Ed448Scalar) -> Ed448Scalar {
+        Ed448Scalar(self.0 * rhs.0)
+    }
+}
+
+impl Neg for Ed448Scalar {
+    type Output = Ed448Scalar;
+    fn neg(self) -> Ed448Scalar {
+        Ed448Scalar(-self.0)
+    }
+}
+
+impl AddAssign for Ed448Scalar {
+    fn add_assign(&mut self, rhs: Ed448Scalar) {
+        self.0 += rhs.0;
+    }
+}
+
+impl SubAssign for Ed448Scalar {
+    fn sub_assign(&mut self, rhs: Ed448Scalar) {
+        self.0 -= rhs.0;
+    }
+}
+
+impl MulAssign for Ed448Scalar {
+    fn mul_assign(&mut self, rhs: Ed448Scalar) {
+        self.0 *= rhs.0;
+    }
+}
+
+impl Sum for Ed448Scalar {
+    fn sum<I: Iterator<Item = Ed448Scalar>>(iter: I) -> Ed448Scalar {
+        iter.fold(Ed448Scalar::ZERO, |acc, s| acc + s)
+    }
+}
+
+impl Mul<Ed448Point> for Ed448Scalar {
+    type Output = Ed448Point;
+    fn mul(self, rhs: Ed448Point) -> Ed448Point {
+        rhs * self
+    }
+}
+
+impl Zeroize for Ed448Scalar {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl Serialize for Ed448Scalar {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_bytes().to_vec().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Ed448Scalar {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bytes = Vec::<u8>::deserialize(deserializer)?;
+        let bytes: [u8; ED448_LEN] = bytes
+            .try_into()
+            .map_err(|_| D::Error::custom("Ed448 scalar is not 57 bytes"))?;
+        Ed448Scalar::from_canonical_bytes(&bytes)
+            .ok_or_else(|| D::Error::custom("Ed448 scalar is not below the group order"))
+    }
+}
+
+/// A point of edwards448 in projective coordinates $(X : Y : Z)$, which
+/// encodes in 57 bytes as RFC 8032 does.
+#[derive(Clone, Copy)]
+pub struct Ed448Point {
+    x: FieldElement,
+    y: FieldElement,
+    z: FieldElement,
+}
+
+impl Ed448Point {
+    pub const IDENTITY: Ed448Point = Ed448Point {
+        x: FieldElement::ZERO,
+        y: FieldElement::ONE,
+        z: FieldElement::ONE,
+    };
+
+    /// The base point B of RFC 8032.
+    pub const GENERATOR: Ed448Point = Ed448Point {
+        x: FieldElement::new(&U448::from_be_hex(
+            "4f1970c66bed0ded221d15a622bf36da9e146570470f1767ea6de324a3d3a46412ae1af72ab66511433b80e18b00938e2626a82bc70cc05e",
+        )),
+        y: FieldElement::new(&U448::from_be_hex(
+            "693f46716eb6bc248876203756c9c7624bea73736ca3984087789c1e05a0c2d73ad3ff1ce67c39c4fdbd132c4ed7c8ad9808795bf230fa14",
+        )),
+        z: FieldElement::ONE,
+    };
+
+    /// Doubling of section 5.2.4 of RFC 8032.
+    pub fn double(&self) -> Ed448Point {
+        let b = (self.x + self.y).square();
+        let c = self.x.square();
+        let d = self.y.square();
+        let e = c + d;
+        let h = self.z.square();
+        let j = e - h - h;
+        Ed448Point {
+            x: (b - e) * j,
+            y: e * (c - d),
+            z: e * j,
+        }
+    }
+
+    /// $[4]P$, which clears the torsion component.
+    pub fn mul_by_cofactor(&self) -> Ed448Point {
+        self.double().double()
+    }
+
+    /// The encoding of section 5.2.2 of RFC 8032, i.e. y in little-endian
+    /// with the least significant bit of x in the top bit.
+    pub fn compress(&self) -> [u8; ED448_LEN] {
+        let z_inv = self.z.invert().0;
+        let x = (self.x * z_inv).retrieve().to_le_bytes();
+        let y = (self.y * z_inv).retrieve().to_le_bytes();
+
+        let mut bytes = [0u8; ED448_LEN];
+        bytes[..56].copy_from_slice(&y);
+        bytes[56] = (x[0] & 1) << 7;
+        bytes
+    }
+
+    /// The decoding of section 5.2.3 of RFC 8032.
+    pub fn decompress(bytes: &[u8; ED448_LEN]) -> Option<Ed448Point> {
+        if bytes[56] & 0x7f != 0 {
+            return None;
+        }
+        let x_0 = bytes[56] >> 7;
+        let y = U448::from_le_slice(&bytes[..56]);
+        if y >= FieldModulus::MODULUS {
+            return None;
+        }
+        let y = FieldElement::new(&y);
+
+        // x^2 = u / v, and x = u^3 v (u^5 v^3)^((p - 3) / 4) is its root if any.
+        let yy = y.square();
+        let u = yy - FieldElement::ONE;
+        let v = D * yy - FieldElement::ONE;
+        let u3v = u.square() * u * v;
+        let x = u3v * (u3v * u.square() * v.square()).pow(&SQRT_EXP);
+        if v * x.square() != u {
+            return None;
+        }
+        if x == FieldElement::ZERO && x_0 == 1 {
+            return None;
+        }
+        let x = if x.retrieve().to_le_bytes()[0] & 1 != x_0 {
+            -x
+        } else {
+            x
+        };
+        Some(Ed448Point {
+            x,
+            y,
+            z: FieldElement::ONE,
+        })
+    }
+}
+
+impl fmt::Debug for Ed448Point {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Ed448Point({})", hex::encode(self.compress()))
+    }
+}
+
+impl Default for Ed448Point {
+    fn default() -> Self {
+        Ed448Point::IDENTITY
+    }
+}
+
+impl PartialEq for Ed448Point {
+    fn eq(&self, other: &Ed448Point) -> bool {
+        self.x * other.z == other.x * self.z && self.y * other.z == other.y * self.z
+    }
+}
+
+impl Eq for Ed448Point {}
+
+impl ConditionallySelectable for Ed448Point {
+    fn conditional_select(a: &Ed448Point, b: &Ed448Point, choice: Choice) -> Ed448Point {
+        Ed448Point {
+            x: FieldElement::conditional_select(&a.x, &b.x, choice),
+            y: FieldElement::conditional_select(&a.y, &b.y, choice),
+            z: FieldElement::conditional_select(&a.z, &b.z, choice),
+        }
+    }
+}
+
+/// Addition of section 5.2.4 of RFC 8032, which is complete.
+impl Add for Ed448Point {
+    type Output = Ed448Point;
+    fn add(self, rhs: Ed448Point) -> Ed448Point {
+        let a = self.z * rhs.z;
+        let b = a.square();
+        let c = self.x * rhs.x;
+        let d = self.y * rhs.y;
+        let e = D * c * d;
+        let f = b - e;
+        let g = b + e;
+        let h = (self.x + self.y) * (rhs.x + rhs.y);
+        Ed448Point {
+            x: a * f * (h - c - d),
+            y: a * g * (d - c),
+            z: f * g,
+        }
+    }
+}
+
+impl Sub for Ed448Point {
+    type Output = Ed448Point;
+    fn sub(self, rhs: Ed448Point) -> Ed448Point {
+        self + (-rhs)
+    }
+}
+
+impl Neg for Ed448Point {
+    type Output = Ed448Point;
+    fn neg(self) -> Ed448Point {
+        Ed448Point {
+            x: -self.x,
+            y: self.y,
+            z: self.z,
+        }
+    }
+}
+
+impl AddAssign for Ed448Point {
+    fn add_assign(&mut self, rhs: Ed448Point) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign for Ed448Point {
+    fn sub_assign(&mut self, rhs: Ed448Point) {
+        *self = *self - rhs;
+    }
+}
+
+/// Double-and-add over the bits of the scalar, adding in constant time.
+impl Mul<Ed448Scalar> for Ed448Point {
+    type Output = Ed448Point;
+    fn mul(self, rhs: Ed448Scalar) -> Ed448Point {
+        let k = rhs.to_bytes();
+        let mut acc = Ed448Point::IDENTITY;
+        // Scalars are below L < 2^446.
+        for i in (0..446).rev() {
+            acc = acc.double();
+            let bit = Choice::from((k[i / 8] >> (i % 8)) & 1);
+            acc = Ed448Point::conditional_select(&acc, &(acc + self), bit);
+        }
+        acc
+    }
+}
+
+impl Sum for Ed448Point {
+    fn sum<I: Iterator<Item = Ed448Point>>(iter: I) -> Ed448Point {
+        iter.fold(Ed448Point::IDENTITY, |acc, p| acc + p)
+    }
+}
+
+impl Zeroize for Ed448Point {
+    fn zeroize(&mut self) {
+        self.x.zeroize();
+        self.y = FieldElement::ONE;
+        self.z = FieldElement::ONE;
+    }
+}
+
+impl Serialize for Ed448Point {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.compress().to_vec().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Ed448Point {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bytes = Vec::<u8>::deserialize(deserializer)?;
+        let bytes: [u8; ED448_LEN] = bytes
+            .try_into()
+            .map_err(|_| D::Error::custom("Ed448 point is not 57 bytes"))?;
+        Ed448Point::decompress(&bytes).ok_or_else(|| D::Error::custom("Invalid Ed448 point"))
+    }
+}
+
+/// Ed448 variants of RFC 8032, each with its (possibly empty) context string.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Ed448Mode {
+    /// PureEdDSA over the message of arbitrary length.
+    Ed448(Vec<u8>),
+    /// HashEdDSA over SHAKE256(msg, 64).
+    Ed448ph(Vec<u8>),
+}
+
+impl Default for Ed448Mode {
+    fn default() -> Self {
+        Ed448Mode::Ed448(Vec::new())
+    }
+}
+
+impl Ed448Mode {
+    pub fn validate(&self) -> Outcome<()> {
+        let (Ed448Mode::Ed448(ctx) | Ed448Mode::Ed448ph(ctx)) = self;
+        assert_throw!(ctx.len() <= 255, "Context string exceeds 255 bytes");
+        Ok(())
+    }
+
+    /// dom4(phflag, context) of RFC 8032.
+    pub fn dom4(&self) -> Vec<u8> {
+        let (phflag, ctx) = match self {
+            Ed448Mode::Ed448(ctx) => (0u8, ctx),
+            Ed448Mode::Ed448ph(ctx) => (1u8, ctx),
+        };
+        let mut dom = b"SigEd448".to_vec();
+        dom.push(phflag);
+        dom.push(ctx.len() as u8);
+        dom.extend_from_slice(ctx);
+        dom
+    }
+
+    /// PH(msg) of RFC 8032.
+    pub fn prehash(&self, msg: &[u8]) -> Vec<u8> {
+        match self {
+            Ed448Mode::Ed448ph(_) => shake256(&[msg], 64),
+            Ed448Mode::Ed448(_) => msg.to_vec(),
+        }
+    }
+
+    /// Everything the challenge commits to besides R and A, i.e. dom4 || PH(msg).
+    pub fn signed_bytes(&self, msg: &[u8]) -> Vec<u8> {
+        let mut m = self.dom4();
+        m.extend(self.prehash(msg));
+        m
+    }
+}
+
+fn shake256(parts: &[&[u8]], len: usize) -> Vec<u8> {
+    let mut hasher = Shake256::default();
+    for part in parts {
+        hasher.update(part);
+    }
+    let mut out = vec![0u8; len];
+    hasher.finalize_xof().read(&mut out);
+    out
+}
+
+fn hash(tag: &str, m: &[u8]) -> Vec<u8> {
+    shake256(&[Ed448::CONTEXT_STRING.as_bytes(), tag.as_bytes(), m], 114)
+}
+
+/// FROST(Ed448, SHAKE256) of RFC 9591, with the Ed448 variants of RFC 8032.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Ed448;
+
+impl Ciphersuite for Ed448 {
+    type Scalar = Ed448Scalar;
+    type Point = Ed448Point;
+    type SignMode = Ed448Mode;
+
+    const CONTEXT_STRING: &'static str = "FROST-ED448-SHAKE256-v1";
+    const SCALAR_LEN: usize = ED448_LEN;
+
+    fn base_mul(s: &Ed448Scalar) -> Ed448Point {
+        Ed448Point::GENERATOR * *s
+    }
+
+    fn scalar_from_u64(x: u64) -> Ed448Scalar {
+        Ed448Scalar(Residue::new(&U448::from_u64(x)))
+    }
+
+    fn invert(s: &Ed448Scalar) -> Ed448Scalar {
+        s.invert()
+    }
+
+    fn random_scalar<R: RngCore + CryptoRng>(rng: &mut R) -> Ed448Scalar {
+        let mut bytes = [0u8; 114];
+        rng.fill_bytes(&mut bytes);
+        let s = Ed448Scalar::from_bytes_mod_order(&bytes);
+        bytes.zeroize();
+        s
+    }
+
+    fn serialize_point(p: &Ed448Point) -> Vec<u8> {
+        p.compress().to_vec()
+    }
+
+    fn deserialize_point(bytes: &[u8]) -> Option<Ed448Point> {
+        Ed448Point::decompress(bytes.try_into().ok()?)
+    }
+
+    fn serialize_scalar(s: &Ed448Scalar) -> Vec<u8> {
+        s.to_bytes().to_vec()
+    }
+
+    fn deserialize_scalar(bytes: &[u8]) -> Option<Ed448Scalar> {
+        Ed448Scalar::from_canonical_bytes(bytes.try_into().ok()?)
+    }
+
+    fn scalar_from_le_bytes_mod_order(bytes: &[u8; 32]) -> Ed448Scalar {
+        Ed448Scalar::from_bytes_mod_order(bytes)
+    }
+
+    fn h1(m: &[u8]) -> Ed448Scalar {
+        Ed448Scalar::from_bytes_mod_order(&hash("rho", m))
+    }
+
+    /// SHAKE256(dom4(0, "") || m, 114), i.e. H2 of RFC 9591.
+    fn h2(m: &[u8]) -> Ed448Scalar {
+        let dom = Ed448Mode::default().dom4();
+        Ed448Scalar::from_bytes_mod_order(&shake256(&[&dom, m], 114))
+    }
+
+    fn h3(m: &[u8]) -> Ed448Scalar {
+        Ed448Scalar::from_bytes_mod_order(&hash("nonce", m))
+    }
+
+    fn h4(m: &[u8]) -> Vec<u8> {
+        hash("msg", m)
+    }
+
+    fn h5(m: &[u8]) -> Vec<u8> {
+        hash("com", m)
+    }
+
+    fn hdkg(m: &[u8]) -> Ed448Scalar {
+        Ed448Scalar::from_bytes_mod_order(&hash("dkg", m))
+    }
+
+    fn validate_mode(mode: &Ed448Mode) -> Outcome<()> {
+        mode.validate()
+    }
+
+    fn signed_bytes(mode: &Ed448Mode, msg: &[u8]) -> Vec<u8> {
+        mode.signed_bytes(msg)
+    }
+
+    /// SHAKE256(dom4 || R || A || PH(m), 114) of RFC 8032, which coincides
+    /// with H2(R || A || m) for pure Ed448 without a context string.
+    fn challenge(mode: &Ed448Mode, msg: &[u8], r: &Ed448Point, pk: &Ed448Point) -> Ed448Scalar {
+        let digest = shake256(
+            &[
+                &mode.dom4(),
+                &r.compress(),
+                &pk.compress(),
+                &mode.prehash(msg),
+            ],
+            114,
+        );
+        Ed448Scalar::from_bytes_mod_order(&digest)
+    }
+
+    fn cross_check(sig: &Signature<Self>, pk: &Ed448Point) -> Outcome<()> {
+        verify_ed448(sig, pk)
+    }
+}
+
+/// The 114-byte encoding of RFC 8032, i.e. R || S.
+pub fn ed448_bytes(sig: &Signature<Ed448>) -> [u8; 114] {
+    let mut bytes = [0u8; 114];
+    bytes[..ED448_LEN].copy_from_slice(&sig.r.compress());
+    bytes[ED448_LEN..].copy_from_slice(&sig.s.to_bytes());
+    bytes
+}
+
+/// Check the signature as the verifier of RFC 8032 does on its encoding,
+/// i.e. with the cofactored equation [4][S]B = [4]R + [4][k]A.
+pub fn verify_ed448(sig: &Signature<Ed448>, pk: &Ed448Point) -> Outcome<()> {
+    verify_ed448_bytes(&pk.compress(), &sig.msg, &ed448_bytes(sig), &sig.mode)
+}
+
+/// Section 5.2.7 of RFC 8032, on the encoded key and signature.
+pub fn verify_ed448_bytes(
+    pk: &[u8; ED448_LEN],
+    msg: &[u8],
+    sig: &[u8; 114],
+    mode: &Ed448Mode,
+) -> Outcome<()> {
+    mode.validate().catch_()?;
+    let r_bytes: &[u8; ED448_LEN] = sig[..ED448_LEN].try_into().unwrap();
+    let s_bytes: &[u8; ED448_LEN] = sig[ED448_LEN..].try_into().unwrap();
+
+    let a = Ed448Point::decompress(pk).ifnone("InvalidPublicKey", "")?;
+    let r = Ed448Point::decompress(r_bytes).ifnone("InvalidSignature", "Invalid R")?;
+    let s = Ed448Scalar::from_canonical_bytes(s_bytes).ifnone("InvalidSignature", "Invalid S")?;
+
+    let digest = shake256(&[&mode.dom4(), r_bytes, pk, &mode.prehash(msg)], 114);
+    let k = Ed448Scalar::from_bytes_mod_order(&digest);
+
+    let lhs = Ed448::base_mul(&s).mul_by_cofactor();
+    let rhs = (r + a * k).mul_by_cofactor();
+    assert_throw!(lhs == rhs, "InvalidSignature", "Ed448 verification failed");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::OsRng;
+
+    use super::*;
+    use crate::biz_algo::testkit::keygen_and_sign;
+    use crate::frost::verify_signature;
+
+    type Vector = (
+        &'static str,
+        &'static str,
+        &'static str,
+        Ed448Mode,
+        &'static str,
+    );
+
+    /// Section 7.4 of RFC 8032: (secret key, public key, message, mode, signature)
+    fn rfc8032_vectors() -> Vec<Vector> {
+        vec![
+            (
+                "6c82a562cb808d10d632be89c8513ebf6c929f34ddfa8c9f63c9960ef6e348a3528c8a3fcc2f044e39a3fc5b94492f8f032e7549a20098f95b",
+                "5fd7449b59b461fd2ce787ec616ad46a1da1342485a70e1f8a0ea75d80e96778edf124769b46c7061bd6783df1e50f6cd1fa1abeafe8256180",
+                "",
+                Ed448Mode::Ed448(vec![]),
+                "533a37f6bbe457251f023c0d88f976ae2dfb504a843e34d2074fd823d41a591f2b233f034f628281f2fd7a22ddd47d7828c59bd0a21bfd3980ff0d2028d4b18a9df63e006c5d1c2d345b925d8dc00b4104852db99ac5c7cdda8530a113a0f4dbb61149f05a7363268c71d95808ff2e652600",
+            ),
+            (
+                "c4eab05d357007c632f3dbb48489924d552b08fe0c353a0d4a1f00acda2c463afbea67c5e8d2877c5e3bc397a659949ef8021e954e0a12274e",
+                "43ba28f430cdff456ae531545f7ecd0ac834a55d9358c0372bfa0c6c6798c0866aea01eb00742802b8438ea4cb82169c235160627b4c3a9480",
+                "03",
+                Ed448Mode::Ed448(vec![]),
+                "26b8f91727bd62897af15e41eb43c377efb9c610d48f2335cb0bd0087810f4352541b143c4b981b7e18f62de8ccdf633fc1bf037ab7cd779805e0dbcc0aae1cbcee1afb2e027df36bc04dcecbf154336c19f0af7e0a6472905e799f1953d2a0ff3348ab21aa4adafd1d234441cf807c03a00",
+            ),
+            (
+                "c4eab05d357007c632f3dbb48489924d552b08fe0c353a0d4a1f00acda2c463afbea67c5e8d2877c5e3bc397a659949ef8021e954e0a12274e",
+                "43ba28f430cdff456ae531545f7ecd0ac834a55d9358c0372bfa0c6c6798c0866aea01eb00742802b8438ea4cb82169c235160627b4c3a9480",
+                "03",
+                Ed448Mode::Ed448(b"foo".to_vec()),
+                "d4f8f6131770dd46f40867d6fd5d5055de43541f8c5e35abbcd001b32a89f7d2151f7647f11d8ca2ae279fb842d607217fce6e042f6815ea000c85741de5c8da1144a6a1aba7f96de42505d7a7298524fda538fccbbb754f578c1cad10d54d0d5428407e85dcbc98a49155c13764e66c3c00",
+            ),
+            (
+                "833fe62409237b9d62ec77587520911e9a759cec1d19755b7da901b96dca3d42ef7822e0d5104127dc05d6dbefde69e3ab2cec7c867c6e2c49",
+                "259b71c19f83ef77a7abd26524cbdb3161b590a48f7d17de3ee0ba9c52beb743c09428a131d6b1b57303d90d8132c276d5ed3d5d01c0f53880",
+                "616263",
+                Ed448Mode::Ed448ph(vec![]),
+                "822f6901f7480f3d5f562c592994d9693602875614483256505600bbc281ae381f54d6bce2ea911574932f52a4e6cadd78769375ec3ffd1b801a0d9b3f4030cd433964b6457ea39476511214f97469b57dd32dbc560a9a94d00bff07620464a3ad203df7dc7ce360c3cd3696d9d9fab90f00",
+            ),
+        ]
+    }
+
+    /// Signatures made by OpenSSL 3.5 with `openssl pkeyutl -sign -rawin` and
+    /// its `instance` and `hexcontext-string` options, but for the empty message,
+    /// which the command refuses and pyca/cryptography signed over OpenSSL. The
+    /// secret keys are SHAKE256("mpc_eddsa ed448 vector {i}", 57).
+    /// (secret key, public key, message, mode, signature)
+    fn openssl_vectors() -> Vec<Vector> {
+        vec![
+            (
+                "46169441856b8b33614705250ff682fc83bf1119ae427cf2d0fe4fc9f126adc81dc22e541ba654788c06c7c6b9090f354b6d40f5d7bf0696c8",
+                "59c180997c01066350f786a1852b161b473a6e505e68caa345fb04ce761b4c75d3b0f3dc0ec0525fb01ef5311ef301d21253131a694ca34c00",
+                "",
+                Ed448Mode::Ed448(vec![]),
+                "e60d5b6a39372c2bae19ab3c3ad8558bbf012f62eee61a574f59c26cfe1e9cc20c4bcee33936bbceebebf3452e5a260dbe17c509d531069680566bed71aadb673675e3ef1f00544af8a19fcb72267ede06bb88f136d521526f452b21eb1b5c21806e4f0f7125f617dd198c842af6effd2000",
+            ),
+            (
+                "1ec55137cf28e33eb521fbc10ed0b494dbf06aa0272bcb449d321617ae16bcc0cca38dc08ef9b6433c1d1e53326a0d43a719355ade4b502562",
+                "ee3f884f97350b45dffdc1883ca2c379a98b7c84ae915fa1848b1b74a7968e68680e20325a6c608e3dfb564807aafcfc7152f484d469268a00",
+                "6d70635f6564647361",
+                Ed448Mode::Ed448(vec![]),
+                "a3bcfaf8062940f2ccb5d2be64211250189189abebf2e5f648679f2f672fa2c81951927246993330a0d6dee33642465bceb4795e8fb7a02480997cbedcab712e6aa8ea5f40c7bd6279703229999ed8bcd5bbd9045435c8cf67f0e2a1ddd44d28da29f2abd374db356e2db18a884efd182600",
+            ),
+            (
+                "9c74e9e9d141727f30d25a1bc0c9de64d40e66a0a6ff8e2fe5dbe4d2d544021b739489124871d0f24f4cd2069447ec9af812d0f94ffef756a4",
+                "264a3400123f244f7a52438c72d3893bb4b1674fc65868c14f201d13463de345b943889b2ef9848a55f47b24c400f458abc0679b8c01f49c00",
+                "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f202122232425262728292a2b2c2d2e2f303132333435363738393a3b3c3d3e3f404142434445464748494a4b4c4d4e4f505152535455565758595a5b5c5d5e5f606162636465666768696a6b6c6d6e6f707172737475767778797a7b7c7d7e7f808182838485868788898a8b8c8d8e8f909192939495969798999a9b9c9d9e9fa0a1a2a3a4a5a6a7a8a9aaabacadaeafb0b1b2b3b4b5b6b7b8b9babbbcbdbebfc0c1c2c3c4c5c6c7",
+                Ed448Mode::Ed448(vec![]),
+                "5cdd0e7df889d23e02c63e2a45a2320c18a26de46257f0b0accbc65444f7da2c6e86e8bb870be48485393b0090e58284964fc8ff8078787d00122fd92bae9225cf73ce8fff1e624feb5cb35124bcc6208a97c323ba97d698bf0dd908b35543e42c916c9dd61af07a85d3efe03326a08e0800",
+            ),
+            (
+                "36c71466347de4f15f85f3286ac45e95d31ec84f4147f2f9c9657a2e581d126d007e754b3cf62fb27500c81e8733aa22b18e8e9ecbde446094",
+                "82097f604a1fc18adec6d37fd7cc57b97323588e44d74e5e1255ca0c759ca1bdc08a8eec979b489781c0f97baa73e1571d3b8d3f5428c26580",
+                "6d70635f6564647361",
+                Ed448Mode::Ed448(b"ctx".to_vec()),
+                "b490faea09ebb8187a950a614db4807f2bb14001b842c0ad9a919e271891f168cba5db6b65a9e34b292e54297c0c56866fa379092fa3ebb9805c13620664a29d60f7941fc99e5b2788bc141a9467fb1e705f0a77d528d2e97090b3babe593f49b59cddc4b7bf9c77e7154218a8e3f9a73f00",
+            ),
+            (
+                "9f1394418a92afde981ae22197e4c6b5cd408d426224154baf1b887963e6a8f189d48ab733c4c3eeb47e1aa7fed2885979da6e215d3820b594",
+                "e28519a4c947eb74cca2b2fb0d3c6fab86fdc0327feb350796939218963dd2f9d4dbca940fd183896391dcd2e2b13c7b6a12f4f8c7fcd58800",
+                "6d70635f6564647361",
+                Ed448Mode::Ed448ph(vec![]),
+                "c6c72ae47ebe561074ad2721125663abe4266369ba3c7d8171b331dbffe089b28404088df365f30e630c0ea4d93d62834b393999cb48a32f008f272e0c2dcf33d5c318769c30ce6691b49f53b51b30a92ce169c39bf5b7c34584e8b1ab2e2ffda01e7926f6baa88e7483b7d81cf8a84b2300",
+            ),
+            (
+                "f89573aa7115f94be972a4a8f28d1f865297f5abcb30b8748ab5e3c7859f3667ebf31073a6aab750aae1bb79105e88c6f1016fbba93f541a84",
+                "dbcde900079c5219f9ce4446fbf113cf61ab340d2145181ece29c1f0817458af5ad496e2b80cade6248c1b29de1e88ae400cab7fd020676800",
+                "6d70635f6564647361",
+                Ed448Mode::Ed448ph(b"ctx".to_vec()),
+                "a33b94451524c7c701888342b342bee061d227aa0ada86ff6d1916e2d990eac85d458f4275eba114507a4323ecbe4c42e3c3cf9a0f64001c8061927b072891f998dacfd5cc33bef769b57f712440ac87bffb56ea2f5d8d087596d71fddfc90df994e9f00aaa47a2f9fd2bac260f77ea43400",
+            ),
+        ]
+    }
+
+    fn check_vectors(vectors: Vec<Vector>) {
+        for (i, (sk, pk, msg, mode, sig_hex)) in vectors.into_iter().enumerate() {
+            // The secret scalar is the pruned first half of SHAKE256(sk, 114).
+            let mut h = shake256(&[&hex::decode(sk).unwrap()], 114);
+            h[0] &= 252;
+            h[55] |= 128;
+            h[56] = 0;
+            let pk_of_sk = Ed448::base_mul(&Ed448Scalar::from_bytes_mod_order(&h[..57]));
+            assert_eq!(hex::encode(pk_of_sk.compress()), pk, "vector {}", i);
+
+            let sig_bytes: [u8; 114] = hex::decode(sig_hex).unwrap().try_into().unwrap();
+            let mut sig: Signature<Ed448> = Signature {
+                r: Ed448Point::decompress(sig_bytes[..57].try_into().unwrap()).unwrap(),
+                s: Ed448Scalar::from_canonical_bytes(sig_bytes[57..].try_into().unwrap()).unwrap(),
+                msg: hex::decode(msg).unwrap(),
+                mode,
+            };
+            verify_signature(&sig, &pk_of_sk).unwrap();
+            verify_ed448(&sig, &pk_of_sk).unwrap();
+            assert_eq!(ed448_bytes(&sig), sig_bytes, "vector {}", i);
+
+            sig.msg.push(0);
+            assert!(verify_signature(&sig, &pk_of_sk).is_err(), "vector {}", i);
+            assert!(verify_ed448(&sig, &pk_of_sk).is_err(), "vector {}", i);
+        }
+    }
+
+    #[test]
+    fn rfc8032_ed448_vectors() {
+        check_vectors(rfc8032_vectors());
+    }
+
+    #[test]
+    fn openssl_ed448_vectors() {
+        check_vectors(openssl_vectors());
+    }
+
+    #[test]
+    fn scalars_and_points_round_trip() {
+        let s = Ed448::random_scalar(&mut OsRng);
+        assert_eq!(Ed448Scalar::from_canonical_bytes(&s.to_bytes()), Some(s));
+        assert_eq!(s * s.invert(), Ed448Scalar::ONE);
+
+        let p = Ed448::base_mul(&s);
+        assert_eq!(Ed448Point::decompress(&p.compress()), Some(p));
+        assert_eq!(Ed448Point::decompress(&(-p).compress()), Some(-p));
+        assert_eq!(p + p, p.double());
+        assert_eq!(p - p, Ed448Point::IDENTITY);
+
+        let json = serde_json::to_string(&(s, p)).unwrap();
+        let (s2, p2): (Ed448Scalar, Ed448Point) = serde_json::from_str(&json).unwrap();
+        assert_eq!((s2, p2), (s, p));
+
+        // The order of B is L, and L - 1 encodes as -1.
+        let l_minus_1 = -Ed448Scalar::ONE;
+        assert_eq!(
+            Ed448::base_mul(&l_minus_1) + Ed448Point::GENERATOR,
+            Ed448Point::IDENTITY
+        );
+        let mut l_bytes = l_minus_1.to_bytes();
+        l_bytes[0] += 1;
+        assert_eq!(Ed448Scalar::from_canonical_bytes(&l_bytes), None);
+
+        // y = p + 1 is rejected, though it would reduce to the y of a point.
+        let mut y_bytes = [0u8; ED448_LEN];
+        y_bytes[28..56].copy_from_slice(&[0xff; 28]);
+        assert_eq!(Ed448Point::decompress(&y_bytes), None);
+        let mut y_bytes = [0u8; ED448_LEN];
+        y_bytes[0] = 1;
+        assert_eq!(Ed448Point::decompress(&y_bytes), Some(Ed448Point::IDENTITY));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn threshold_signature_verifies_with_ed448() {
+        let mode = Ed448Mode::Ed448ph(b"regulated".to_vec());
+        let (pk, sig) = keygen_and_sign::<Ed448>(b"threshold ed448", &mode).await;
+        verify_signature(&sig, &pk).unwrap();
+        verify_ed448(&sig, &pk).unwrap();
+    }
+}
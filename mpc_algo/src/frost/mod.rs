@@ -1,71 +1,86 @@
+mod blame;
+pub use blame::*;
+mod ciphersuite;
+pub use ciphersuite::*;
+mod ed448;
+pub use ed448::*;
 mod party_key;
 pub use party_key::*;
+mod policy;
+pub use policy::*;
+mod ristretto255;
+pub use ristretto255::*;
+mod secp256k1;
+pub use secp256k1::*;
 mod signing_key;
 pub use signing_key::*;
+pub mod rfc9591;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct SigningCommitmentPair {
-    pub g_d: EdwardsPoint,
-    pub g_e: EdwardsPoint,
+#[serde(bound = "")]
+pub struct SigningCommitmentPair<C: Ciphersuite = Ed25519> {
+    pub g_d: C::Point,
+    pub g_e: C::Point,
 }
 
-#[derive(Copy, Clone)]
-pub struct SigningNoncePair {
-    d: Nonce,
-    e: Nonce,
+#[derive(Copy, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct SigningNoncePair<C: Ciphersuite = Ed25519> {
+    d: Nonce<C>,
+    e: Nonce<C>,
 }
 
-#[derive(Copy, Clone)]
-pub struct Nonce {
-    secret: Scalar,
-    pub public: EdwardsPoint,
+#[derive(Copy, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct Nonce<C: Ciphersuite = Ed25519> {
+    secret: C::Scalar,
+    pub public: C::Point,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct Signature {
-    pub r: EdwardsPoint,
-    pub s: Scalar,
-    pub hash: Vec<u8>,
+#[serde(bound = "")]
+pub struct Signature<C: Ciphersuite = Ed25519> {
+    pub r: C::Point,
+    pub s: C::Scalar,
+    pub msg: Vec<u8>,
+    pub mode: C::SignMode,
 }
 
-impl Zeroize for KeyGenZKP {
+/// Ed25519 variants of RFC 8032.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SignMode {
+    /// PureEdDSA over the message of arbitrary length, as Solana expects.
+    #[default]
+    Ed25519,
+    /// HashEdDSA over SHA-512(msg), with the (possibly empty) context string.
+    Ed25519ph(Vec<u8>),
+    /// PureEdDSA with a non-empty context string.
+    Ed25519ctx(Vec<u8>),
+}
+
+impl<C: Ciphersuite> Zeroize for KeyGenZKP<C> {
     fn zeroize(&mut self) {
         self.g_k_i.zeroize();
         self.sigma.zeroize();
     }
 }
 
-impl SigningCommitmentPair {
-    pub fn new(g_d: EdwardsPoint, g_e: EdwardsPoint) -> Outcome<SigningCommitmentPair> {
-        assert_throw!(
-            g_d != EdwardsPoint::identity(),
-            "Invalid signing commitment"
-        );
-        assert_throw!(
-            g_e != EdwardsPoint::identity(),
-            "Invalid signing commitment"
-        );
+impl<C: Ciphersuite> SigningCommitmentPair<C> {
+    pub fn new(g_d: C::Point, g_e: C::Point) -> Outcome<SigningCommitmentPair<C>> {
+        assert_throw!(g_d != C::Point::default(), "Invalid signing commitment");
+        assert_throw!(g_e != C::Point::default(), "Invalid signing commitment");
 
         Ok(SigningCommitmentPair { g_d, g_e })
     }
 }
 
-impl SigningNoncePair {
-    pub fn new<R: RngCore + CryptoRng>(rng: &mut R) -> Outcome<SigningNoncePair> {
-        let (d, e) = (Scalar::random(rng), Scalar::random(rng));
-        let (d_pub, e_pub) = (
-            &constants::ED25519_BASEPOINT_TABLE * &d,
-            &constants::ED25519_BASEPOINT_TABLE * &e,
-        );
-
-        assert_throw!(
-            d_pub != EdwardsPoint::identity(),
-            "Invalid nonce commitment"
-        );
-        assert_throw!(
-            e_pub != EdwardsPoint::identity(),
-            "Invalid nonce commitment"
-        );
+impl<C: Ciphersuite> SigningNoncePair<C> {
+    pub fn new<R: RngCore + CryptoRng>(rng: &mut R) -> Outcome<SigningNoncePair<C>> {
+        let (d, e) = (C::random_scalar(rng), C::random_scalar(rng));
+        let (d_pub, e_pub) = (C::base_mul(&d), C::base_mul(&e));
+
+        assert_throw!(d_pub != C::Point::default(), "Invalid nonce commitment");
+        assert_throw!(e_pub != C::Point::default(), "Invalid nonce commitment");
 
         Ok(SigningNoncePair {
             d: Nonce {
@@ -78,10 +93,68 @@ impl SigningNoncePair {
             },
         })
     }
+
+    /// The nonce pair of $-R$, for suites that take R with an even y.
+    pub fn negate(&self) -> SigningNoncePair<C> {
+        let negate = |n: &Nonce<C>| Nonce {
+            secret: -n.secret,
+            public: -n.public,
+        };
+        SigningNoncePair {
+            d: negate(&self.d),
+            e: negate(&self.e),
+        }
+    }
+}
+
+impl SignMode {
+    pub fn validate(&self) -> Outcome<()> {
+        match self {
+            SignMode::Ed25519 => {}
+            SignMode::Ed25519ph(ctx) => {
+                assert_throw!(ctx.len() <= 255, "Context string exceeds 255 bytes");
+            }
+            SignMode::Ed25519ctx(ctx) => {
+                assert_throw!(ctx.len() <= 255, "Context string exceeds 255 bytes");
+                assert_throw!(!ctx.is_empty(), "Ed25519ctx requires a context string");
+            }
+        }
+        Ok(())
+    }
+
+    /// dom2(phflag, context) of RFC 8032, which is empty for pure Ed25519.
+    pub fn dom2(&self) -> Vec<u8> {
+        let (phflag, ctx) = match self {
+            SignMode::Ed25519 => return Vec::new(),
+            SignMode::Ed25519ph(ctx) => (1u8, ctx),
+            SignMode::Ed25519ctx(ctx) => (0u8, ctx),
+        };
+        let mut dom = b"SigEd25519 no Ed25519 collisions".to_vec();
+        dom.push(phflag);
+        dom.push(ctx.len() as u8);
+        dom.extend_from_slice(ctx);
+        dom
+    }
+
+    /// PH(msg) of RFC 8032.
+    pub fn prehash(&self, msg: &[u8]) -> Vec<u8> {
+        match self {
+            SignMode::Ed25519ph(_) => Sha512::digest(msg).to_vec(),
+            _ => msg.to_vec(),
+        }
+    }
+
+    /// Everything the challenge commits to besides R and Y, i.e. dom2 || PH(msg).
+    /// Binding factors are computed over it, so that they cover the mode as well.
+    pub fn signed_bytes(&self, msg: &[u8]) -> Vec<u8> {
+        let mut m = self.dom2();
+        m.extend(self.prehash(msg));
+        m
+    }
 }
 
-use curve25519_dalek::{constants, edwards::EdwardsPoint, scalar::Scalar, traits::Identity};
 use libexception::*;
 use rand::{CryptoRng, RngCore};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha512};
 use zeroize::Zeroize;
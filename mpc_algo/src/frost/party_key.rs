@@ -1,92 +1,108 @@
 use std::collections::{HashMap, HashSet};
 
-use curve25519_dalek::{constants, edwards::EdwardsPoint, scalar::Scalar, traits::Identity};
+use curve25519_dalek::{edwards::EdwardsPoint, scalar::Scalar, traits::IsIdentity};
 use libexception::*;
-use mpc_spec::MpcAddr;
+use mpc_spec::{FrostMode, MpcAddr};
 use rand::{CryptoRng, RngCore};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use zeroize::Zeroize;
 
+use super::{lagrange_lambda, lagrange_lambda_at, rfc9591, Blame, Ciphersuite, Ed25519, Evidence};
+
+/// Sub-shares of each member, the first one at its member id.
+pub type SubShareDict<C = Ed25519> = HashMap<MpcAddr, Vec<<C as Ciphersuite>::Scalar>>;
+
 #[derive(Copy, Clone, Debug, Serialize, Deserialize)]
-pub struct PartyKey {
-    pub u_i: Scalar,
-    pub k_i: Scalar,
+#[serde(bound = "")]
+pub struct PartyKey<C: Ciphersuite = Ed25519> {
+    pub u_i: C::Scalar,
+    pub k_i: C::Scalar,
 }
 
-impl PartyKey {
+impl<C: Ciphersuite> PartyKey<C> {
     pub fn new<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
-        let u_i = Scalar::random(rng);
-        let k = Scalar::random(rng);
+        let u_i = C::random_scalar(rng);
+        let k = C::random_scalar(rng);
         Self { u_i, k_i: k }
     }
 
-    pub fn g_u_i(&self) -> EdwardsPoint {
-        &constants::ED25519_BASEPOINT_TABLE * &self.u_i
+    pub fn g_u_i(&self) -> C::Point {
+        C::base_mul(&self.u_i)
     }
 
-    pub fn g_k_i(&self) -> EdwardsPoint {
-        &constants::ED25519_BASEPOINT_TABLE * &self.k_i
+    pub fn g_k_i(&self) -> C::Point {
+        C::base_mul(&self.k_i)
     }
 
-    #[allow(dead_code)]
-    pub fn import<R: RngCore + CryptoRng>(u_i: Scalar, rng: &mut R) -> Self {
-        let k = Scalar::random(rng);
+    pub fn import<R: RngCore + CryptoRng>(u_i: C::Scalar, rng: &mut R) -> Self {
+        let k = C::random_scalar(rng);
         Self { u_i, k_i: k }
     }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct KeyGenDKGProposedCommitment {
-    pub shares_commitment: Vec<EdwardsPoint>,
-    pub zkp: KeyGenZKP,
+#[serde(bound = "")]
+pub struct KeyGenDKGProposedCommitment<C: Ciphersuite = Ed25519> {
+    pub shares_commitment: Vec<C::Point>,
+    pub zkp: KeyGenZKP<C>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct KeyGenZKP {
-    pub g_k_i: EdwardsPoint, // KeyGen: g_k
-    pub sigma: Scalar,       // KeyGen: sigma
+#[serde(bound = "")]
+pub struct KeyGenZKP<C: Ciphersuite = Ed25519> {
+    pub g_k_i: C::Point,  // KeyGen: g_k
+    pub sigma: C::Scalar, // KeyGen: sigma
 }
 
-impl Zeroize for KeyGenDKGProposedCommitment {
+impl<C: Ciphersuite> Zeroize for KeyGenDKGProposedCommitment<C> {
     fn zeroize(&mut self) {
         self.shares_commitment.zeroize();
         self.zkp.zeroize();
     }
 }
 
-impl KeyGenDKGProposedCommitment {
-    pub fn is_valid_zkp(&self, challenge: Scalar) -> Outcome<()> {
+impl<C: Ciphersuite> KeyGenDKGProposedCommitment<C> {
+    pub fn is_valid_zkp(&self, challenge: C::Scalar) -> Outcome<()> {
         let valid_zkp = self.zkp.g_k_i
-            == (&constants::ED25519_BASEPOINT_TABLE * &self.zkp.sigma)
-                - (self.get_commitment_to_secret() * challenge);
+            == C::base_mul(&self.zkp.sigma) - (self.get_commitment_to_secret() * challenge);
         assert_throw!(valid_zkp);
         Ok(())
     }
 
-    pub fn get_commitment_to_secret(&self) -> EdwardsPoint {
+    pub fn get_commitment_to_secret(&self) -> C::Point {
         self.shares_commitment[0]
     }
 }
 
 /// keygen_receive_commitments_and_validate_peers gathers commitments from
 /// peers and validates the zero knowledge proof of knowledge for the peer's
-/// secret term. It returns a list of all participants who failed the check,
-/// a list of commitments for the peers that remain in a valid state,
-/// and an error term.
+/// secret term, as well as the number of coefficients. It returns a list of
+/// commitments for the peers, or an `IdentifiableAbort` blaming all
+/// participants who failed the check.
 ///
 /// Here, we return a DKG commitmentment that is explicitly marked as valid,
 /// to ensure that this step of the protocol is performed before going on to
 /// keygen_finalize
-pub fn keygen_validate_peers(
-    proposed_coms: &HashMap<MpcAddr, KeyGenDKGProposedCommitment>,
+pub fn keygen_validate_peers<C: Ciphersuite>(
+    proposed_coms: &HashMap<MpcAddr, KeyGenDKGProposedCommitment<C>>,
     context: &str,
-) -> Outcome<HashMap<MpcAddr, Vec<EdwardsPoint>>> {
-    let mut invalid_ids = Vec::new();
+    mode: FrostMode,
+    th: usize,
+) -> Outcome<HashMap<MpcAddr, Vec<C::Point>>> {
+    let mut blame = Blame::<C>::default();
     let mut valid_coms = HashMap::new();
 
     for (id, com) in proposed_coms.iter() {
-        let challenge = generate_dkg_challenge(
+        // to avoid DKG attack via increasing threshold on the fly.
+        if com.shares_commitment.len() != th {
+            let com = com.shares_commitment.clone();
+            blame.accuse(*id, Evidence::MalformedCommitment { com, th });
+            continue;
+        }
+
+        let challenge = generate_dkg_challenge::<C>(
+            mode,
             *id,
             context,
             &com.get_commitment_to_secret(),
@@ -98,16 +114,106 @@ pub fn keygen_validate_peers(
             let valid_com = com.shares_commitment.clone();
             valid_coms.insert(*id, valid_com);
         } else {
-            invalid_ids.push(*id);
+            let com = com.clone();
+            blame.accuse(*id, Evidence::InvalidZkp { com });
         }
     }
+    blame.throw_if_any()?;
 
-    if invalid_ids.len() > 0 {
-        let errmsg = format!("Invalid zkp from parties {:?}", invalid_ids);
-        throw!("", &errmsg);
+    Ok(valid_coms)
+}
+
+/// Validate the commitments of refresh polynomials, which must have `th`
+/// coefficients and a zero constant term, so that the public key is kept.
+pub fn refresh_validate_peers(
+    refresh_coms: &HashMap<MpcAddr, Vec<EdwardsPoint>>,
+    th: usize,
+) -> Outcome<()> {
+    let mut blame: Blame = Blame::default();
+    for (id, com) in refresh_coms.iter() {
+        if com.len() != th {
+            let com = com.clone();
+            blame.accuse(*id, Evidence::MalformedCommitment { com, th });
+        } else if !com[0].is_identity() {
+            let com = com.clone();
+            blame.accuse(*id, Evidence::NonZeroRefresh { com });
+        }
     }
+    blame.throw_if_any()?;
+    Ok(())
+}
 
-    Ok(valid_coms)
+/// Validate the commitments of resharing polynomials, which must have `th`
+/// coefficients and hide $\lambda_i x_i$ of their dealer, so that the group's
+/// contribution to the public key is kept.
+pub fn reshare_validate_peers(
+    reshare_coms: &HashMap<MpcAddr, Vec<EdwardsPoint>>,
+    vss_com_dict: &HashMap<MpcAddr, Vec<EdwardsPoint>>,
+    th: usize,
+) -> Outcome<()> {
+    let holders: HashSet<MpcAddr> = reshare_coms.keys().cloned().collect();
+    let mut blame: Blame = Blame::default();
+    for (id, com) in reshare_coms.iter() {
+        if com.len() != th {
+            let com = com.clone();
+            blame.accuse(*id, Evidence::MalformedCommitment { com, th });
+            continue;
+        }
+        let lambda_i = lagrange_lambda(*id, &holders).catch_()?;
+        if com[0] != eval_xi_com(*id, vss_com_dict) * lambda_i {
+            let com = com.clone();
+            blame.accuse(*id, Evidence::InvalidReshare { com });
+        }
+    }
+    blame.throw_if_any()?;
+    Ok(())
+}
+
+/// `merge_vss_share()` of the sub-shares of a member of `weight`, where
+/// `party_shares[j][k]` is the share of dealer `j` at the `k`-th point of `my_id`.
+pub fn merge_weighted_vss_share<C: Ciphersuite>(
+    party_shares: &SubShareDict<C>,
+    share_coms: &HashMap<MpcAddr, Vec<C::Point>>,
+    my_id: MpcAddr,
+    weight: u16,
+) -> Outcome<Vec<C::Scalar> /* x_{i,k} */> {
+    let points = share_points::<C>(my_id, weight);
+    blame_invalid_sub_shares::<C>(party_shares, share_coms, my_id, weight)
+        .catch_()?
+        .throw_if_any()?;
+
+    let mut x_i = vec![C::Scalar::default(); points.len()];
+    for shares in party_shares.values() {
+        for (x, share) in x_i.iter_mut().zip(shares.iter()) {
+            *x += *share;
+        }
+    }
+
+    Ok(x_i)
+}
+
+/// Blame the dealers in `party_shares` whose sub-shares of `my_id` of
+/// `weight` are not evaluations of their polynomial in `share_coms`.
+pub fn blame_invalid_sub_shares<C: Ciphersuite>(
+    party_shares: &SubShareDict<C>,
+    share_coms: &HashMap<MpcAddr, Vec<C::Point>>,
+    my_id: MpcAddr,
+    weight: u16,
+) -> Outcome<Blame<C>> {
+    let points = share_points::<C>(my_id, weight);
+    let mut blame = Blame::<C>::default();
+    for (id, shares) in party_shares.iter() {
+        let com = share_coms.get(id).ifnone_()?;
+        assert_throw!(shares.len() == points.len(), "Wrong number of sub-shares");
+        for (x, share) in points.iter().zip(shares.iter()) {
+            if verify_vss_share_at::<C>(x, share, com).is_err() {
+                let (share, com) = (*share, com.clone());
+                blame.accuse(*id, Evidence::InvalidShare { share, com });
+                break;
+            }
+        }
+    }
+    Ok(blame)
 }
 
 pub fn merge_vss_share(
@@ -116,10 +222,15 @@ pub fn merge_vss_share(
     my_id: MpcAddr,
 ) -> Outcome<Scalar /* x_i, aka the signing key */> {
     // first, verify the integrity of the shares
+    let mut blame: Blame = Blame::default();
     for (id, share) in party_shares.iter() {
         let com = share_coms.get(id).ifnone_()?;
-        verify_vss_share(my_id, share, com).catch_()?;
+        if verify_vss_share(my_id, share, com).is_err() {
+            let (share, com) = (*share, com.clone());
+            blame.accuse(*id, Evidence::InvalidShare { share, com });
+        }
     }
+    blame.throw_if_any()?;
 
     let mut x_i = Scalar::zero();
     for ps in party_shares.values() {
@@ -136,6 +247,21 @@ pub fn generate_vss_share<R: RngCore + CryptoRng>(
     th: usize, // At least `th` members during sign.
     rng: &mut R,
 ) -> Outcome<(Vec<EdwardsPoint>, HashMap<MpcAddr, Scalar>)> {
+    let _obj: _ =
+        generate_weighted_vss_share::<Ed25519, R>(u_i, my_id, members, &HashMap::new(), th, rng)?;
+    Ok((_obj.0, first_shares(_obj.1)))
+}
+
+/// `generate_vss_share()` where a member of weight w gets w sub-shares.
+/// Members absent from `weights` weigh 1.
+pub fn generate_weighted_vss_share<C: Ciphersuite, R: RngCore + CryptoRng>(
+    u_i: &C::Scalar,
+    my_id: MpcAddr,
+    members: &HashSet<MpcAddr>,
+    weights: &HashMap<MpcAddr, u16>,
+    th: usize, // At least `th` sub-shares during sign.
+    rng: &mut R,
+) -> Outcome<(Vec<C::Point>, SubShareDict<C>)> {
     assert_throw!(
         members.contains(&my_id),
         format!("{} not in members", my_id)
@@ -146,26 +272,50 @@ pub fn generate_vss_share<R: RngCore + CryptoRng>(
             "vss_share: members not in same group"
         );
     }
-    assert_throw!(1 <= th && th < members.len());
+    deal_weighted_vss_share::<C, R>(u_i, members, weights, th, rng)
+}
+
+/// Share `secret` among `members`, who need not include the dealer.
+pub fn deal_vss_share<R: RngCore + CryptoRng>(
+    secret: &Scalar,
+    members: &HashSet<MpcAddr>,
+    th: usize, // At least `th` members during sign.
+    rng: &mut R,
+) -> Outcome<(Vec<EdwardsPoint>, HashMap<MpcAddr, Scalar>)> {
+    let _obj: _ = deal_weighted_vss_share::<Ed25519, R>(secret, members, &HashMap::new(), th, rng)?;
+    Ok((_obj.0, first_shares(_obj.1)))
+}
+
+/// `deal_vss_share()` where a member of weight w gets w sub-shares, at the
+/// points of `share_points()`. Members absent from `weights` weigh 1.
+pub fn deal_weighted_vss_share<C: Ciphersuite, R: RngCore + CryptoRng>(
+    secret: &C::Scalar,
+    members: &HashSet<MpcAddr>,
+    weights: &HashMap<MpcAddr, u16>,
+    th: usize, // At least `th` sub-shares during sign.
+    rng: &mut R,
+) -> Outcome<(Vec<C::Point>, SubShareDict<C>)> {
+    let total: usize = members.iter().map(|i| weight_of(i, weights) as usize).sum();
+    assert_throw!(1 <= th && th < total);
 
     // randomly generate a polynomial
-    let mut poly: Vec<Scalar> = vec![u_i.clone()];
+    let mut poly: Vec<C::Scalar> = vec![*secret];
     for _ in 1..th {
-        poly.push(Scalar::random(rng));
+        poly.push(C::random_scalar(rng));
     }
 
     // commit to the polynomial
     let mut poly_com = Vec::new();
     for c in poly.iter() {
-        poly_com.push(&constants::ED25519_BASEPOINT_TABLE * c);
+        poly_com.push(C::base_mul(c));
     }
 
     // treat member ID as $x$,
     // and evaluate the polynomial at each $x$.
-    let mut shares: HashMap<MpcAddr, Scalar> = HashMap::new();
+    let mut shares: SubShareDict<C> = HashMap::new();
     for i in members.iter() {
-        let x = Scalar::from(i.member_id());
-        let y = eval_poly(&poly, &x);
+        let points = share_points::<C>(*i, weight_of(i, weights));
+        let y: Vec<C::Scalar> = points.iter().map(|x| eval_poly::<C>(&poly, x)).collect();
         shares.insert(*i, y);
     }
 
@@ -176,17 +326,73 @@ pub fn generate_vss_share<R: RngCore + CryptoRng>(
     Ok((poly_com, shares))
 }
 
+fn first_shares(mut shares: SubShareDict) -> HashMap<MpcAddr, Scalar> {
+    shares.iter_mut().map(|(i, y)| (*i, y.remove(0))).collect()
+}
+
+/// Weight of `id` in `weights`, where members absent from it weigh 1.
+pub fn weight_of(id: &MpcAddr, weights: &HashMap<MpcAddr, u16>) -> u16 {
+    weights.get(id).cloned().unwrap_or(1)
+}
+
+/// Evaluation points of the sub-shares of `id`, i.e. $m + k \cdot 2^{16}$
+/// for $k < w$ where $m$ is the member id. The first one is the member id
+/// itself, so unweighted members keep their only share.
+pub fn share_points<C: Ciphersuite>(id: MpcAddr, weight: u16) -> Vec<C::Scalar> {
+    (0..u64::from(weight))
+        .map(|k| C::scalar_from_u64(u64::from(id.member_id()) + (k << 16)))
+        .collect()
+}
+
+/// Split $\lambda_i(t) x_i$ into random sub-shares, one for every helper,
+/// where $\lambda_i(t)$ interpolates the polynomial of the helpers at the
+/// member id of `target`. The sub-shares received by a helper add up to its
+/// part of $x_t$, and no helper learns another one's $x_i$.
+pub fn repair_split<R: RngCore + CryptoRng>(
+    my_id: MpcAddr,
+    x_i: &Scalar,
+    target: MpcAddr,
+    helpers: &HashSet<MpcAddr>,
+    rng: &mut R,
+) -> Outcome<HashMap<MpcAddr, Scalar>> {
+    assert_throw!(helpers.contains(&my_id), "I am not a helper");
+    assert_throw!(!helpers.contains(&target), "The target cannot help itself");
+    let t = Scalar::from(target.member_id());
+    let delta = lagrange_lambda_at(my_id, helpers, &t).catch_()? * x_i;
+
+    let mut sub_shares: HashMap<MpcAddr, Scalar> = HashMap::new();
+    let mut rest = delta;
+    for j in helpers.iter() {
+        if *j != my_id {
+            let r = Scalar::random(rng);
+            rest -= r;
+            sub_shares.insert(*j, r);
+        }
+    }
+    sub_shares.insert(my_id, rest);
+    Ok(sub_shares)
+}
+
 /// This may vary from chain to chain, from protocol to protocol.
-pub fn generate_dkg_challenge(
+pub fn generate_dkg_challenge<C: Ciphersuite>(
+    mode: FrostMode,
     index: MpcAddr,
     context: &str,
-    public: &EdwardsPoint,
-    commitment: &EdwardsPoint,
-) -> Outcome<Scalar> {
+    public: &C::Point,
+    commitment: &C::Point,
+) -> Outcome<C::Scalar> {
+    if mode == FrostMode::Rfc9591 {
+        let mut m = C::serialize_scalar(&rfc9591::identifier::<C>(index));
+        m.extend(C::serialize_point(public));
+        m.extend(C::serialize_point(commitment));
+        m.extend_from_slice(context.as_bytes());
+        return Ok(C::hdkg(&m));
+    }
+
     let mut hasher = Sha256::new();
     // the order of the below may change to allow for EdDSA verification compatibility
-    hasher.update(commitment.compress().to_bytes());
-    hasher.update(public.compress().to_bytes());
+    hasher.update(C::serialize_point(commitment));
+    hasher.update(C::serialize_point(public));
     hasher.update(index.to_string());
     hasher.update(context);
     let result = hasher.finalize();
@@ -196,16 +402,23 @@ pub fn generate_dkg_challenge(
         .try_into()
         .expect("Error generating commitment!");
 
-    Ok(Scalar::from_bytes_mod_order(a))
+    Ok(C::scalar_from_le_bytes_mod_order(&a))
 }
 
 /// Verify that a share is consistent with a commitment.
 /// i.e. verify that a share is computed from the polynomial represented by `com`.
 pub fn verify_vss_share(id: MpcAddr, share: &Scalar, com: &[EdwardsPoint]) -> Outcome<()> {
-    let polycom = &constants::ED25519_BASEPOINT_TABLE * share;
+    verify_vss_share_at::<Ed25519>(&Scalar::from(id.member_id()), share, com)
+}
 
-    let x = Scalar::from(id.member_id());
-    let expanded_polycom = eval_polycom(com, &x);
+/// Verify a share of the polynomial represented by `com` at the point `x`.
+pub fn verify_vss_share_at<C: Ciphersuite>(
+    x: &C::Scalar,
+    share: &C::Scalar,
+    com: &[C::Point],
+) -> Outcome<()> {
+    let polycom = C::base_mul(share);
+    let expanded_polycom = eval_polycom::<C>(com, x);
     assert_throw!(polycom == expanded_polycom, "Invalid share");
 
     Ok(())
@@ -216,28 +429,38 @@ pub fn eval_xi_com(
     index: MpcAddr,
     vss_com_dict: &HashMap<MpcAddr, Vec<EdwardsPoint>>,
 ) -> EdwardsPoint {
-    let mut gxi = EdwardsPoint::identity();
-    let i = Scalar::from(index.member_id());
-    for vss_com in vss_com_dict.values() {
-        gxi += eval_polycom(vss_com, &i);
+    eval_sub_xi_coms::<Ed25519>(index, 1, vss_com_dict)[0]
+}
+
+/// Evaluate $x_{i,k} \ast G$ of each sub-share of `index`, without knowing them.
+pub fn eval_sub_xi_coms<C: Ciphersuite>(
+    index: MpcAddr,
+    weight: u16,
+    vss_com_dict: &HashMap<MpcAddr, Vec<C::Point>>,
+) -> Vec<C::Point> {
+    let mut gxi = vec![C::Point::default(); weight as usize];
+    for (k, x) in share_points::<C>(index, weight).iter().enumerate() {
+        for vss_com in vss_com_dict.values() {
+            gxi[k] += eval_polycom::<C>(vss_com, x);
+        }
     }
     gxi
 }
 
 /// evaluate a polynomial using Qin Jiushao (秦久韶) / Horner's method.
 /// NOTE: coefficients should be traversed in DEscending power of `x`.
-fn eval_poly(poly: &[Scalar], x: &Scalar) -> Scalar {
-    let mut y = Scalar::zero();
+pub(super) fn eval_poly<C: Ciphersuite>(poly: &[C::Scalar], x: &C::Scalar) -> C::Scalar {
+    let mut y = C::Scalar::default();
     for coef in poly.iter().rev() {
-        y = y * x + coef;
+        y = y * *x + *coef;
     }
     y
 }
 
-fn eval_polycom(coef_coms: &[EdwardsPoint], x: &Scalar) -> EdwardsPoint {
-    let mut polycom = EdwardsPoint::identity();
+pub(super) fn eval_polycom<C: Ciphersuite>(coef_coms: &[C::Point], x: &C::Scalar) -> C::Point {
+    let mut polycom = C::Point::default();
     for coef_com in coef_coms.iter().rev() {
-        polycom = (polycom * x) + coef_com;
+        polycom = (polycom * *x) + *coef_com;
     }
     polycom
 }
@@ -0,0 +1,199 @@
+use std::collections::{HashMap, HashSet};
+
+use curve25519_dalek::{constants, edwards::EdwardsPoint, scalar::Scalar};
+use libexception::*;
+use mpc_spec::AccessPolicy;
+use rand::{CryptoRng, RngCore};
+use zeroize::Zeroize;
+
+use super::{eval_poly, eval_polycom, Ciphersuite, Ed25519};
+
+/// Coefficient of each group of `groups` in the secret, i.e.
+/// $s = \sum_g c_g V_g$ where $V_g$ is the value dealt to group $g$.
+///
+/// At each gate of `k` sub-policies, the first `k` satisfied ones are chosen,
+/// and $c_g$ is the product of the Lagrange coefficients at zero along the
+/// path of $g$. Groups left out of the choice have a zero coefficient.
+pub fn policy_coefs<C: Ciphersuite>(
+    policy: &AccessPolicy,
+    groups: &HashSet<u16>,
+) -> Outcome<HashMap<u16, C::Scalar>> {
+    assert_throw!(
+        policy.is_satisfied_by(groups),
+        "NotQualified",
+        format!("Groups {:?} do not satisfy the policy", groups)
+    );
+    let mut coefs: HashMap<u16, C::Scalar> =
+        groups.iter().map(|g| (*g, C::Scalar::default())).collect();
+    choose::<C>(policy, &C::scalar_from_u64(1), groups, &mut coefs);
+    Ok(coefs)
+}
+
+fn choose<C: Ciphersuite>(
+    policy: &AccessPolicy,
+    coef: &C::Scalar,
+    groups: &HashSet<u16>,
+    coefs: &mut HashMap<u16, C::Scalar>,
+) {
+    match policy {
+        AccessPolicy::Group(gid) => {
+            coefs.insert(*gid, *coef);
+        }
+        AccessPolicy::Threshold(k, subs) => {
+            let chosen: Vec<usize> = (0..subs.len())
+                .filter(|i| subs[*i].is_satisfied_by(groups))
+                .take(*k)
+                .collect();
+            for i in chosen.iter() {
+                let lambda = gate_lambda::<C>(*i, &chosen);
+                choose::<C>(&subs[*i], &(*coef * lambda), groups, coefs);
+            }
+        }
+    }
+}
+
+/// Lagrange coefficient at zero of the `i`-th sub-policy among `chosen`,
+/// the evaluation point of the `i`-th one being `i + 1`.
+fn gate_lambda<C: Ciphersuite>(i: usize, chosen: &[usize]) -> C::Scalar {
+    let x_i = C::scalar_from_u64(i as u64 + 1);
+    let mut num = C::scalar_from_u64(1);
+    let mut den = C::scalar_from_u64(1);
+    for j in chosen.iter() {
+        if *j == i {
+            continue;
+        }
+        let x_j = C::scalar_from_u64(*j as u64 + 1);
+        num *= x_j;
+        den *= x_j - x_i;
+    }
+    num * C::invert(&den)
+}
+
+/// Share `secret` along the gates of `policy`. Each gate of `k` sub-policies
+/// draws a polynomial of degree `k - 1` with its own value as the constant
+/// term, and gives its `i`-th sub-policy the value at `i + 1`.
+///
+/// Returns the commitments of the gate polynomials in pre-order, and the
+/// value dealt to each group.
+pub fn deal_policy_share<R: RngCore + CryptoRng>(
+    secret: &Scalar,
+    policy: &AccessPolicy,
+    rng: &mut R,
+) -> (Vec<Vec<EdwardsPoint>>, HashMap<u16, Scalar>) {
+    let mut gate_coms: Vec<Vec<EdwardsPoint>> = Vec::new();
+    let mut values: HashMap<u16, Scalar> = HashMap::new();
+    deal_gate(secret, policy, rng, &mut gate_coms, &mut values);
+    (gate_coms, values)
+}
+
+fn deal_gate<R: RngCore + CryptoRng>(
+    value: &Scalar,
+    policy: &AccessPolicy,
+    rng: &mut R,
+    gate_coms: &mut Vec<Vec<EdwardsPoint>>,
+    values: &mut HashMap<u16, Scalar>,
+) {
+    match policy {
+        AccessPolicy::Group(gid) => {
+            values.insert(*gid, *value);
+        }
+        AccessPolicy::Threshold(k, subs) => {
+            let mut poly: Vec<Scalar> = vec![*value];
+            for _ in 1..*k {
+                poly.push(Scalar::random(rng));
+            }
+            let poly_com = poly
+                .iter()
+                .map(|c| &constants::ED25519_BASEPOINT_TABLE * c)
+                .collect();
+            gate_coms.push(poly_com);
+            for (i, sub) in subs.iter().enumerate() {
+                let mut y = eval_poly::<Ed25519>(&poly, &Scalar::from(i as u64 + 1));
+                deal_gate(&y, sub, rng, gate_coms, values);
+                y.zeroize();
+            }
+            for c in poly.iter_mut() {
+                c.zeroize();
+            }
+        }
+    }
+}
+
+/// Commitment of the value dealt to each group, given the commitment of the
+/// secret and those of the gate polynomials of `deal_policy_share()`.
+/// `None` if the gate commitments do not fit the policy, or do not hide `root`.
+pub fn eval_policy_com(
+    root: &EdwardsPoint,
+    policy: &AccessPolicy,
+    gate_coms: &[Vec<EdwardsPoint>],
+) -> Option<HashMap<u16, EdwardsPoint>> {
+    let mut value_coms: HashMap<u16, EdwardsPoint> = HashMap::new();
+    let mut next = 0usize;
+    eval_gate_com(root, policy, gate_coms, &mut next, &mut value_coms)?;
+    match next == gate_coms.len() {
+        true => Some(value_coms),
+        false => None,
+    }
+}
+
+fn eval_gate_com(
+    value_com: &EdwardsPoint,
+    policy: &AccessPolicy,
+    gate_coms: &[Vec<EdwardsPoint>],
+    next: &mut usize,
+    value_coms: &mut HashMap<u16, EdwardsPoint>,
+) -> Option<()> {
+    match policy {
+        AccessPolicy::Group(gid) => {
+            value_coms.insert(*gid, *value_com);
+        }
+        AccessPolicy::Threshold(k, subs) => {
+            let poly_com = gate_coms.get(*next)?;
+            *next += 1;
+            if poly_com.len() != *k || poly_com[0] != *value_com {
+                return None;
+            }
+            for (i, sub) in subs.iter().enumerate() {
+                let y_com = eval_polycom::<Ed25519>(poly_com, &Scalar::from(i as u64 + 1));
+                eval_gate_com(&y_com, sub, gate_coms, next, value_coms)?;
+            }
+        }
+    }
+    Some(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::OsRng;
+    use AccessPolicy::*;
+
+    #[test]
+    fn reconstruct_from_qualified_groups() {
+        let policies = [
+            AccessPolicy::or(vec![Group(1), AccessPolicy::and(vec![Group(2), Group(3)])]),
+            Threshold(2, vec![Group(1), Group(2), Group(3)]),
+        ];
+        let mut rng = OsRng;
+        let secret = Scalar::random(&mut rng);
+        for policy in policies.iter() {
+            let (gate_coms, values) = deal_policy_share(&secret, policy, &mut rng);
+            let root = &constants::ED25519_BASEPOINT_TABLE * &secret;
+            let value_coms = eval_policy_com(&root, policy, &gate_coms).unwrap();
+            for (gid, value) in values.iter() {
+                assert_eq!(value_coms[gid], &constants::ED25519_BASEPOINT_TABLE * value);
+            }
+            assert!(eval_policy_com(&(root + root), policy, &gate_coms).is_none());
+
+            for groups in [vec![1, 2], vec![2, 3], vec![1, 2, 3]] {
+                let groups: HashSet<u16> = groups.into_iter().collect();
+                let coefs = policy_coefs::<Ed25519>(policy, &groups).unwrap();
+                let s: Scalar = coefs.iter().map(|(g, c)| c * values[g]).sum();
+                assert_eq!(s, secret);
+                // A constant dealt to every group adds up to itself.
+                assert_eq!(coefs.values().sum::<Scalar>(), Scalar::one());
+            }
+            assert!(policy_coefs::<Ed25519>(policy, &[2].into_iter().collect()).is_err());
+        }
+    }
+}
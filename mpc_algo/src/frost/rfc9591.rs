@@ -0,0 +1,316 @@
+//! Round one and the binding factors of RFC 9591 over any `Ciphersuite`, and
+//! the hash functions of its FROST(Ed25519, SHA-512) ciphersuite.
+//!
+//! Participant identifiers are the full 32-bit `MpcAddr`, so that members of
+//! different groups never share a binding factor. Lagrange interpolation is
+//! still performed on `member_id` within each group.
+
+use std::collections::HashMap;
+
+use curve25519_dalek::scalar::Scalar;
+use itertools::Itertools;
+use libexception::*;
+use mpc_spec::MpcAddr;
+use rand::{CryptoRng, RngCore};
+use sha2::{Digest, Sha512};
+
+use super::{Ciphersuite, Nonce, SigningCommitmentPair, SigningNoncePair};
+
+pub const CONTEXT_STRING: &str = "FROST-ED25519-SHA512-v1";
+
+fn hash(tag: &str, m: &[u8]) -> Sha512 {
+    let mut hasher = Sha512::new();
+    hasher.update(CONTEXT_STRING);
+    hasher.update(tag);
+    hasher.update(m);
+    hasher
+}
+
+/// H1, used to derive binding factors.
+pub fn h1(m: &[u8]) -> Scalar {
+    Scalar::from_hash(hash("rho", m))
+}
+
+/// H2, used to derive the challenge. It has no prefix, which keeps the
+/// signature verifiable by any RFC 8032 Ed25519 verifier.
+pub fn h2(m: &[u8]) -> Scalar {
+    let mut hasher = Sha512::new();
+    hasher.update(m);
+    Scalar::from_hash(hasher)
+}
+
+/// H3, used to derive nonces.
+pub fn h3(m: &[u8]) -> Scalar {
+    Scalar::from_hash(hash("nonce", m))
+}
+
+/// H4, used to digest the message before computing binding factors.
+pub fn h4(m: &[u8]) -> Vec<u8> {
+    hash("msg", m).finalize().to_vec()
+}
+
+/// H5, used to digest the encoded commitment list.
+pub fn h5(m: &[u8]) -> Vec<u8> {
+    hash("com", m).finalize().to_vec()
+}
+
+/// Challenge of the DKG proof of knowledge. RFC 9591 leaves DKG out of scope,
+/// so this follows the domain separation of H1-H5 with a "dkg" tag.
+pub fn hdkg(m: &[u8]) -> Scalar {
+    Scalar::from_hash(hash("dkg", m))
+}
+
+pub fn identifier<C: Ciphersuite>(id: MpcAddr) -> C::Scalar {
+    C::scalar_from_u64(u64::from(id.as_primitive()))
+}
+
+/// nonce_generate() of RFC 9591, with the 32 random bytes supplied by the caller.
+pub fn nonce_generate<C: Ciphersuite>(random_bytes: &[u8; 32], secret: &C::Scalar) -> C::Scalar {
+    let mut m = random_bytes.to_vec();
+    m.extend(C::serialize_scalar(secret));
+    C::h3(&m)
+}
+
+/// commit() of RFC 9591. The nonces are bound to the signing share `x_i`, in
+/// addition to fresh randomness, to survive a weak RNG.
+pub fn commit<C: Ciphersuite, R: RngCore + CryptoRng>(
+    x_i: &C::Scalar,
+    rng: &mut R,
+) -> Outcome<(SigningCommitmentPair<C>, SigningNoncePair<C>)> {
+    let mut d_rand = [0u8; 32];
+    let mut e_rand = [0u8; 32];
+    rng.fill_bytes(&mut d_rand);
+    rng.fill_bytes(&mut e_rand);
+    let d = nonce_generate::<C>(&d_rand, x_i);
+    let e = nonce_generate::<C>(&e_rand, x_i);
+
+    let nonce = SigningNoncePair {
+        d: Nonce {
+            secret: d,
+            public: C::base_mul(&d),
+        },
+        e: Nonce {
+            secret: e,
+            public: C::base_mul(&e),
+        },
+    };
+    let com = SigningCommitmentPair::new(nonce.d.public, nonce.e.public).catch_()?;
+    Ok((com, nonce))
+}
+
+/// Serialize (identifier, hiding commitment, binding commitment) of every
+/// signer, in ascending order of identifiers.
+pub fn encode_group_commitment_list<C: Ciphersuite>(
+    nonce_com_dict: &HashMap<MpcAddr, SigningCommitmentPair<C>>,
+) -> Vec<u8> {
+    let mut encoded = Vec::new();
+    for (id, com) in nonce_com_dict.iter().sorted_by_key(|(id, _)| *id) {
+        encoded.extend(C::serialize_scalar(&identifier::<C>(*id)));
+        encoded.extend(C::serialize_point(&com.g_d));
+        encoded.extend(C::serialize_point(&com.g_e));
+    }
+    encoded
+}
+
+pub fn compute_binding_factors<C: Ciphersuite>(
+    pk: &C::Point,
+    nonce_com_dict: &HashMap<MpcAddr, SigningCommitmentPair<C>>,
+    msg: &[u8],
+) -> HashMap<MpcAddr, C::Scalar> {
+    let mut rho_input_prefix = C::serialize_point(pk);
+    rho_input_prefix.extend(C::h4(msg));
+    rho_input_prefix.extend(C::h5(&encode_group_commitment_list(nonce_com_dict)));
+
+    let mut rho_dict = HashMap::new();
+    for id in nonce_com_dict.keys() {
+        let mut rho_input = rho_input_prefix.clone();
+        rho_input.extend(C::serialize_scalar(&identifier::<C>(*id)));
+        rho_dict.insert(*id, C::h1(&rho_input));
+    }
+    rho_dict
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use curve25519_dalek::{constants, edwards::EdwardsPoint};
+
+    use super::*;
+    use crate::frost::{
+        agg_nonce_com, sign_and_respond, verify_signature, verify_solana, Ciphersuite, Ed25519,
+        Ed448, Ristretto255, Secp256k1, SignMode, Signature,
+    };
+
+    fn scalar(s: &str) -> Scalar {
+        let bytes: [u8; 32] = hex::decode(s).unwrap().try_into().unwrap();
+        Scalar::from_canonical_bytes(bytes).unwrap()
+    }
+
+    fn point_hex(p: &EdwardsPoint) -> String {
+        hex::encode(p.compress().as_bytes())
+    }
+
+    fn rand_bytes(s: &str) -> [u8; 32] {
+        hex::decode(s).unwrap().try_into().unwrap()
+    }
+
+    fn nonce_pair(d: Scalar, e: Scalar) -> SigningNoncePair {
+        let G = &constants::ED25519_BASEPOINT_TABLE;
+        SigningNoncePair {
+            d: Nonce {
+                secret: d,
+                public: G * &d,
+            },
+            e: Nonce {
+                secret: e,
+                public: G * &e,
+            },
+        }
+    }
+
+    /// RFC 9591, Appendix E.1: FROST(Ed25519, SHA-512), 2-of-3, signers 1 and 3.
+    #[test]
+    fn rfc9591_ed25519_sha512_vector() {
+        let G = &constants::ED25519_BASEPOINT_TABLE;
+        let group_sk = scalar("7b1c33d3f5291d85de664833beb1ad469f7fb6025a0ec78b3a790c6e13a98304");
+        let group_pk = G * &group_sk;
+        assert_eq!(
+            point_hex(&group_pk),
+            "15d21ccd7ee42959562fc8aa63224c8851fb3ec85a3faf66040d380fb9738673"
+        );
+        let msg = hex::decode("74657374").unwrap();
+
+        let p1 = MpcAddr::from(1u32);
+        let p3 = MpcAddr::from(3u32);
+        let x1 = scalar("929dcc590407aae7d388761cddb0c0db6f5627aea8e217f4a033f2ec83d93509");
+        let x3 = scalar("d3cb090a075eb154e82fdb4b3cb507f110040905468bb9c46da8bdea643a9a02");
+
+        // Round one
+        let d1 = nonce_generate::<Ed25519>(
+            &rand_bytes("0fd2e39e111cdc266f6c0f4d0fd45c947761f1f5d3cb583dfcb9bbaf8d4c9fec"),
+            &x1,
+        );
+        let e1 = nonce_generate::<Ed25519>(
+            &rand_bytes("69cd85f631d5f7f2721ed5e40519b1366f340a87c2f6856363dbdcda348a7501"),
+            &x1,
+        );
+        let d3 = nonce_generate::<Ed25519>(
+            &rand_bytes("86d64a260059e495d0fb4fcc17ea3da7452391baa494d4b00321098ed2a0062f"),
+            &x3,
+        );
+        let e3 = nonce_generate::<Ed25519>(
+            &rand_bytes("13e6b25afb2eba51716a9a7d44130c0dbae0004a9ef8d7b5550c8a0e07c61775"),
+            &x3,
+        );
+        assert_eq!(
+            hex::encode(d1.as_bytes()),
+            "812d6104142944d5a55924de6d49940956206909f2acaeedecda2b726e630407"
+        );
+        assert_eq!(
+            hex::encode(e1.as_bytes()),
+            "b1110165fc2334149750b28dd813a39244f315cff14d4e89e6142f262ed83301"
+        );
+        assert_eq!(
+            hex::encode(d3.as_bytes()),
+            "c256de65476204095ebdc01bd11dc10e57b36bc96284595b8215222374f99c0e"
+        );
+        assert_eq!(
+            hex::encode(e3.as_bytes()),
+            "243d71944d929063bc51205714ae3c2218bd3451d0214dfb5aeec2a90c35180d"
+        );
+        let nonce1 = nonce_pair(d1, e1);
+        let nonce3 = nonce_pair(d3, e3);
+        assert_eq!(
+            point_hex(&nonce1.d.public),
+            "b5aa8ab305882a6fc69cbee9327e5a45e54c08af61ae77cb8207be3d2ce13de3"
+        );
+
+        let mut nonce_com_dict: HashMap<MpcAddr, SigningCommitmentPair> = HashMap::new();
+        for (id, nonce) in [(p1, &nonce1), (p3, &nonce3)] {
+            let com = SigningCommitmentPair::new(nonce.d.public, nonce.e.public).unwrap();
+            nonce_com_dict.insert(id, com);
+        }
+
+        // Round two
+        let rho_dict = compute_binding_factors(&group_pk, &nonce_com_dict, &msg);
+        assert_eq!(
+            hex::encode(rho_dict[&p1].as_bytes()),
+            "f2cb9d7dd9beff688da6fcc83fa89046b3479417f47f55600b106760eb3b5603"
+        );
+        assert_eq!(
+            hex::encode(rho_dict[&p3].as_bytes()),
+            "b087686bf35a13f3dc78e780a34b0fe8a77fef1b9938c563f5573d71d8d7890f"
+        );
+
+        let sig_r = agg_nonce_com(&nonce_com_dict, &rho_dict).unwrap();
+        let signers: HashSet<MpcAddr> = [p1, p3].into_iter().collect();
+        let z1 = sign_and_respond(
+            p1,
+            &[x1],
+            &rho_dict,
+            &sig_r,
+            &nonce1,
+            &signers,
+            &HashMap::new(),
+            &group_pk,
+            &SignMode::Ed25519,
+            &msg,
+        )
+        .unwrap();
+        let z3 = sign_and_respond(
+            p3,
+            &[x3],
+            &rho_dict,
+            &sig_r,
+            &nonce3,
+            &signers,
+            &HashMap::new(),
+            &group_pk,
+            &SignMode::Ed25519,
+            &msg,
+        )
+        .unwrap();
+        assert_eq!(
+            hex::encode(z1.as_bytes()),
+            "001719ab5a53ee1a12095cd088fd149702c0720ce5fd2f29dbecf24b7281b603"
+        );
+        assert_eq!(
+            hex::encode(z3.as_bytes()),
+            "bd86125de990acc5e1f13781d8e32c03a9bbd4c53539bbc106058bfd14326007"
+        );
+
+        // Aggregate
+        let sig: Signature = Signature {
+            r: sig_r,
+            s: z1 + z3,
+            msg: msg.clone(),
+            mode: SignMode::Ed25519,
+        };
+        assert_eq!(
+            format!("{}{}", point_hex(&sig.r), hex::encode(sig.s.as_bytes())),
+            "36282629c383bb820a88b71cae937d41f2f2adfcc3d02e55507e2fb9e2dd3cbe\
+             bd9d2b0844e49ae0f3fa935161e1419aab7b47d21a37ebeae1f17d4987b3160b"
+        );
+        verify_signature(&sig, &group_pk).unwrap();
+        verify_solana(&sig, &group_pk).unwrap();
+    }
+
+    /// DeserializeScalar accepts the encoding of $L - 1$ but not of $L$, which
+    /// is $L - 1$ plus one in the least significant byte for all suites.
+    fn order_is_rejected<C: Ciphersuite>(lsb: usize) {
+        let mut bytes = C::serialize_scalar(&-C::scalar_from_u64(1));
+        assert!(C::deserialize_scalar(&bytes).is_some());
+        bytes[lsb] += 1;
+        assert_eq!(C::deserialize_scalar(&bytes), None);
+        assert_eq!(C::deserialize_scalar(&bytes[1..]), None);
+    }
+
+    #[test]
+    fn deserialize_scalar_is_canonical() {
+        order_is_rejected::<Ed25519>(0);
+        order_is_rejected::<Ristretto255>(0);
+        order_is_rejected::<Secp256k1>(31);
+        order_is_rejected::<Ed448>(0);
+    }
+}
@@ -0,0 +1,209 @@
+//! FROST(ristretto255, SHA-512) of RFC 9591, whose challenge is the one of
+//! schnorrkel instead of H2. The group signatures are thus sr25519 signatures,
+//! as Substrate's `sr25519::verify` accepts them.
+
+use curve25519_dalek::{
+    constants,
+    ristretto::{CompressedRistretto, RistrettoPoint},
+    scalar::Scalar,
+};
+use libexception::*;
+use merlin::Transcript;
+use rand::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha512};
+
+use super::{Ciphersuite, Signature};
+
+/// The signing context of Substrate, under which `sr25519::verify` checks.
+pub const SUBSTRATE_CONTEXT: &[u8] = b"substrate";
+
+/// The context of schnorrkel's `signing_context()`, which separates the
+/// signatures of different applications.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SigningContext(pub Vec<u8>);
+
+impl Default for SigningContext {
+    fn default() -> Self {
+        SigningContext(SUBSTRATE_CONTEXT.to_vec())
+    }
+}
+
+/// FROST(ristretto255, SHA-512) of RFC 9591, signing as schnorrkel does.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Ristretto255;
+
+fn hash(tag: &str, m: &[u8]) -> Sha512 {
+    let mut hasher = Sha512::new();
+    hasher.update(Ristretto255::CONTEXT_STRING);
+    hasher.update(tag);
+    hasher.update(m);
+    hasher
+}
+
+impl Ciphersuite for Ristretto255 {
+    type Scalar = Scalar;
+    type Point = RistrettoPoint;
+    type SignMode = SigningContext;
+
+    const CONTEXT_STRING: &'static str = "FROST-RISTRETTO255-SHA512-v1";
+    const SCALAR_LEN: usize = 32;
+
+    fn base_mul(s: &Scalar) -> RistrettoPoint {
+        &constants::RISTRETTO_BASEPOINT_TABLE * s
+    }
+
+    fn scalar_from_u64(x: u64) -> Scalar {
+        Scalar::from(x)
+    }
+
+    fn invert(s: &Scalar) -> Scalar {
+        s.invert()
+    }
+
+    fn random_scalar<R: RngCore + CryptoRng>(rng: &mut R) -> Scalar {
+        Scalar::random(rng)
+    }
+
+    fn serialize_point(p: &RistrettoPoint) -> Vec<u8> {
+        p.compress().to_bytes().to_vec()
+    }
+
+    fn deserialize_point(bytes: &[u8]) -> Option<RistrettoPoint> {
+        if bytes.len() != 32 {
+            return None;
+        }
+        CompressedRistretto::from_slice(bytes).decompress()
+    }
+
+    fn serialize_scalar(s: &Scalar) -> Vec<u8> {
+        s.to_bytes().to_vec()
+    }
+
+    fn deserialize_scalar(bytes: &[u8]) -> Option<Scalar> {
+        Scalar::from_canonical_bytes(bytes.try_into().ok()?)
+    }
+
+    fn scalar_from_le_bytes_mod_order(bytes: &[u8; 32]) -> Scalar {
+        Scalar::from_bytes_mod_order(*bytes)
+    }
+
+    fn h1(m: &[u8]) -> Scalar {
+        Scalar::from_hash(hash("rho", m))
+    }
+
+    /// H2 of RFC 9591, which `challenge()` does not use.
+    fn h2(m: &[u8]) -> Scalar {
+        Scalar::from_hash(hash("chal", m))
+    }
+
+    fn h3(m: &[u8]) -> Scalar {
+        Scalar::from_hash(hash("nonce", m))
+    }
+
+    fn h4(m: &[u8]) -> Vec<u8> {
+        hash("msg", m).finalize().to_vec()
+    }
+
+    fn h5(m: &[u8]) -> Vec<u8> {
+        hash("com", m).finalize().to_vec()
+    }
+
+    fn hdkg(m: &[u8]) -> Scalar {
+        Scalar::from_hash(hash("dkg", m))
+    }
+
+    /// The length-prefixed signing context followed by the message.
+    fn signed_bytes(mode: &SigningContext, msg: &[u8]) -> Vec<u8> {
+        let mut m = (mode.0.len() as u64).to_be_bytes().to_vec();
+        m.extend_from_slice(&mode.0);
+        m.extend_from_slice(msg);
+        m
+    }
+
+    /// The challenge of schnorrkel's `sign_simple()`, drawn from the Merlin
+    /// transcript of the signing context, the message, Y and R.
+    fn challenge(
+        mode: &SigningContext,
+        msg: &[u8],
+        r: &RistrettoPoint,
+        pk: &RistrettoPoint,
+    ) -> Scalar {
+        let mut t = Transcript::new(b"SigningContext");
+        t.append_message(b"", &mode.0);
+        t.append_message(b"sign-bytes", msg);
+        t.append_message(b"proto-name", b"Schnorr-sig");
+        t.append_message(b"sign:pk", pk.compress().as_bytes());
+        t.append_message(b"sign:R", r.compress().as_bytes());
+
+        let mut buf = [0u8; 64];
+        t.challenge_bytes(b"sign:c", &mut buf);
+        Scalar::from_bytes_mod_order_wide(&buf)
+    }
+
+    fn cross_check(sig: &Signature<Self>, pk: &RistrettoPoint) -> Outcome<()> {
+        verify_schnorrkel(sig, pk)
+    }
+}
+
+/// The 64-byte encoding of schnorrkel, i.e. R || s with the top bit of s set
+/// to tell it from the Ed25519-like encoding of old versions.
+pub fn sr25519_bytes(sig: &Signature<Ristretto255>) -> [u8; 64] {
+    let mut bytes = [0u8; 64];
+    bytes[..32].copy_from_slice(sig.r.compress().as_bytes());
+    bytes[32..].copy_from_slice(sig.s.as_bytes());
+    bytes[63] |= 128;
+    bytes
+}
+
+/// Check the signature with schnorrkel, under its signing context.
+pub fn verify_schnorrkel(sig: &Signature<Ristretto255>, pk: &RistrettoPoint) -> Outcome<()> {
+    let pk = schnorrkel::PublicKey::from_bytes(pk.compress().as_bytes()).catch_()?;
+    let lib_sig = schnorrkel::Signature::from_bytes(&sr25519_bytes(sig)).catch_()?;
+    pk.verify_simple(&sig.mode.0, &sig.msg, &lib_sig).catch_()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::biz_algo::testkit::keygen_and_sign;
+    use crate::frost::verify_signature;
+
+    #[test]
+    fn schnorrkel_signature_verifies() {
+        let keypair = schnorrkel::MiniSecretKey::from_bytes(&[7u8; 32])
+            .unwrap()
+            .expand_to_keypair(schnorrkel::ExpansionMode::Ed25519);
+        let lib_sig = keypair.sign_simple(SUBSTRATE_CONTEXT, b"hello");
+
+        let pk = CompressedRistretto(keypair.public.to_bytes())
+            .decompress()
+            .unwrap();
+        let bytes = lib_sig.to_bytes();
+        let mut s_bytes: [u8; 32] = bytes[32..].try_into().unwrap();
+        s_bytes[31] &= 127;
+        let mut sig: Signature<Ristretto255> = Signature {
+            r: CompressedRistretto::from_slice(&bytes[..32])
+                .decompress()
+                .unwrap(),
+            s: Scalar::from_canonical_bytes(s_bytes).unwrap(),
+            msg: b"hello".to_vec(),
+            mode: SigningContext::default(),
+        };
+        verify_signature(&sig, &pk).unwrap();
+        assert_eq!(sr25519_bytes(&sig), bytes);
+
+        sig.mode = SigningContext(b"polkadot".to_vec());
+        assert!(verify_signature(&sig, &pk).is_err());
+        assert!(verify_schnorrkel(&sig, &pk).is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn threshold_signature_verifies_with_schnorrkel() {
+        let mode = SigningContext::default();
+        let (pk, sig) = keygen_and_sign::<Ristretto255>(b"threshold sr25519", &mode).await;
+        verify_signature(&sig, &pk).unwrap();
+        verify_schnorrkel(&sig, &pk).unwrap();
+    }
+}
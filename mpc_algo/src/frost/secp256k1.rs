@@ -0,0 +1,590 @@
+//! FROST(secp256k1, SHA-256) of RFC 9591, whose challenge and encodings are
+//! those of BIP-340, so that the group signatures are Taproot signatures.
+//!
+//! BIP-340 only knows the x coordinate of the key and of R, and takes the
+//! points with an even y for them. Signers thus negate their shares whenever
+//! the key has an odd y, and their nonces whenever R does.
+
+use std::{
+    iter::Sum,
+    ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign},
+};
+
+use k256::{
+    elliptic_curve::{
+        bigint::U512,
+        generic_array::GenericArray,
+        group::GroupEncoding,
+        hash2curve::{hash_to_field, ExpandMsgXmd},
+        ops::Reduce,
+        point::AffineCoordinates,
+        PrimeField,
+    },
+    AffinePoint, FieldBytes, ProjectivePoint, Scalar, U256,
+};
+use libexception::*;
+use rand::{CryptoRng, RngCore};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sha2::{Digest, Sha256};
+use zeroize::Zeroize;
+
+use super::{Ciphersuite, Signature};
+
+/// A point of secp256k1, which encodes in the SEC1 compressed form.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Secp256k1Point(pub ProjectivePoint);
+
+impl Add for Secp256k1Point {
+    type Output = Secp256k1Point;
+    fn add(self, rhs: Secp256k1Point) -> Secp256k1Point {
+        Secp256k1Point(self.0 + rhs.0)
+    }
+}
+
+impl Sub for Secp256k1Point {
+    type Output = Secp256k1Point;
+    fn sub(self, rhs: Secp256k1Point) -> Secp256k1Point {
+        Secp256k1Point(self.0 - rhs.0)
+    }
+}
+
+impl Neg for Secp256k1Point {
+    type Output = Secp256k1Point;
+    fn neg(self) -> Secp256k1Point {
+        Secp256k1Point(-self.0)
+    }
+}
+
+impl AddAssign for Secp256k1Point {
+    fn add_assign(&mut self, rhs: Secp256k1Point) {
+        self.0 += rhs.0;
+    }
+}
+
+impl SubAssign for Secp256k1Point {
+    fn sub_assign(&mut self, rhs: Secp256k1Point) {
+        self.0 -= rhs.0;
+    }
+}
+
+impl Mul<Scalar> for Secp256k1Point {
+    type Output = Secp256k1Point;
+    fn mul(self, rhs: Scalar) -> Secp256k1Point {
+        Secp256k1Point(self.0 * rhs)
+    }
+}
+
+impl Mul<Secp256k1Point> for Scalar {
+    type Output = Secp256k1Point;
+    fn mul(self, rhs: Secp256k1Point) -> Secp256k1Point {
+        Secp256k1Point(rhs.0 * self)
+    }
+}
+
+impl Sum for Secp256k1Point {
+    fn sum<I: Iterator<Item = Secp256k1Point>>(iter: I) -> Secp256k1Point {
+        Secp256k1Point(iter.map(|p| p.0).sum())
+    }
+}
+
+impl Zeroize for Secp256k1Point {
+    fn zeroize(&mut self) {
+        self.0 = ProjectivePoint::IDENTITY;
+    }
+}
+
+impl Serialize for Secp256k1Point {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.to_affine().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Secp256k1Point {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let p = AffinePoint::deserialize(deserializer)?;
+        Ok(Secp256k1Point(p.into()))
+    }
+}
+
+/// FROST(secp256k1, SHA-256) of RFC 9591, signing as BIP-340 does.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Secp256k1;
+
+/// hash_to_field() of RFC 9380 with expand_message_xmd and SHA-256, as
+/// RFC 9591 hashes to scalars for secp256k1.
+fn hash_to_scalar(tag: &str, m: &[u8]) -> Scalar {
+    let dst = [Secp256k1::CONTEXT_STRING.as_bytes(), tag.as_bytes()].concat();
+    let mut out = [Scalar::ZERO];
+    hash_to_field::<ExpandMsgXmd<k256::sha2::Sha256>, Scalar>(&[m], &[&dst], &mut out)
+        .expect("DST is shorter than 256 bytes");
+    out[0]
+}
+
+fn hash(tag: &str, m: &[u8]) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    hasher.update(Secp256k1::CONTEXT_STRING);
+    hasher.update(tag);
+    hasher.update(m);
+    hasher.finalize().to_vec()
+}
+
+/// hash_tag(m) of BIP-340, i.e. SHA256(SHA256(tag) || SHA256(tag) || m).
+pub fn tagged_hash(tag: &str, m: &[u8]) -> [u8; 32] {
+    let tag_hash = Sha256::digest(tag.as_bytes());
+    let mut hasher = Sha256::new();
+    hasher.update(tag_hash);
+    hasher.update(tag_hash);
+    hasher.update(m);
+    hasher.finalize().into()
+}
+
+fn reduce(bytes: &[u8; 32]) -> Scalar {
+    <Scalar as Reduce<U256>>::reduce_bytes(FieldBytes::from_slice(bytes))
+}
+
+/// The 32-byte x coordinate of `p`, as BIP-340 encodes keys and R.
+pub fn x_only(p: &Secp256k1Point) -> [u8; 32] {
+    p.0.to_affine().x().into()
+}
+
+/// Lift an x-only key of BIP-340 to the point with an even y.
+pub fn lift_x(x: &[u8; 32]) -> Outcome<Secp256k1Point> {
+    let mut bytes = [0x02u8; 33];
+    bytes[1..].copy_from_slice(x);
+    Secp256k1::deserialize_point(&bytes).ifnone("InvalidPoint", "Not the x of a curve point")
+}
+
+impl Ciphersuite for Secp256k1 {
+    type Scalar = Scalar;
+    type Point = Secp256k1Point;
+    type SignMode = ();
+
+    const CONTEXT_STRING: &'static str = "FROST-secp256k1-SHA256-TR-v1";
+    const SCALAR_LEN: usize = 32;
+
+    fn base_mul(s: &Scalar) -> Secp256k1Point {
+        Secp256k1Point(ProjectivePoint::GENERATOR * s)
+    }
+
+    fn scalar_from_u64(x: u64) -> Scalar {
+        Scalar::from(x)
+    }
+
+    fn invert(s: &Scalar) -> Scalar {
+        s.invert().unwrap_or(Scalar::ZERO)
+    }
+
+    fn random_scalar<R: RngCore + CryptoRng>(rng: &mut R) -> Scalar {
+        let mut bytes = [0u8; 64];
+        rng.fill_bytes(&mut bytes);
+        let s = <Scalar as Reduce<U512>>::reduce_bytes(GenericArray::from_slice(&bytes));
+        bytes.zeroize();
+        s
+    }
+
+    fn serialize_point(p: &Secp256k1Point) -> Vec<u8> {
+        p.0.to_bytes().to_vec()
+    }
+
+    fn deserialize_point(bytes: &[u8]) -> Option<Secp256k1Point> {
+        if bytes.len() != 33 {
+            return None;
+        }
+        let p = ProjectivePoint::from_bytes(GenericArray::from_slice(bytes));
+        Option::from(p).map(Secp256k1Point)
+    }
+
+    fn serialize_scalar(s: &Scalar) -> Vec<u8> {
+        s.to_bytes().to_vec()
+    }
+
+    fn deserialize_scalar(bytes: &[u8]) -> Option<Scalar> {
+        let bytes: [u8; 32] = bytes.try_into().ok()?;
+        Scalar::from_repr(bytes.into()).into()
+    }
+
+    fn scalar_from_le_bytes_mod_order(bytes: &[u8; 32]) -> Scalar {
+        let mut be = *bytes;
+        be.reverse();
+        reduce(&be)
+    }
+
+    fn h1(m: &[u8]) -> Scalar {
+        hash_to_scalar("rho", m)
+    }
+
+    /// The challenge hash of BIP-340, in place of H2 of RFC 9591.
+    fn h2(m: &[u8]) -> Scalar {
+        reduce(&tagged_hash("BIP0340/challenge", m))
+    }
+
+    fn h3(m: &[u8]) -> Scalar {
+        hash_to_scalar("nonce", m)
+    }
+
+    fn h4(m: &[u8]) -> Vec<u8> {
+        hash("msg", m)
+    }
+
+    fn h5(m: &[u8]) -> Vec<u8> {
+        hash("com", m)
+    }
+
+    fn hdkg(m: &[u8]) -> Scalar {
+        hash_to_scalar("dkg", m)
+    }
+
+    fn has_odd_y(p: &Secp256k1Point) -> bool {
+        p.0.to_affine().y_is_odd().into()
+    }
+
+    /// H2(x(R) || x(Y) || m) of BIP-340.
+    fn challenge(_mode: &(), msg: &[u8], r: &Secp256k1Point, pk: &Secp256k1Point) -> Scalar {
+        let mut m = x_only(r).to_vec();
+        m.extend_from_slice(&x_only(pk));
+        m.extend_from_slice(msg);
+        Self::h2(&m)
+    }
+
+    fn cross_check(sig: &Signature<Self>, pk: &Secp256k1Point) -> Outcome<()> {
+        verify_bip340(sig, pk)
+    }
+}
+
+/// The 64-byte encoding of BIP-340, i.e. x(R) || s.
+pub fn bip340_bytes(sig: &Signature<Secp256k1>) -> [u8; 64] {
+    let mut bytes = [0u8; 64];
+    bytes[..32].copy_from_slice(&x_only(&sig.r));
+    bytes[32..].copy_from_slice(&sig.s.to_bytes());
+    bytes
+}
+
+/// Check the signature with the BIP-340 verifier of k256, under the x-only `pk`.
+pub fn verify_bip340(sig: &Signature<Secp256k1>, pk: &Secp256k1Point) -> Outcome<()> {
+    let vk = k256::schnorr::VerifyingKey::from_bytes(&x_only(pk)).catch_()?;
+    let lib_sig = k256::schnorr::Signature::try_from(&bip340_bytes(sig)[..]).catch_()?;
+    vk.verify_raw(&sig.msg, &lib_sig).catch_()?;
+    Ok(())
+}
+
+/// The taproot tweak of BIP-341, i.e. H_TapTweak(x(P) || merkle_root), where
+/// the merkle root is left out for keys without a script path.
+pub fn taproot_tweak(internal_key: &Secp256k1Point, merkle_root: Option<&[u8; 32]>) -> Scalar {
+    let mut m = x_only(internal_key).to_vec();
+    if let Some(merkle_root) = merkle_root {
+        m.extend_from_slice(merkle_root);
+    }
+    reduce(&tagged_hash("TapTweak", &m))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use mpc_spec::{FrostMode, MpcAddr, SessionId};
+
+    use super::*;
+    use crate::biz_algo::testkit::*;
+    use crate::biz_algo::{
+        algo_sign, chain_code_of, DerivationScheme, EpochMsg, KeyStore, Taproot,
+    };
+    use crate::frost::{
+        agg_nonce_com, gen_rho_dict, verify_signature, Ciphersuite, SigningCommitmentPair,
+    };
+
+    fn hex32(s: &str) -> [u8; 32] {
+        hex::decode(s).unwrap().try_into().unwrap()
+    }
+
+    /// Verify (public key, message, signature) of BIP-340 as ours, which fails
+    /// already at parsing for some of the invalid ones.
+    fn bip340_verify(pk: &str, msg: &str, sig: &str) -> bool {
+        let sig_bytes = hex::decode(sig).unwrap();
+        let parsed = (|| -> Outcome<(Secp256k1Point, Signature<Secp256k1>)> {
+            let pk = lift_x(&hex32(pk)).catch_()?;
+            let r = lift_x(&sig_bytes[..32].try_into().unwrap()).catch_()?;
+            let s: Option<Scalar> =
+                Scalar::from_repr(*FieldBytes::from_slice(&sig_bytes[32..])).into();
+            let sig = Signature {
+                r,
+                s: s.ifnone_()?,
+                msg: hex::decode(msg).unwrap(),
+                mode: (),
+            };
+            Ok((pk, sig))
+        })();
+        let (pk, sig) = match parsed {
+            Ok(v) => v,
+            Err(_) => return false,
+        };
+        let valid = verify_signature(&sig, &pk).is_ok();
+        assert_eq!(valid, verify_bip340(&sig, &pk).is_ok());
+        if valid {
+            assert_eq!(bip340_bytes(&sig).to_vec(), sig_bytes);
+        }
+        valid
+    }
+
+    /// test-vectors.csv of BIP-340: (secret key, public key, message, signature, valid)
+    const BIP340_VECTORS: &[(&str, &str, &str, &str, bool)] = &[
+        (
+            "0000000000000000000000000000000000000000000000000000000000000003",
+            "F9308A019258C31049344F85F89D5229B531C845836F99B08601F113BCE036F9",
+            "0000000000000000000000000000000000000000000000000000000000000000",
+            "E907831F80848D1069A5371B402410364BDF1C5F8307B0084C55F1CE2DCA821525F66A4A85EA8B71E482A74F382D2CE5EBEEE8FDB2172F477DF4900D310536C0",
+            true,
+        ),
+        (
+            "B7E151628AED2A6ABF7158809CF4F3C762E7160F38B4DA56A784D9045190CFEF",
+            "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+            "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+            "6896BD60EEAE296DB48A229FF71DFE071BDE413E6D43F917DC8DCF8C78DE33418906D11AC976ABCCB20B091292BFF4EA897EFCB639EA871CFA95F6DE339E4B0A",
+            true,
+        ),
+        (
+            "C90FDAA22168C234C4C6628B80DC1CD129024E088A67CC74020BBEA63B14E5C9",
+            "DD308AFEC5777E13121FA72B9CC1B7CC0139715309B086C960E18FD969774EB8",
+            "7E2D58D8B3BCDF1ABADEC7829054F90DDA9805AAB56C77333024B9D0A508B75C",
+            "5831AAEED7B44BB74E5EAB94BA9D4294C49BCF2A60728D8B4C200F50DD313C1BAB745879A5AD954A72C45A91C3A51D3C7ADEA98D82F8481E0E1E03674A6F3FB7",
+            true,
+        ),
+        (
+            "0B432B2677937381AEF05BB02A66ECD012773062CF3FA2549E44F58ED2401710",
+            "25D1DFF95105F5253C4022F628A996AD3A0D95FBF21D468A1B33F8C160D8F517",
+            "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF",
+            "7EB0509757E246F19449885651611CB965ECC1A187DD51B64FDA1EDC9637D5EC97582B9CB13DB3933705B32BA982AF5AF25FD78881EBB32771FC5922EFC66EA3",
+            true,
+        ),
+        (
+            "",
+            "D69C3509BB99E412E68B0FE8544E72837DFA30746D8BE2AA65975F29D22DC7B9",
+            "4DF3C3F68FCC83B27E9D42C90431A72499F17875C81A599B566C9889B9696703",
+            "00000000000000000000003B78CE563F89A0ED9414F5AA28AD0D96D6795F9C6376AFB1548AF603B3EB45C9F8207DEE1060CB71C04E80F593060B07D28308D7F4",
+            true,
+        ),
+        // public key not on the curve
+        (
+            "",
+            "EEFDEA4CDB677750A420FEE807EACF21EB9898AE79B9768766E4FAA04A2D4A34",
+            "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+            "6CFF5C3BA86C69EA4B7376F31A9BCB4F74C1976089B2D9963DA2E5543E17776969E89B4C5564D00349106B8497785DD7D1D713A8AE82B32FA79D5F7FC407D39B",
+            false,
+        ),
+        // has_even_y(R) is false
+        (
+            "",
+            "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+            "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+            "FFF97BD5755EEEA420453A14355235D382F6472F8568A18B2F057A14602975563CC27944640AC607CD107AE10923D9EF7A73C643E166BE5EBEAFA34B1AC553E2",
+            false,
+        ),
+        // negated message
+        (
+            "",
+            "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+            "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+            "1FA62E331EDBC21C394792D2AB1100A7B432B013DF3F6FF4F99FCB33E0E1515F28890B3EDB6E7189B630448B515CE4F8622A954CFE545735AAEA5134FCCDB2BD",
+            false,
+        ),
+        // negated s value
+        (
+            "",
+            "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+            "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+            "6CFF5C3BA86C69EA4B7376F31A9BCB4F74C1976089B2D9963DA2E5543E177769961764B3AA9B2FFCB6EF947B6887A226E8D7C93E00C5ED0C1834FF0D0C2E6DA6",
+            false,
+        ),
+        // sG - eP is infinite, with x(inf) as 0
+        (
+            "",
+            "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+            "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+            "0000000000000000000000000000000000000000000000000000000000000000123DDA8328AF9C23A94C1FEECFD123BA4FB73476F0D594DCB65C6425BD186051",
+            false,
+        ),
+        // sG - eP is infinite, with x(inf) as 1
+        (
+            "",
+            "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+            "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+            "00000000000000000000000000000000000000000000000000000000000000017615FBAF5AE28864013C099742DEADB4DBA87F11AC6754F93780D5A1837CF197",
+            false,
+        ),
+        // sig[0:32] is not an X coordinate on the curve
+        (
+            "",
+            "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+            "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+            "4A298DACAE57395A15D0795DDBFD1DCB564DA82B0F269BC70A74F8220429BA1D69E89B4C5564D00349106B8497785DD7D1D713A8AE82B32FA79D5F7FC407D39B",
+            false,
+        ),
+        // sig[0:32] is equal to field size
+        (
+            "",
+            "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+            "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+            "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEFFFFFC2F69E89B4C5564D00349106B8497785DD7D1D713A8AE82B32FA79D5F7FC407D39B",
+            false,
+        ),
+        // sig[32:64] is equal to curve order
+        (
+            "",
+            "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+            "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+            "6CFF5C3BA86C69EA4B7376F31A9BCB4F74C1976089B2D9963DA2E5543E177769FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEBAAEDCE6AF48A03BBFD25E8CD0364141",
+            false,
+        ),
+        // public key is not a valid X coordinate because it exceeds the field size
+        (
+            "",
+            "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEFFFFFC30",
+            "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+            "6CFF5C3BA86C69EA4B7376F31A9BCB4F74C1976089B2D9963DA2E5543E17776969E89B4C5564D00349106B8497785DD7D1D713A8AE82B32FA79D5F7FC407D39B",
+            false,
+        ),
+        // messages of other lengths than 32 bytes
+        (
+            "0340034003400340034003400340034003400340034003400340034003400340",
+            "778CAA53B4393AC467774D09497A87224BF9FAB6F6E68B23086497324D6FD117",
+            "",
+            "71535DB165ECD9FBBC046E5FFAEA61186BB6AD436732FCCC25291A55895464CF6069CE26BF03466228F19A3A62DB8A649F2D560FAC652827D1AF0574E427AB63",
+            true,
+        ),
+        (
+            "0340034003400340034003400340034003400340034003400340034003400340",
+            "778CAA53B4393AC467774D09497A87224BF9FAB6F6E68B23086497324D6FD117",
+            "11",
+            "08A20A0AFEF64124649232E0693C583AB1B9934AE63B4C3511F3AE1134C6A303EA3173BFEA6683BD101FA5AA5DBC1996FE7CACFC5A577D33EC14564CEC2BACBF",
+            true,
+        ),
+        (
+            "0340034003400340034003400340034003400340034003400340034003400340",
+            "778CAA53B4393AC467774D09497A87224BF9FAB6F6E68B23086497324D6FD117",
+            "0102030405060708090A0B0C0D0E0F1011",
+            "5130F39A4059B43BC7CAC09A19ECE52B5D8699D1A71E3C52DA9AFDB6B50AC370C4A482B77BF960F8681540E25B6771ECE1E5A37FD80E5A51897C5566A97EA5A5",
+            true,
+        ),
+    ];
+
+    #[test]
+    fn bip340_vectors() {
+        for (i, (sk, pk, msg, sig, valid)) in BIP340_VECTORS.iter().enumerate() {
+            if !sk.is_empty() {
+                let sk: Option<Scalar> = Scalar::from_repr(hex32(sk).into()).into();
+                let pk_of_sk = Secp256k1::base_mul(&sk.unwrap());
+                assert_eq!(hex::encode_upper(x_only(&pk_of_sk)), *pk, "vector {}", i);
+            }
+            assert_eq!(bip340_verify(pk, msg, sig), *valid, "vector {}", i);
+        }
+    }
+
+    #[test]
+    fn bip341_taproot_tweak() {
+        // (internal key, merkle root, tweak, output key) from wallet-test-vectors.json
+        // of BIP-341
+        let cases = [
+            (
+                "d6889cb081036e0faefa3a35157ad71086b123b2b144b649798b494c300a961d",
+                None,
+                "b86e7be8f39bab32a6f2c0443abbc210f0edac0e2c53d501b36b64437d9c6c70",
+                "53a1f6e454df1aa2776a2814a721372d6258050de330b3c6d10ee8f4e0dda343",
+            ),
+            (
+                "187791b6f712a8ea41c8ecdd0ee77fab3e85263b37e1ec18a3651926b3a6cf27",
+                Some("5b75adecf53548f3ec6ad7d78383bf84cc57b55a3127c72b9a2481752dd88b21"),
+                "cbd8679ba636c1110ea247542cfbd964131a6be84f873f7f3b62a777528ed001",
+                "147c9c57132f6e7ecddba9800bb0c4449251c92a1e60371ee77557b6620f3ea3",
+            ),
+        ];
+        for (internal_key, merkle_root, tweak, output_key) in cases {
+            let p = lift_x(&hex32(internal_key)).unwrap();
+            let merkle_root = merkle_root.map(hex32);
+            let t = taproot_tweak(&p, merkle_root.as_ref());
+            assert_eq!(hex::encode(t.to_bytes()), tweak);
+            let q = p + Secp256k1::base_mul(&t);
+            assert_eq!(hex::encode(x_only(&q)), output_key);
+        }
+    }
+
+    #[test]
+    fn odd_points_negate() {
+        let p = lift_x(&hex32(
+            "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+        ))
+        .unwrap();
+        assert!(!Secp256k1::has_odd_y(&p));
+        assert!(Secp256k1::has_odd_y(&-p));
+        assert_eq!(x_only(&p), x_only(&-p));
+        let bytes = Secp256k1::serialize_point(&-p);
+        assert_eq!(Secp256k1::deserialize_point(&bytes), Some(-p));
+    }
+
+    /// R of a session as the signers aggregate it, before they negate it for
+    /// an odd y, from their broadcast nonce commitments.
+    fn aggregate_r(
+        messenger: &MemMessenger,
+        sid: SessionId,
+        signers: &[MpcAddr],
+        child_pk: &Secp256k1Point,
+        msg: &[u8],
+    ) -> Secp256k1Point {
+        let topic = format!("{}/nonce_com", sid);
+        let mut com_dict: HashMap<MpcAddr, SigningCommitmentPair<Secp256k1>> = HashMap::new();
+        for j in signers.iter() {
+            let mut com: EpochMsg<Vec<SigningCommitmentPair<Secp256k1>>> =
+                messenger.peek(&topic, *j, MpcAddr::bcast_id()).unwrap();
+            com_dict.insert(*j, com.body.remove(0));
+        }
+        let signed = Secp256k1::signed_bytes(&(), msg);
+        let rho_dict = gen_rho_dict(FrostMode::Rfc9591, sid, child_pk, &signed, &com_dict);
+        agg_nonce_com(&com_dict, &rho_dict).unwrap()
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn taproot_signature_of_odd_key_and_odd_r() {
+        let messenger = MemMessenger::default();
+        let arch = key_arch(&[(1, 2, 3)]);
+        let keystores: Vec<KeyStore<Secp256k1>> = loop {
+            let keystores =
+                keygen::<Secp256k1>(&messenger, &arch, &paired(&arch), FrostMode::Rfc9591).await;
+            if Secp256k1::has_odd_y(&keystores[0].pk().unwrap()) {
+                break keystores;
+            }
+        };
+        let pk = keystores[0].pk().unwrap();
+        let chain_code = chain_code_of::<Secp256k1>(&keystores[0]).unwrap();
+        let scheme = Taproot {
+            merkle_root: Some([7u8; 32]),
+        };
+        let (_, output_key) = scheme.derive("", &pk, &chain_code).unwrap();
+        let even_key = match Secp256k1::has_odd_y(&output_key) {
+            true => -output_key,
+            false => output_key,
+        };
+
+        // Sign until the signers have to negate their nonces as well.
+        let ses_arch = ses_arch(&[(1, &[1, 3])]);
+        let signers = [MpcAddr::new(1, 1), MpcAddr::new(1, 3)];
+        let mut odd_r = false;
+        for i in 0..64u8 {
+            let msg = [i; 32];
+            let sid = SessionId::random();
+            let tasks = [&keystores[0], &keystores[2]].map(|keystore| {
+                algo_sign::<Secp256k1>(&messenger, sid, &ses_arch, "", &msg, &(), &scheme, keystore)
+            });
+            let [sig_1, sig_3] = tasks;
+            let (sig_1, sig_3) = tokio::join!(sig_1, sig_3);
+            let r = aggregate_r(&messenger, sid, &signers, &even_key, &msg);
+            for sig in [sig_1.unwrap(), sig_3.unwrap()] {
+                verify_signature(&sig, &output_key).unwrap();
+                verify_bip340(&sig, &output_key).unwrap();
+                assert_eq!(x_only(&sig.r), x_only(&r));
+            }
+            if Secp256k1::has_odd_y(&r) {
+                odd_r = true;
+                break;
+            }
+        }
+        assert!(odd_r);
+    }
+}
@@ -1,20 +1,30 @@
 use std::collections::{HashMap, HashSet};
 
-use curve25519_dalek::{constants, edwards::EdwardsPoint, scalar::Scalar, traits::Identity};
+use curve25519_dalek::{edwards::EdwardsPoint, scalar::Scalar};
 use libexception::*;
-use mpc_spec::MpcAddr;
+use mpc_spec::{FrostMode, MpcAddr, SessionId};
 use rand::{CryptoRng, RngCore};
 use sha2::{Digest, Sha256, Sha512};
 
-use super::{Signature, SigningCommitmentPair, SigningNoncePair};
+use super::{
+    eval_sub_xi_coms, rfc9591, share_points, weight_of, Ciphersuite, SignMode, Signature,
+    SigningCommitmentPair, SigningNoncePair,
+};
 
 /// preprocess is performed by each participant; their commitments are published
 /// and stored in an external location for later use in signing, while their
 /// signing nonces are stored locally.
-pub fn sign_preprocess<R: RngCore + CryptoRng>(
+/// In RFC 9591 mode the nonces are additionally bound to the signing share `x_i`.
+pub fn sign_preprocess<C: Ciphersuite, R: RngCore + CryptoRng>(
+    mode: FrostMode,
+    x_i: &C::Scalar,
     rng: &mut R,
-) -> Outcome<(SigningCommitmentPair, SigningNoncePair)> {
-    let nonce = SigningNoncePair::new(rng).catch_()?;
+) -> Outcome<(SigningCommitmentPair<C>, SigningNoncePair<C>)> {
+    if mode == FrostMode::Rfc9591 {
+        return rfc9591::commit(x_i, rng);
+    }
+
+    let nonce = SigningNoncePair::<C>::new(rng).catch_()?;
     let com = SigningCommitmentPair::new(nonce.d.public, nonce.e.public).catch_()?;
     Ok((com, nonce))
 }
@@ -23,16 +33,20 @@ pub fn sign_preprocess<R: RngCore + CryptoRng>(
 /// operation; these responses are then aggregated into the final FROST
 /// signature by the signature aggregator performing the aggregate function
 /// with each response.
-pub fn sign_and_respond(
+///
+/// `x_i` holds the sub-shares of `my_id`, one unless it is weighted.
+pub fn sign_and_respond<C: Ciphersuite>(
     my_id: MpcAddr,
-    x_i: &Scalar,
-    rho_dict: &HashMap<MpcAddr, Scalar>,
-    sig_r: &EdwardsPoint,
-    nonce: &SigningNoncePair, // .len() == cached_com_count
+    x_i: &[C::Scalar],
+    rho_dict: &HashMap<MpcAddr, C::Scalar>,
+    sig_r: &C::Point,
+    nonce: &SigningNoncePair<C>, // .len() == cached_com_count
     signers: &HashSet<MpcAddr>,
-    main_pk: &EdwardsPoint,
+    weights: &HashMap<MpcAddr, u16>,
+    main_pk: &C::Point,
+    mode: &C::SignMode,
     msg: &[u8],
-) -> Outcome<Scalar> {
+) -> Outcome<C::Scalar> {
     let my_rho_i = rho_dict.get(&my_id).ifnone_()?;
 
     // R = k * G = sum(D_l + E_l * rho_l)
@@ -41,13 +55,15 @@ pub fn sign_and_respond(
         my_id,
         signers.iter().map(|x| x.to_string()).collect::<Vec<_>>()
     );
-    let lambda_i = lagrange_lambda(my_id, &signers).catch_()?;
+    let lambda_i = weighted_lambdas::<C>(my_id, &signers, weights).catch_()?;
+    assert_throw!(lambda_i.len() == x_i.len(), "Wrong number of sub-shares");
+    let s_i: C::Scalar = lambda_i.iter().zip(x_i.iter()).map(|(l, x)| *l * *x).sum();
 
     // c= H_2(R, Y, m)
-    let c = generate_challenge(msg, &sig_r, main_pk);
+    let c = generate_challenge::<C>(mode, msg, &sig_r, main_pk);
 
     // z_i = d_i + (e_i * rho_i) + lambda_i * s_i * c
-    let response = nonce.d.secret + (nonce.e.secret * my_rho_i) + (lambda_i * x_i * c);
+    let response = nonce.d.secret + (nonce.e.secret * *my_rho_i) + (s_i * c);
 
     Ok(response /* z_i */)
 }
@@ -56,54 +72,126 @@ pub fn sign_and_respond(
 /// for performing Lagrange interpolation, which underpins threshold secret
 /// sharing schemes based on Shamir secret sharing.
 pub fn lagrange_lambda(id: MpcAddr, signers: &HashSet<MpcAddr>) -> Outcome<Scalar> {
+    lagrange_lambda_at(id, signers, &Scalar::zero())
+}
+
+/// generates the lagrange coefficient of the ith participant for interpolating
+/// the polynomial at `x`, rather than at zero.
+pub fn lagrange_lambda_at(id: MpcAddr, signers: &HashSet<MpcAddr>, x: &Scalar) -> Outcome<Scalar> {
     let mut num = Scalar::one();
     let mut den = Scalar::one();
     for j in signers {
         if *j == id {
             continue;
         }
-        num *= Scalar::from(j.member_id());
-        den *= Scalar::from(j.member_id()) - Scalar::from(id.member_id());
+        num *= x - Scalar::from(j.member_id());
+        den *= Scalar::from(id.member_id()) - Scalar::from(j.member_id());
     }
     assert_throw!(den != Scalar::zero(), "Duplicate shares provided");
 
     Ok(num * den.invert())
 }
 
-// to be reviewed again? For H(m, R) instead of H(R, Y, m)???
-/// generates the challenge value H(m, R) used for both signing and verification.
-/// ed25519_ph hashes the message first, and derives the challenge as H(H(m), R),
-/// this would be a better optimization but incompatibility with other
-/// implementations may be undesirable.
-pub fn generate_challenge(msg: &[u8], com: &EdwardsPoint, pk: &EdwardsPoint) -> Scalar {
-    let mut ha = Sha512::new();
-    ha.update(com.compress().to_bytes());
-    ha.update(pk.compress().to_bytes());
-    ha.update(msg);
-    Scalar::from_hash(ha)
-}
-
-pub fn agg_nonce_com(
-    com_dict: &HashMap<MpcAddr, SigningCommitmentPair>,
-    bindings: &HashMap<MpcAddr, Scalar>,
-) -> Outcome<EdwardsPoint> {
-    let mut group_com = EdwardsPoint::identity();
+/// Lagrange coefficients at zero of each sub-share of `id` among those of
+/// `signers`, where a member of weight w counts as w signers.
+pub fn weighted_lambdas<C: Ciphersuite>(
+    id: MpcAddr,
+    signers: &HashSet<MpcAddr>,
+    weights: &HashMap<MpcAddr, u16>,
+) -> Outcome<Vec<C::Scalar>> {
+    assert_throw!(signers.contains(&id), format!("{} is not a signer", id));
+    let mut all_points: Vec<C::Scalar> = Vec::new();
+    for j in signers.iter() {
+        all_points.extend(share_points::<C>(*j, weight_of(j, weights)));
+    }
+    let mut lambdas: Vec<C::Scalar> = Vec::new();
+    for x_i in share_points::<C>(id, weight_of(&id, weights)).iter() {
+        let mut num = C::scalar_from_u64(1);
+        let mut den = C::scalar_from_u64(1);
+        for x_j in all_points.iter().filter(|x_j| *x_j != x_i) {
+            num *= -*x_j;
+            den *= *x_i - *x_j;
+        }
+        assert_throw!(den != C::Scalar::default(), "Duplicate shares provided");
+        lambdas.push(num * C::invert(&den));
+    }
+    Ok(lambdas)
+}
+
+/// Evaluate $\sum_k \lambda_{i,k} x_{i,k} G$ of `id` among `signers`, i.e. the
+/// part of the group key it signs for, without knowing its sub-shares.
+pub fn eval_signer_com<C: Ciphersuite>(
+    id: MpcAddr,
+    signers: &HashSet<MpcAddr>,
+    weights: &HashMap<MpcAddr, u16>,
+    vss_com_dict: &HashMap<MpcAddr, Vec<C::Point>>,
+) -> Outcome<C::Point> {
+    let lambdas = weighted_lambdas::<C>(id, signers, weights).catch_()?;
+    let coms = eval_sub_xi_coms::<C>(id, weight_of(&id, weights), vss_com_dict);
+    let mut res = C::Point::default();
+    for (l, com) in lambdas.iter().zip(coms.iter()) {
+        res += *com * *l;
+    }
+    Ok(res)
+}
+
+/// generates the challenge value of the signature, used for both signing and
+/// verification. See `Ciphersuite::challenge()`.
+pub fn generate_challenge<C: Ciphersuite>(
+    mode: &C::SignMode,
+    msg: &[u8],
+    com: &C::Point,
+    pk: &C::Point,
+) -> C::Scalar {
+    C::challenge(mode, msg, com, pk)
+}
+
+pub fn agg_nonce_com<C: Ciphersuite>(
+    com_dict: &HashMap<MpcAddr, SigningCommitmentPair<C>>,
+    bindings: &HashMap<MpcAddr, C::Scalar>,
+) -> Outcome<C::Point> {
+    let mut group_com = C::Point::default();
     for (id, com) in com_dict {
         let rho_i = bindings.get(id).ifnone_()?;
-        group_com += com.g_d + (com.g_e * rho_i)
+        group_com += com.g_d + (com.g_e * *rho_i)
     }
 
     Ok(group_com)
 }
 
-pub fn gen_rho_i(
+/// Compute the binding factor of every signer.
+/// `pk` is the (possibly derived) public key being signed for. Legacy binding
+/// factors are bound to the session `sid` as well, while RFC 9591 ones are
+/// left as the RFC defines them.
+pub fn gen_rho_dict<C: Ciphersuite>(
+    mode: FrostMode,
+    sid: SessionId,
+    pk: &C::Point,
+    msg: &[u8],
+    nonce_com_dict: &HashMap<MpcAddr, SigningCommitmentPair<C>>,
+) -> HashMap<MpcAddr, C::Scalar> {
+    match mode {
+        FrostMode::Legacy => {
+            let mut rho_dict = HashMap::new();
+            for j in nonce_com_dict.keys() {
+                rho_dict.insert(*j, gen_rho_i(*j, sid, msg, nonce_com_dict));
+            }
+            rho_dict
+        }
+        FrostMode::Rfc9591 => rfc9591::compute_binding_factors(pk, nonce_com_dict, msg),
+    }
+}
+
+pub fn gen_rho_i<C: Ciphersuite>(
     i: MpcAddr,
+    sid: SessionId,
     msg: &[u8],
-    nonce_com_dict: &HashMap<MpcAddr, SigningCommitmentPair>,
-) -> Scalar {
+    nonce_com_dict: &HashMap<MpcAddr, SigningCommitmentPair<C>>,
+) -> C::Scalar {
     let mut hasher = Sha256::new();
     hasher.update("I".as_bytes());
     hasher.update(i.to_be_bytes());
+    hasher.update(sid.as_bytes());
     hasher.update(msg);
 
     use itertools::Itertools;
@@ -111,8 +199,8 @@ pub fn gen_rho_i(
 
     for (j, com) in nonce_com_it {
         hasher.update(j.to_be_bytes());
-        hasher.update(com.g_d.compress().as_bytes());
-        hasher.update(com.g_e.compress().as_bytes());
+        hasher.update(C::serialize_point(&com.g_d));
+        hasher.update(C::serialize_point(&com.g_e));
     }
     let result = hasher.finalize();
 
@@ -120,32 +208,72 @@ pub fn gen_rho_i(
         .as_slice()
         .try_into()
         .expect("Error generating rho_i!");
-    Scalar::from_bytes_mod_order(rho_i)
+    C::scalar_from_le_bytes_mod_order(&rho_i)
 }
 
-pub fn is_valid_response(
-    resp: &Scalar,
-    xig: &EdwardsPoint,
-    λi: &Scalar,
-    commitment: &EdwardsPoint,
-    challenge: &Scalar,
+pub fn is_valid_response<C: Ciphersuite>(
+    resp: &C::Scalar,
+    xig: &C::Point,
+    λi: &C::Scalar,
+    commitment: &C::Point,
+    challenge: &C::Scalar,
 ) -> bool {
-    (&constants::ED25519_BASEPOINT_TABLE * resp) == (commitment + (xig * (challenge * λi)))
+    C::base_mul(resp) == (*commitment + (*xig * (*challenge * *λi)))
 }
 
 /// validate performs a plain Schnorr validation operation; this is identical
 /// to performing validation of a Schnorr signature that has been signed by a
 /// single party.
-pub fn verify_signature(sig: &Signature, pubkey: &EdwardsPoint) -> Outcome<()> {
-    let G = &constants::ED25519_BASEPOINT_TABLE;
-    let challenge = generate_challenge(&sig.hash, &sig.r, &pubkey);
-    let r = G * &sig.s - pubkey * challenge;
+///
+/// For BIP-340 the key is x-only, i.e. either point of its x verifies alike.
+pub fn verify_signature<C: Ciphersuite>(sig: &Signature<C>, pubkey: &C::Point) -> Outcome<()> {
+    C::validate_mode(&sig.mode).catch_()?;
+    assert_throw!(!C::has_odd_y(&sig.r), "Signature is invalid");
+    let pubkey = &match C::has_odd_y(pubkey) {
+        true => -*pubkey,
+        false => *pubkey,
+    };
+    let challenge = generate_challenge::<C>(&sig.mode, &sig.msg, &sig.r, &pubkey);
+    let r = C::base_mul(&sig.s) - *pubkey * challenge;
     assert_throw!(r == sig.r, "Signature is invalid");
     Ok(())
 }
 
+/// Check the signature with ed25519-dalek, according to its mode.
+/// ed25519-dalek does not implement Ed25519ctx, which is therefore rejected here;
+/// use `verify_signature()` for it.
+pub fn verify_dalek(sig: &Signature, pk: &EdwardsPoint) -> Outcome<()> {
+    match &sig.mode {
+        SignMode::Ed25519 => verify_solana(sig, pk).catch_()?,
+        SignMode::Ed25519ph(ctx) => {
+            let (pk, lib_sig) = to_dalek(sig, pk).catch_()?;
+            let mut prehashed = Sha512::new();
+            prehashed.update(&sig.msg);
+            pk.verify_prehashed(prehashed, Some(ctx), &lib_sig)
+                .catch_()?;
+        }
+        SignMode::Ed25519ctx(_) => throw!(
+            "UnsupportedSignMode",
+            "ed25519-dalek cannot verify Ed25519ctx signatures"
+        ),
+    }
+    Ok(())
+}
+
 pub fn verify_solana(sig: &Signature, pk: &EdwardsPoint) -> Outcome<()> {
-    let msg = &sig.hash;
+    assert_throw!(
+        sig.mode == SignMode::Ed25519,
+        "Solana only accepts pure Ed25519 signatures"
+    );
+    let (pk, lib_sig) = to_dalek(sig, pk).catch_()?;
+    pk.verify_strict(&sig.msg, &lib_sig).catch_()?;
+    Ok(())
+}
+
+fn to_dalek(
+    sig: &Signature,
+    pk: &EdwardsPoint,
+) -> Outcome<(ed25519_dalek::PublicKey, ed25519_dalek::Signature)> {
     let pk = {
         let pk_bytes = pk.compress().to_bytes();
         let pk = ed25519_dalek::PublicKey::from_bytes(&pk_bytes).catch_()?;
@@ -159,7 +287,114 @@ pub fn verify_solana(sig: &Signature, pk: &EdwardsPoint) -> Outcome<()> {
         let sig = LibSignature::from_bytes(&sig_bytes).catch_()?;
         sig
     };
+    Ok((pk, sig))
+}
 
-    pk.verify_strict(msg, &sig).catch_()?;
-    Ok(())
+#[cfg(test)]
+mod tests {
+    use curve25519_dalek::edwards::CompressedEdwardsY;
+
+    use super::*;
+
+    /// (public key, message, signature) from RFC 8032, section 7.
+    fn rfc8032_vector(pk: &str, msg: &str, sig: &str, mode: SignMode) -> (EdwardsPoint, Signature) {
+        let pk = CompressedEdwardsY::from_slice(&hex::decode(pk).unwrap())
+            .decompress()
+            .unwrap();
+        let sig_bytes = hex::decode(sig).unwrap();
+        let r = CompressedEdwardsY::from_slice(&sig_bytes[..32])
+            .decompress()
+            .unwrap();
+        let s = Scalar::from_canonical_bytes(sig_bytes[32..].try_into().unwrap()).unwrap();
+        let sig: Signature = Signature {
+            r,
+            s,
+            msg: hex::decode(msg).unwrap(),
+            mode,
+        };
+        (pk, sig)
+    }
+
+    #[test]
+    fn rfc8032_ed25519() {
+        let (pk, sig) = rfc8032_vector(
+            "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a",
+            "",
+            "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b",
+            SignMode::Ed25519,
+        );
+        verify_signature(&sig, &pk).unwrap();
+        verify_dalek(&sig, &pk).unwrap();
+        verify_solana(&sig, &pk).unwrap();
+    }
+
+    #[test]
+    fn rfc8032_ed25519ctx() {
+        let (pk, mut sig) = rfc8032_vector(
+            "dfc9425e4f968f7f0c29f0259cf5f9aed6851c2bb4ad8bfb860cfee0ab248292",
+            "f726936d19c800494e3fdaff20b276a8",
+            "55a4cc2f70a54e04288c5f4cd1e45a7bb520b36292911876cada7323198dd87a8b36950b95130022907a7fb7c4e9b2d5f6cca685a587b4b21f4b888e4e7edb0d",
+            SignMode::Ed25519ctx(b"foo".to_vec()),
+        );
+        verify_signature(&sig, &pk).unwrap();
+        assert!(verify_dalek(&sig, &pk).is_err());
+
+        sig.mode = SignMode::Ed25519ctx(b"bar".to_vec());
+        assert!(verify_signature(&sig, &pk).is_err());
+        sig.mode = SignMode::Ed25519;
+        assert!(verify_signature(&sig, &pk).is_err());
+    }
+
+    #[test]
+    fn rfc8032_ed25519ph() {
+        let (pk, mut sig) = rfc8032_vector(
+            "ec172b93ad5e563bf4932c70e1245034c35467ef2efd4d64ebf819683467e2bf",
+            "616263",
+            "98a70222f0b8121aa9d30f813d683f809e462b469c7ff87639499bb94e6dae4131f85042463c2a355a2003d062adf5aaa10b8c61e636062aaad11c2a26083406",
+            SignMode::Ed25519ph(Vec::new()),
+        );
+        verify_signature(&sig, &pk).unwrap();
+        verify_dalek(&sig, &pk).unwrap();
+        assert!(verify_solana(&sig, &pk).is_err());
+
+        sig.mode = SignMode::Ed25519ctx(Vec::new());
+        assert!(verify_signature(&sig, &pk).is_err());
+        sig.mode = SignMode::Ed25519;
+        assert!(verify_signature(&sig, &pk).is_err());
+    }
+
+    #[test]
+    fn weighted_lambdas_interpolate() {
+        use crate::frost::{deal_weighted_vss_share, Ed25519};
+        use rand::rngs::OsRng;
+
+        let members: HashSet<MpcAddr> = (1..=4).map(|i| MpcAddr::new(1, i)).collect();
+        let weights: HashMap<MpcAddr, u16> = [(MpcAddr::new(1, 1), 3)].into_iter().collect();
+        let secret = Scalar::random(&mut OsRng);
+        let (_, shares) =
+            deal_weighted_vss_share::<Ed25519, _>(&secret, &members, &weights, 4, &mut OsRng)
+                .unwrap();
+        assert_eq!(shares[&MpcAddr::new(1, 1)].len(), 3);
+
+        // The weighted member and any other one reach the threshold of 4.
+        let signers: HashSet<MpcAddr> = [1, 3].into_iter().map(|i| MpcAddr::new(1, i)).collect();
+        let mut s = Scalar::zero();
+        for j in signers.iter() {
+            let lambdas = weighted_lambdas::<Ed25519>(*j, &signers, &weights).unwrap();
+            s += lambdas
+                .iter()
+                .zip(shares[j].iter())
+                .map(|(l, x)| l * x)
+                .sum::<Scalar>();
+        }
+        assert_eq!(s, secret);
+
+        // Without weights, they are the usual Lagrange coefficients.
+        let lambdas =
+            weighted_lambdas::<Ed25519>(MpcAddr::new(1, 3), &signers, &HashMap::new()).unwrap();
+        assert_eq!(
+            lambdas,
+            vec![lagrange_lambda(MpcAddr::new(1, 3), &signers).unwrap()]
+        );
+    }
 }
@@ -5,3 +5,9 @@ mod biz_algo;
 mod frost;
 
 pub use biz_algo::*;
+pub use frost::{
+    bip340_bytes, ed448_bytes, lift_x, sr25519_bytes, taproot_tweak, verify_bip340, verify_ed448,
+    verify_ed448_bytes, verify_schnorrkel, x_only, Blame, Ciphersuite, Ed25519, Ed448, Ed448Mode,
+    Ed448Point, Ed448Scalar, Evidence, Ristretto255, Secp256k1, Secp256k1Point, SigningContext,
+    IDENTIFIABLE_ABORT, SUBSTRATE_CONTEXT,
+};
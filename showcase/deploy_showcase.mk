@@ -1,3 +1,6 @@
+# Parties of one keygen or sign session share its id, fresh on every run.
+SESSION_ID := $(shell od -An -tx1 -N16 /dev/urandom | tr -d ' \n')
+
 proto: 
 	@cargo build --release --manifest-path=showcase/src/sesman/protoc_rust/Cargo.toml
 	@rsync -a showcase/src/sesman/protoc_rust/target/release/protoc_rust showcase/src/sesman/protoc_rust.run
@@ -19,16 +22,16 @@ demo_keygen: build
 	@sleep 1
 	@tmux send-keys -t eddsa:man "cd $(shell pwd)/out && ./demo_sesman_server > log.txt" C-m
 	@sleep 5
-	@tmux send-keys -t eddsa:Li "cd $(shell pwd)/out && ./demo_keygen -n Li " C-m
-	@tmux send-keys -t eddsa:Na "cd $(shell pwd)/out && ./demo_keygen -n Na " C-m
-	@tmux send-keys -t eddsa:K  "cd $(shell pwd)/out && ./demo_keygen -n K  " C-m
-	@tmux send-keys -t eddsa:Rb "cd $(shell pwd)/out && ./demo_keygen -n Rb " C-m
-	@tmux send-keys -t eddsa:Cs "cd $(shell pwd)/out && ./demo_keygen -n Cs " C-m
-	@tmux send-keys -t eddsa:Be "cd $(shell pwd)/out && ./demo_keygen -n Be " C-m
-	@tmux send-keys -t eddsa:Mg "cd $(shell pwd)/out && ./demo_keygen -n Mg " C-m
-	@tmux send-keys -t eddsa:Ca "cd $(shell pwd)/out && ./demo_keygen -n Ca " C-m
-	@tmux send-keys -t eddsa:Sr "cd $(shell pwd)/out && ./demo_keygen -n Sr " C-m
-	@tmux send-keys -t eddsa:Ba "cd $(shell pwd)/out && ./demo_keygen -n Ba " C-m
+	@tmux send-keys -t eddsa:Li "cd $(shell pwd)/out && ./demo_keygen -n Li -s $(SESSION_ID)" C-m
+	@tmux send-keys -t eddsa:Na "cd $(shell pwd)/out && ./demo_keygen -n Na -s $(SESSION_ID)" C-m
+	@tmux send-keys -t eddsa:K  "cd $(shell pwd)/out && ./demo_keygen -n K  -s $(SESSION_ID)" C-m
+	@tmux send-keys -t eddsa:Rb "cd $(shell pwd)/out && ./demo_keygen -n Rb -s $(SESSION_ID)" C-m
+	@tmux send-keys -t eddsa:Cs "cd $(shell pwd)/out && ./demo_keygen -n Cs -s $(SESSION_ID)" C-m
+	@tmux send-keys -t eddsa:Be "cd $(shell pwd)/out && ./demo_keygen -n Be -s $(SESSION_ID)" C-m
+	@tmux send-keys -t eddsa:Mg "cd $(shell pwd)/out && ./demo_keygen -n Mg -s $(SESSION_ID)" C-m
+	@tmux send-keys -t eddsa:Ca "cd $(shell pwd)/out && ./demo_keygen -n Ca -s $(SESSION_ID)" C-m
+	@tmux send-keys -t eddsa:Sr "cd $(shell pwd)/out && ./demo_keygen -n Sr -s $(SESSION_ID)" C-m
+	@tmux send-keys -t eddsa:Ba "cd $(shell pwd)/out && ./demo_keygen -n Ba -s $(SESSION_ID)" C-m
 
 demo_sign: build
 	@tmux new-session -s eddsa   \
@@ -43,10 +46,35 @@ demo_sign: build
 	@sleep 1
 	@tmux send-keys -t eddsa:man "cd $(shell pwd)/out && ./demo_sesman_server" C-m
 	@sleep 1
-	@tmux send-keys -t eddsa:Li "cd $(shell pwd)/out && ./demo_sign -n Li " C-m
-	@tmux send-keys -t eddsa:Na "cd $(shell pwd)/out && ./demo_sign -n Na " C-m
-	@tmux send-keys -t eddsa:K  "cd $(shell pwd)/out && ./demo_sign -n K  " C-m
-	@tmux send-keys -t eddsa:Be "cd $(shell pwd)/out && ./demo_sign -n Be " C-m
-	@tmux send-keys -t eddsa:Mg "cd $(shell pwd)/out && ./demo_sign -n Mg " C-m
-	@tmux send-keys -t eddsa:Ca "cd $(shell pwd)/out && ./demo_sign -n Ca " C-m
-	@tmux send-keys -t eddsa:Ba "cd $(shell pwd)/out && ./demo_sign -n Ba " C-m
+	@tmux send-keys -t eddsa:Li "cd $(shell pwd)/out && ./demo_sign -n Li -s $(SESSION_ID)" C-m
+	@tmux send-keys -t eddsa:Na "cd $(shell pwd)/out && ./demo_sign -n Na -s $(SESSION_ID)" C-m
+	@tmux send-keys -t eddsa:K  "cd $(shell pwd)/out && ./demo_sign -n K  -s $(SESSION_ID)" C-m
+	@tmux send-keys -t eddsa:Be "cd $(shell pwd)/out && ./demo_sign -n Be -s $(SESSION_ID)" C-m
+	@tmux send-keys -t eddsa:Mg "cd $(shell pwd)/out && ./demo_sign -n Mg -s $(SESSION_ID)" C-m
+	@tmux send-keys -t eddsa:Ca "cd $(shell pwd)/out && ./demo_sign -n Ca -s $(SESSION_ID)" C-m
+	@tmux send-keys -t eddsa:Ba "cd $(shell pwd)/out && ./demo_sign -n Ba -s $(SESSION_ID)" C-m
+
+# Every participant approves the export by typing, in its own window,
+# the address printed by demo_keygen.
+demo_export: build
+	@tmux new-session -s eddsa   \
+		-n Li  -d ";" new-window \
+		-n Na  -d ";" new-window \
+		-n K   -d ";" new-window \
+		-n Be  -d ";" new-window \
+		-n Mg  -d ";" new-window \
+		-n Ca  -d ";" new-window \
+		-n Ba  -d ";" new-window \
+		-n Fr  -d ";" new-window \
+		-n man -d ";"
+	@sleep 1
+	@tmux send-keys -t eddsa:man "cd $(shell pwd)/out && ./demo_sesman_server" C-m
+	@sleep 1
+	@tmux send-keys -t eddsa:Li "cd $(shell pwd)/out && ./demo_export -n Li -s $(SESSION_ID) --approve "
+	@tmux send-keys -t eddsa:Na "cd $(shell pwd)/out && ./demo_export -n Na -s $(SESSION_ID) --approve "
+	@tmux send-keys -t eddsa:K  "cd $(shell pwd)/out && ./demo_export -n K  -s $(SESSION_ID) --approve "
+	@tmux send-keys -t eddsa:Be "cd $(shell pwd)/out && ./demo_export -n Be -s $(SESSION_ID) --approve "
+	@tmux send-keys -t eddsa:Mg "cd $(shell pwd)/out && ./demo_export -n Mg -s $(SESSION_ID) --approve "
+	@tmux send-keys -t eddsa:Ca "cd $(shell pwd)/out && ./demo_export -n Ca -s $(SESSION_ID) --approve "
+	@tmux send-keys -t eddsa:Ba "cd $(shell pwd)/out && ./demo_export -n Ba -s $(SESSION_ID) --approve "
+	@tmux send-keys -t eddsa:Fr "cd $(shell pwd)/out && ./demo_export -n Fr -s $(SESSION_ID) --approve "
@@ -0,0 +1,114 @@
+mod sesman;
+use clap::{Arg, ArgAction, Command};
+use sesman::client::ShowcaseSesmanClient;
+
+use std::collections::{HashMap, HashSet};
+
+use curve25519_dalek::edwards::CompressedEdwardsY;
+use libexception::*;
+use mpc_algo::*;
+use mpc_spec::{MpcAddr, SessionId};
+
+/// Name of the party who receives the exported key.
+pub const RECIPIENT: &str = "Fr";
+
+#[tokio::main] // `tokio` re-exported by `mpc_sesman::prelude::*`
+async fn main() -> Outcome<()> {
+    // parse command line arguments
+    let matches = Command::new("demo_export")
+        .arg(
+            Arg::new("member_name")
+                .short('n')
+                .required(true)
+                .action(ArgAction::Set),
+        )
+        .arg(
+            Arg::new("session_id")
+                .short('s')
+                .required(true)
+                .action(ArgAction::Set),
+        )
+        .arg(
+            // Every participant types the address to export, as its approval.
+            Arg::new("approve")
+                .long("approve")
+                .required(true)
+                .action(ArgAction::Set),
+        )
+        .arg(
+            Arg::new("drv_path")
+                .short('p')
+                .default_value("")
+                .action(ArgAction::Set),
+        )
+        .get_matches();
+
+    let member_name = matches.get_one::<String>("member_name").ifnone_()?.clone();
+    let sid = matches.get_one::<String>("session_id").ifnone_()?;
+    let sid = SessionId::from_text(sid).catch_()?;
+    let address = matches.get_one::<String>("approve").ifnone_()?.clone();
+    let drv_path = matches.get_one::<String>("drv_path").ifnone_()?.clone();
+    let ses_arch = showcase_ses_arch();
+
+    let pk_bytes = bs58::decode(&address).into_vec().catch("", &address)?;
+    assert_throw!(pk_bytes.len() == 32, "InvalidAddress", &address);
+    let pk = CompressedEdwardsY::from_slice(&pk_bytes)
+        .decompress()
+        .ifnone("InvalidAddress", &address)?;
+    let approval = ExportApproval {
+        recipient: MpcAddr::new(4, 1),
+        pk,
+        drv_path,
+        hd_scheme: HdScheme::Legacy,
+    };
+
+    let client = ShowcaseSesmanClient {};
+    if member_name == RECIPIENT {
+        let key = algo_export_receive(&client, sid, &ses_arch, &approval)
+            .await
+            .catch_()?;
+
+        use tokio::{
+            fs::{create_dir_all, File},
+            io::AsyncWriteExt,
+        };
+        create_dir_all("keystore").await.catch_()?;
+        let path = format!("keystore/{}.expanded.json", key.address());
+        let mut file = File::create(&path).await.catch_()?;
+        file.write_all(key.to_expanded_key_json().catch_()?.as_bytes())
+            .await
+            .catch_()?;
+        println!("exported: {}", path);
+        return Ok(());
+    }
+
+    // load keystore
+    use tokio::{fs::File, io::AsyncReadExt};
+    let path = &format!("keystore/{}.dat", &member_name);
+    let mut file = File::open(path).await.catch("", &path)?;
+    let mut buf = Vec::new();
+    file.read_to_end(&mut buf).await.catch_()?;
+    let keystore = serde_pickle::from_slice(&buf, Default::default()).catch_()?;
+
+    algo_export_send(&client, sid, &ses_arch, &approval, &keystore)
+        .await
+        .catch_()?;
+
+    Ok(())
+}
+
+fn showcase_ses_arch() -> HashMap<u16, HashSet<MpcAddr>> {
+    let mut res = HashMap::new();
+    let gid_members_list = vec![
+        (1, vec![1, 2, 3, 6, 7, 8, 10]),
+        (2, vec![1, 2, 3]),
+        (3, vec![1, 2, 3, 5]),
+    ];
+
+    for (gid, members) in gid_members_list {
+        let members = members.into_iter().map(|i| MpcAddr::new(gid, i)).collect();
+        res.insert(gid, members);
+    }
+
+    res
+}
@@ -5,7 +5,7 @@ use std::collections::{HashMap, HashSet};
 
 use libexception::*;
 use mpc_algo::*;
-use mpc_spec::MpcAddr;
+use mpc_spec::{FrostMode, MpcAddr, SessionId};
 
 #[tokio::main] // `tokio` re-exported by `mpc_sesman::prelude::*`
 async fn main() -> Outcome<()> {
@@ -17,16 +17,39 @@ async fn main() -> Outcome<()> {
                 .required(true)
                 .action(ArgAction::Set),
         )
+        .arg(
+            Arg::new("session_id")
+                .short('s')
+                .required(true)
+                .action(ArgAction::Set),
+        )
         .get_matches();
 
     let name = matches.get_one::<String>("member_name").ifnone_()?.clone();
     let key_arch = showcase_key_arch();
     let whoami = showcase_name_id(&name).catch_()?;
+    let sid = matches.get_one::<String>("session_id").ifnone_()?;
+    let sid = SessionId::from_text(sid).catch_()?;
 
     let client = ShowcaseSesmanClient {};
-    let keystore = algo_keygen(&client, &key_arch, &whoami, "showcase")
-        .await
-        .catch_()?;
+    let keystore = algo_keygen::<Ed25519>(
+        &client,
+        sid,
+        &key_arch,
+        &whoami,
+        "showcase",
+        FrostMode::Rfc9591,
+    )
+    .await
+    .catch_()?;
+
+    let pk = keystore.pk().catch_()?;
+    println!(
+        "address: {}",
+        bs58::encode(pk.compress().as_bytes()).into_string()
+    );
+    let chain_code = chain_code_of::<Ed25519>(&keystore).catch_()?;
+    println!("xpub: {}", encode_xpub(&pk, &chain_code));
 
     use tokio::{
         fs::{create_dir_all, File},
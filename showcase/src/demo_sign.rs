@@ -1,20 +1,12 @@
 mod sesman;
 use clap::{Arg, ArgAction, Command};
 use sesman::client::ShowcaseSesmanClient;
-use sha2::{Digest, Sha512};
 
 use std::collections::{HashMap, HashSet};
 
 use libexception::*;
 use mpc_algo::*;
-use mpc_spec::MpcAddr;
-
-fn showcase_msg_hash() -> Vec<u8> {
-    let msg = "Je ne veux pas travailler. Je ne veux pas déjeuner. Je veux seulement l'oublier. Et puis je fume.";
-    let mut hasher = Sha512::new();
-    hasher.update(msg.as_bytes());
-    hasher.finalize().to_vec()
-}
+use mpc_spec::{MpcAddr, SessionId};
 
 pub const MSG: &str =
     "Je ne veux pas travailler. Je ne veux pas déjeuner. Je veux seulement l'oublier. Et puis je fume.";
@@ -29,9 +21,17 @@ async fn main() -> Outcome<()> {
                 .required(true)
                 .action(ArgAction::Set),
         )
+        .arg(
+            Arg::new("session_id")
+                .short('s')
+                .required(true)
+                .action(ArgAction::Set),
+        )
         .get_matches();
 
     let member_name = matches.get_one::<String>("member_name").ifnone_()?.clone();
+    let sid = matches.get_one::<String>("session_id").ifnone_()?;
+    let sid = SessionId::from_text(sid).catch_()?;
     let ses_arch = showcase_ses_arch();
 
     // load keystore
@@ -44,11 +44,14 @@ async fn main() -> Outcome<()> {
 
     // sign
     let client = ShowcaseSesmanClient {};
-    let sig = algo_sign(
+    let sig = algo_sign::<Ed25519>(
         &client,
+        sid,
         &ses_arch,
         "m/1/14/514",
-        &showcase_msg_hash(),
+        MSG.as_bytes(),
+        &SignMode::Ed25519,
+        &HdScheme::Legacy,
         &keystore,
     )
     .await
@@ -57,10 +60,10 @@ async fn main() -> Outcome<()> {
     '_print: {
         let sig_r = bs58::encode(&sig.r.compress().as_bytes()).into_string();
         let sig_s = bs58::encode(&sig.s.as_bytes()).into_string();
-        let tx_hash = bs58::encode(&sig.hash).into_string();
+        let msg = String::from_utf8_lossy(&sig.msg);
         println!("sig_r: {}", sig_r);
         println!("sig_s: {}", sig_s);
-        println!("tx_hash: {}", tx_hash);
+        println!("msg: {}", msg);
     }
 
     Ok(())